@@ -0,0 +1,96 @@
+//! Models built from a LoRA adapter on top of another model's weights, for
+//! `omar adapters` and the warnings before deleting a model adapters build on.
+//! An adapter's manifest names the base weights as its model layer, so the base
+//! is whichever installed tag has those weights and no adapter of its own.
+
+use std::collections::HashSet;
+
+use crate::{format_size, read_manifest, table::{self, Align}, InstalledModel};
+
+/// Media type of a LoRA adapter layer.
+const ADAPTER: &str = "application/vnd.ollama.image.adapter";
+
+/// An installed model with adapter layers, and the tags it's built on.
+pub struct Adapter<'a> {
+    pub model: &'a InstalledModel,
+    /// Tags with the same weights and no adapter; empty when the base was deleted
+    /// and only its weights are left, kept for the adapter.
+    pub bases: Vec<&'a InstalledModel>,
+    /// Bytes of the adapter layers themselves.
+    pub size: u64,
+}
+
+/// The bytes of `model`'s adapter layers, or `None` when it has none.
+fn adapter_size(model: &InstalledModel) -> Option<u64> {
+    let manifest = read_manifest(&model.path).ok()?;
+    let adapters: Vec<_> = manifest.layers.iter().filter(|layer| layer.media_type == ADAPTER).collect();
+    (!adapters.is_empty()).then(|| adapters.iter().map(|layer| layer.size).sum())
+}
+
+/// Every installed model built on an adapter, by name.
+pub fn find(installed: &[InstalledModel]) -> Vec<Adapter<'_>> {
+    let sizes: Vec<_> = installed.iter().map(adapter_size).collect();
+    let mut adapters: Vec<_> = installed
+        .iter()
+        .zip(&sizes)
+        .filter_map(|(model, size)| {
+            let bases = installed
+                .iter()
+                .zip(&sizes)
+                .filter(|(base, base_size)| base.digest == model.digest && base_size.is_none())
+                .map(|(base, _)| base)
+                .collect();
+            size.map(|size| Adapter { model, bases, size })
+        })
+        .collect();
+    adapters.sort_by(|a, b| a.model.name.cmp(&b.model.name));
+    adapters
+}
+
+/// Warnings for the adapters deleting `deleting` leaves without a base tag, when
+/// they're being kept. Their weights stay on disk, since the adapter still needs
+/// them, so deleting the base frees none of that space, and the base can't be
+/// pulled again to update it or recreate the adapter elsewhere by name.
+pub fn orphaned(installed: &[InstalledModel], deleting: &[&InstalledModel]) -> Vec<String> {
+    let deleting: HashSet<&str> = deleting.iter().map(|m| m.name.as_str()).collect();
+    find(installed)
+        .into_iter()
+        .filter(|adapter| !deleting.contains(adapter.model.name.as_str()))
+        .filter(|adapter| {
+            !adapter.bases.is_empty() && adapter.bases.iter().all(|base| deleting.contains(base.name.as_str()))
+        })
+        .map(|adapter| {
+            let bases: Vec<_> = adapter.bases.iter().map(|base| base.name.as_str()).collect();
+            format!(
+                "adapter {} is built on {}; its weights stay on disk for the adapter, but no tag will name its base",
+                adapter.model.name,
+                bases.join(", ")
+            )
+        })
+        .collect()
+}
+
+/// `omar adapters`: each adapter with the tags it's built on.
+pub fn run(installed: &[InstalledModel]) -> String {
+    let adapters = find(installed);
+    if adapters.is_empty() {
+        return "\nNo installed models are built on an adapter.\n".to_string();
+    }
+    let rows: Vec<_> = adapters
+        .iter()
+        .map(|adapter| {
+            let base = if adapter.bases.is_empty() {
+                format!("{} (no tag left)", &adapter.model.digest[..12.min(adapter.model.digest.len())])
+            } else {
+                adapter.bases.iter().map(|base| base.name.as_str()).collect::<Vec<_>>().join(", ")
+            };
+            vec![adapter.model.name.clone(), base, format_size(adapter.size), format_size(adapter.model.size)]
+        })
+        .collect();
+    let mut out = String::from("\nModels built on adapters:\n");
+    out.push_str(&table::render(
+        &[("Adapter", Align::Left), ("Base", Align::Left), ("Adapter Size", Align::Right), ("Base Size", Align::Right)],
+        &rows,
+    ));
+    out
+}
@@ -0,0 +1,298 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{self, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, ServerName, UnixTime},
+    DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use std::{collections::HashMap, env, path::PathBuf, sync::Arc, time::Duration};
+
+use crate::{prune::normalize_name, InstalledModel, ModelManifest};
+
+#[derive(Debug, Deserialize)]
+struct RunningModel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PsResponse {
+    models: Vec<RunningModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaggedModel {
+    name: String,
+    digest: String,
+    size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    models: Vec<TaggedModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShowResponse {
+    #[serde(default)]
+    modelfile: String,
+    /// GGUF metadata such as `llama.context_length`.
+    #[serde(default)]
+    model_info: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    message: ChatMessage,
+}
+
+/// Base URL of the local Ollama server, honouring `OLLAMA_HOST` the way the ollama CLI does.
+pub fn ollama_host() -> String {
+    let host = env::var("OLLAMA_HOST").unwrap_or_default();
+    let host = host.trim().trim_end_matches('/');
+    if host.is_empty() {
+        return "http://127.0.0.1:11434".to_string();
+    }
+
+    let (scheme, rest) = match host.split_once("://") {
+        Some((scheme, rest)) => (scheme, rest),
+        None => ("http", host),
+    };
+    let rest = rest.replacen("0.0.0.0", "127.0.0.1", 1);
+    let has_port = rest.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+
+    if has_port {
+        format!("{}://{}", scheme, rest)
+    } else {
+        format!("{}://{}:11434", scheme, rest)
+    }
+}
+
+/// Names of the models the Ollama server currently has loaded.
+///
+/// Returns `Ok(None)` when no server is listening, since nothing can be loaded then.
+pub fn loaded_models() -> Result<Option<Vec<String>>> {
+    let url = format!("{}/api/ps", ollama_host());
+    match ureq::get(&url).timeout(Duration::from_secs(5)).call() {
+        Ok(response) => {
+            let ps: PsResponse = response
+                .into_json()
+                .with_context(|| format!("Failed to parse response from {}", url))?;
+            Ok(Some(ps.models.into_iter().map(|m| m.name).collect()))
+        }
+        Err(ureq::Error::Transport(transport))
+            if transport.kind() == ureq::ErrorKind::ConnectionFailed =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err).with_context(|| format!("Failed to query {}", url)),
+    }
+}
+
+/// Every model the Ollama server has, built from `/api/tags` and `/api/show` alone so
+/// it works when the models directory isn't mounted.
+///
+/// The digest is the model layer's, taken from the `FROM` line of the modelfile, so it
+/// matches what the logs and the history database use. Sizes are the whole model's,
+/// as the API doesn't break them down by layer.
+pub fn installed_models() -> Result<Vec<InstalledModel>> {
+    let host = ollama_host();
+    let url = format!("{}/api/tags", host);
+    let tags: TagsResponse = ureq::get(&url)
+        .timeout(Duration::from_secs(5))
+        .call()
+        .with_context(|| format!("Failed to query {}", url))?
+        .into_json()
+        .with_context(|| format!("Failed to parse response from {}", url))?;
+
+    concurrently(&tags.models, |model| {
+        let digest = model_layer_digest(&host, &model.name)?.unwrap_or_else(|| {
+            model.digest.strip_prefix("sha256:").unwrap_or(&model.digest).to_string()
+        });
+        Ok(InstalledModel {
+            name: normalize_name(&model.name),
+            path: Default::default(),
+            blobs: vec![(digest.clone(), model.size)],
+            digest,
+            size: model.size,
+        })
+    })
+    .into_iter()
+    .collect()
+}
+
+fn show(host: &str, name: &str) -> Result<ShowResponse> {
+    let url = format!("{}/api/show", host);
+    ureq::post(&url)
+        .timeout(Duration::from_secs(15))
+        .send_json(serde_json::json!({ "model": name, "name": name }))
+        .with_context(|| format!("Failed to query {} for {}", url, name))?
+        .into_json()
+        .with_context(|| format!("Failed to parse response from {}", url))
+}
+
+/// The context length `name` was trained with, from the server's copy of its GGUF
+/// metadata.
+pub(crate) fn context_length(name: &str) -> Result<Option<u64>> {
+    Ok(show(&ollama_host(), name)?
+        .model_info
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64()))
+}
+
+/// Asks `model` on the local server for its reply to `prompt`, given the `system`
+/// prompt, and waits for all of it. Loading a large model and generating can take
+/// minutes on a CPU, so the timeout is generous.
+pub(crate) fn chat(model: &str, system: &str, prompt: &str) -> Result<String> {
+    let host = ollama_host();
+    let url = format!("{}/api/chat", host);
+    let request = serde_json::json!({
+        "model": model,
+        "stream": false,
+        "messages": [
+            { "role": "system", "content": system },
+            { "role": "user", "content": prompt },
+        ],
+    });
+    match ureq::post(&url).timeout(Duration::from_secs(600)).send_json(request) {
+        Ok(response) => {
+            let chat: ChatResponse = response
+                .into_json()
+                .with_context(|| format!("Failed to parse response from {}", url))?;
+            Ok(chat.message.content)
+        }
+        Err(ureq::Error::Status(404, _)) => Err(anyhow!("{} isn't installed; pull it with `ollama pull {}`", model, model)),
+        Err(ureq::Error::Transport(transport)) if transport.kind() == ureq::ErrorKind::ConnectionFailed => {
+            Err(anyhow!("No Ollama server is listening at {}; start it with `ollama serve`", host))
+        }
+        Err(err) => Err(err).with_context(|| format!("Failed to query {}", url)),
+    }
+}
+
+/// The digest of the blob `name`'s modelfile is built `FROM`, if the server says.
+fn model_layer_digest(host: &str, name: &str) -> Result<Option<String>> {
+    Ok(show(host, name)?
+        .modelfile
+        .lines()
+        .filter_map(|line| line.strip_prefix("FROM "))
+        .find_map(|from| from.trim().rsplit_once("sha256-").map(|(_, digest)| digest.to_string())))
+}
+
+/// How to trust the registries models were pulled from, for private ones behind a
+/// certificate the usual roots don't vouch for.
+#[derive(Debug, Default, Clone)]
+pub struct RegistryTls {
+    /// Accept any certificate, as `ollama pull --insecure` does.
+    pub insecure: bool,
+    /// PEM file of extra certificate authorities to trust, besides the usual roots.
+    pub ca_cert: Option<PathBuf>,
+}
+
+/// Accepts every certificate, for `--insecure`. Signatures are still checked, so
+/// the connection is encrypted, just not to a server anyone vouched for.
+#[derive(Debug)]
+struct AnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+impl RegistryTls {
+    /// An agent for registry requests trusting what `self` says. Without either
+    /// option it's ureq's own, trusting the usual roots.
+    pub fn agent(&self) -> Result<ureq::Agent> {
+        if !self.insecure && self.ca_cert.is_none() {
+            return Ok(ureq::agent());
+        }
+        // ring, as ureq uses, named outright in case another dependency enables a second provider
+        let provider = Arc::new(crypto::ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .context("Failed to set up TLS")?;
+        let config = if self.insecure {
+            builder.dangerous().with_custom_certificate_verifier(Arc::new(AnyCertificate(provider))).with_no_client_auth()
+        } else {
+            let mut roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+            if let Some(path) = &self.ca_cert {
+                let certs = CertificateDer::pem_file_iter(path)
+                    .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                    .map_err(|err| anyhow!("Failed to read certificates from {}: {}", path.display(), err))?;
+                if certs.is_empty() {
+                    bail!("{} has no PEM certificates in it", path.display());
+                }
+                for cert in certs {
+                    roots.add(cert).with_context(|| format!("Failed to trust a certificate from {}", path.display()))?;
+                }
+            }
+            builder.with_root_certificates(roots).with_no_client_auth()
+        };
+        Ok(ureq::AgentBuilder::new().tls_config(Arc::new(config)).build())
+    }
+}
+
+/// Fetches the current manifest for `repository:tag` from an OCI registry such as
+/// `registry.ollama.ai`, or a private one reached through `agent`.
+pub fn registry_manifest(agent: &ureq::Agent, registry: &str, repository: &str, tag: &str) -> Result<ModelManifest> {
+    let url = format!("https://{}/v2/{}/manifests/{}", registry, repository, tag);
+    agent
+        .get(&url)
+        .set("Accept", "application/vnd.docker.distribution.manifest.v2+json")
+        .timeout(Duration::from_secs(15))
+        .call()
+        .with_context(|| format!("Failed to fetch {}", url))?
+        .into_json()
+        .with_context(|| format!("Failed to parse manifest from {}", url))
+}
+
+/// Requests [`concurrently`] has in flight at most.
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Runs `f` on every item, up to [`MAX_CONCURRENT_REQUESTS`] at a time, and returns
+/// the results in order. Network calls go through this so that a slow host or
+/// registry only delays the report by its own timeout rather than adding to
+/// everyone else's, without opening a connection per tag on a host with hundreds.
+pub(crate) fn concurrently<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    crate::parallel_map_on(items, MAX_CONCURRENT_REQUESTS, f)
+}
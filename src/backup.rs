@@ -0,0 +1,172 @@
+use anyhow::{bail, Context, Result};
+use glob::glob;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{parse_manifest_path, paths, prune::AnyManifest, strip_digest};
+
+/// Every file under `root`, paired with its path relative to `root`. Directories
+/// that can't be listed are skipped with a message.
+fn manifest_files(root: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut files = Vec::new();
+
+    for entry in glob(&paths::glob_pattern(root, "**/*")).context("Failed to read glob pattern")? {
+        let path = match entry {
+            Ok(path) => path,
+            Err(err) => {
+                println!("Skipping {}: {}", err.path().display(), err.error());
+                continue;
+            }
+        };
+        if path.is_file() {
+            let relative = path.strip_prefix(root)?.to_path_buf();
+            files.push((path, relative));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Copies the manifests tree into `dest`, mirroring its registry/user/model/tag layout.
+pub fn backup(model_dir: &Path, dest: &Path) -> Result<()> {
+    let manifest_dir = model_dir.join("manifests");
+    if !manifest_dir.is_dir() {
+        bail!("No manifests directory found at {}", manifest_dir.display());
+    }
+
+    let files = manifest_files(&manifest_dir)?;
+    let mut copied = 0;
+    for (path, relative) in &files {
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        // A manifest we can't read is skipped; one we can't write means the
+        // backup itself is broken
+        let content = match fs::read(path) {
+            Ok(content) => content,
+            Err(err) => {
+                println!("Skipping {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        fs::write(&target, content)
+            .with_context(|| format!("Failed to copy {} to {}", path.display(), target.display()))?;
+        copied += 1;
+    }
+
+    println!("Backed up {} manifest(s) to {}", copied, dest.display());
+    Ok(())
+}
+
+/// Copies manifests from a backup into the models directory.
+///
+/// A manifest is only restored when every blob it references is still present,
+/// so a restore can never produce a model that fails to load. Existing manifests
+/// are left alone unless `overwrite` is set.
+pub fn restore(model_dir: &Path, src: &Path, overwrite: bool) -> Result<()> {
+    if !src.is_dir() {
+        bail!("Backup directory {} does not exist", src.display());
+    }
+
+    let manifest_dir = model_dir.join("manifests");
+    let blob_dir = model_dir.join("blobs");
+    let (mut restored, mut unchanged, mut broken) = (0, 0, 0);
+
+    for (path, relative) in manifest_files(src)? {
+        let name = parse_manifest_path(&path).unwrap_or_else(|| relative.display().to_string());
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                println!("Skipping {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let manifest: AnyManifest = match serde_json::from_str(&content) {
+            Ok(manifest) => manifest,
+            Err(_) => {
+                println!("Skipping {}: not a valid manifest", path.display());
+                continue;
+            }
+        };
+
+        let target = manifest_dir.join(&relative);
+        if target.exists() {
+            let same = fs::read_to_string(&target).map(|c| c == content).unwrap_or(false);
+            if same || !overwrite {
+                unchanged += 1;
+                continue;
+            }
+        }
+
+        let missing = manifest
+            .config
+            .iter()
+            .chain(manifest.layers.iter())
+            .filter(|l| !blob_dir.join(format!("sha256-{}", strip_digest(&l.digest))).exists())
+            .count();
+        if missing > 0 {
+            println!("Skipping {}: {} blob(s) no longer on disk", name, missing);
+            broken += 1;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&target, &content)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        println!("Restored {}", name);
+        restored += 1;
+    }
+
+    println!(
+        "\nRestored {} manifest(s), {} already present, {} skipped for missing blobs.",
+        restored, unchanged, broken
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prune;
+
+    /// Only manifests whose blobs are all still there come back, and one already in
+    /// place is left alone without `overwrite`.
+    #[test]
+    fn restore_brings_back_manifests_with_every_blob() {
+        let (dir, installed) = prune::test_models_dir(
+            "backup",
+            &[
+                ("llama3:latest", &["config-a", "weights-a"]),
+                ("qwen2:7b", &["config-b", "weights-b"]),
+                ("phi3:mini", &["config-c", "weights-c"]),
+            ],
+        );
+        let saved = dir.join("saved");
+        backup(&dir, &saved).unwrap();
+
+        // llama3 goes with its blobs, qwen2 only loses its manifest, and phi3 is edited
+        prune::delete_models(&dir, &[&installed[0]]).unwrap();
+        fs::remove_file(&installed[1].path).unwrap();
+        fs::write(&installed[2].path, "{}").unwrap();
+
+        restore(&dir, &saved, false).unwrap();
+
+        assert!(!installed[0].path.exists());
+        assert_eq!(
+            fs::read(&installed[1].path).unwrap(),
+            fs::read(saved.join(installed[1].path.strip_prefix(dir.join("manifests")).unwrap())).unwrap()
+        );
+        assert_eq!(fs::read_to_string(&installed[2].path).unwrap(), "{}");
+
+        restore(&dir, &saved, true).unwrap();
+        assert_ne!(fs::read_to_string(&installed[2].path).unwrap(), "{}");
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,114 @@
+//! Remembers the manifests already read, by path, along with the modification
+//! time and size they had, so that a models directory on a slow network share
+//! only has the manifests that changed since the last run read again.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+use tracing::debug;
+
+use crate::{read_manifest, ModelManifest, Result};
+
+/// Bumped whenever the cached layout changes, so an older cache is thrown away.
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    modified: SystemTime,
+    len: u64,
+    manifest: ModelManifest,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Contents {
+    version: u32,
+    entries: HashMap<PathBuf, Entry>,
+}
+
+/// The manifest cache file, loaded for one scan.
+pub(crate) struct ManifestCache {
+    path: Option<PathBuf>,
+    contents: Contents,
+    seen: HashSet<PathBuf>,
+    changed: bool,
+}
+
+impl ManifestCache {
+    /// Loads the cache at `path`, starting an empty one when it's missing, can't
+    /// be read or was written by another version. With no path nothing is cached.
+    pub(crate) fn open(path: Option<PathBuf>) -> ManifestCache {
+        let contents = path
+            .as_deref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice::<Contents>(&bytes).ok())
+            .filter(|contents| contents.version == VERSION)
+            .unwrap_or(Contents { version: VERSION, entries: HashMap::new() });
+        debug!(path = ?path, entries = contents.entries.len(), "loaded the manifest cache");
+        ManifestCache { path, contents, seen: HashSet::new(), changed: false }
+    }
+
+    /// The manifest at `path`, from the cache when its modification time and size
+    /// are the ones in `metadata`, and read from disk otherwise.
+    pub(crate) fn read(&self, path: &Path, metadata: &fs::Metadata) -> Result<ModelManifest> {
+        let cached = self.contents.entries.get(path).filter(|entry| self.unchanged(entry, metadata));
+        match cached {
+            Some(entry) => Ok(entry.manifest.clone()),
+            None => read_manifest(path),
+        }
+    }
+
+    /// Remembers that `path` held `manifest` when it had `metadata`.
+    pub(crate) fn insert(&mut self, path: &Path, metadata: &fs::Metadata, manifest: &ModelManifest) {
+        let Some(modified) = self.path.as_ref().and_then(|_| metadata.modified().ok()) else {
+            return;
+        };
+        self.seen.insert(path.to_path_buf());
+        if self.contents.entries.get(path).is_some_and(|entry| self.unchanged(entry, metadata)) {
+            return;
+        }
+        self.contents
+            .entries
+            .insert(path.to_path_buf(), Entry { modified, len: metadata.len(), manifest: manifest.clone() });
+        self.changed = true;
+    }
+
+    fn unchanged(&self, entry: &Entry, metadata: &fs::Metadata) -> bool {
+        self.path.is_some() && metadata.modified().is_ok_and(|modified| modified == entry.modified) && metadata.len() == entry.len
+    }
+
+    /// Forgets the manifests under `dir` that this scan didn't find, then writes
+    /// the cache back if anything changed. A cache that can't be written is only
+    /// logged, since the next run just reads the manifests again.
+    pub(crate) fn save(mut self, dir: &Path) {
+        let Some(path) = self.path.take() else { return };
+        let before = self.contents.entries.len();
+        self.contents.entries.retain(|entry, _| !entry.starts_with(dir) || self.seen.contains(entry));
+        if !self.changed && self.contents.entries.len() == before {
+            return;
+        }
+        // Written beside the cache and renamed over it, so an interrupted run or
+        // a concurrent one never leaves half a file behind. Named by pid and a
+        // counter, since two scans in one process can save at the same time.
+        static SAVES: AtomicUsize = AtomicUsize::new(0);
+        let save = SAVES.fetch_add(1, Ordering::Relaxed);
+        let temp = path.with_extension(format!("{}.{}.tmp", std::process::id(), save));
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| serde_json::to_vec(&self.contents).map_err(std::io::Error::other))
+            .and_then(|bytes| fs::write(&temp, bytes))
+            .and_then(|()| fs::rename(&temp, &path));
+        match written {
+            Ok(()) => debug!(path = %path.display(), entries = self.contents.entries.len(), "saved the manifest cache"),
+            Err(err) => {
+                let _ = fs::remove_file(&temp);
+                debug!(path = %path.display(), %err, "couldn't save the manifest cache");
+            }
+        }
+    }
+}
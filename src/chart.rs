@@ -0,0 +1,118 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use ratatui::crossterm::terminal;
+use std::collections::HashMap;
+
+use crate::{format_size, ModelUsage, Status};
+
+/// What `chart` draws a bar for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Chart {
+    /// Disk space taken by each model
+    Sizes,
+    /// Loads of each model, within the --window when one is given
+    Usage,
+}
+
+/// Bars narrower than this aren't worth drawing, however narrow the terminal.
+const MIN_BAR_WIDTH: usize = 10;
+
+/// A bar `value / max` of `width` cells long, in eighths of a cell.
+fn bar(value: f64, max: f64, width: usize) -> String {
+    const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    let eighths = if max > 0.0 { (value / max * (width * 8) as f64).round() as usize } else { 0 };
+    let mut bar = "█".repeat(eighths / 8);
+    if eighths % 8 > 0 {
+        bar.push(PARTIAL[eighths % 8]);
+    }
+    bar
+}
+
+/// One line per `(label, value, shown value)`, in the order given, with bars scaled
+/// so the largest value fills the terminal.
+fn render(items: &[(String, f64, String)]) -> String {
+    let label_width = items.iter().map(|(label, _, _)| label.chars().count()).max().unwrap_or(0);
+    let value_width = items.iter().map(|(_, _, shown)| shown.chars().count()).max().unwrap_or(0);
+    let columns = terminal::size().map_or(80, |(columns, _)| columns as usize);
+    let width = columns.saturating_sub(label_width + value_width + 4).max(MIN_BAR_WIDTH);
+    let max = items.iter().map(|(_, value, _)| *value).fold(0.0, f64::max);
+
+    let mut out = String::new();
+    for (label, value, shown) in items {
+        let line = format!("{:<label_width$}  {:>value_width$}  {}", label, shown, bar(*value, max, width));
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn sizes(hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Vec<(String, f64, String)> {
+    let mut models: Vec<_> = hash_to_name_size.values().collect();
+    models.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    models
+        .into_iter()
+        .map(|(tags, size)| (tags.join(", "), *size as f64, format_size(*size)))
+        .collect()
+}
+
+/// Loads per model, or loads within the window when there is one. Installed models
+/// that were never loaded get an empty bar, so the dead weight shows.
+fn usage(
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+    model_usage: &HashMap<String, ModelUsage>,
+    windowed: bool,
+) -> Vec<(String, f64, String)> {
+    let mut counts: Vec<(String, usize)> = model_usage
+        .values()
+        .map(|m| {
+            let name = match m.status {
+                Status::Deleted => format!("{} (deleted)", m.name),
+                _ => m.name.clone(),
+            };
+            let count = if windowed { m.window_count.unwrap_or(0) } else { m.usage_count };
+            (name, count)
+        })
+        .chain(
+            hash_to_name_size
+                .iter()
+                .filter(|(digest, _)| !model_usage.contains_key(*digest))
+                .map(|(_, (tags, _))| (tags.join(", "), 0)),
+        )
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+        .into_iter()
+        .map(|(name, count)| (name, count as f64, count.to_string()))
+        .collect()
+}
+
+/// Prints a horizontal bar chart, largest first. `window` is the label of the
+/// `--window` the usage was counted within, if any.
+pub fn run(
+    chart: Chart,
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+    model_usage: &HashMap<String, ModelUsage>,
+    window: Option<&str>,
+) -> Result<()> {
+    let (title, items) = match chart {
+        Chart::Sizes => (
+            "Disk space by model (tags sharing weights are counted once):".to_string(),
+            sizes(hash_to_name_size),
+        ),
+        Chart::Usage => (
+            match window {
+                Some(window) => format!("Loads by model in the last {}:", window),
+                None => "Loads by model:".to_string(),
+            },
+            usage(hash_to_name_size, model_usage, window.is_some()),
+        ),
+    };
+    if items.is_empty() {
+        println!("\nNo models to chart.\n");
+        return Ok(());
+    }
+    println!("\n{}\n", title);
+    print!("{}", render(&items));
+    println!();
+    Ok(())
+}
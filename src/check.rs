@@ -0,0 +1,122 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+use crate::{format_size, notify, total_size, InstalledModel, ModelUsage};
+
+/// Exit statuses monitoring plugins report with.
+pub const OK: i32 = 0;
+pub const WARNING: i32 = 1;
+pub const CRITICAL: i32 = 2;
+pub const UNKNOWN: i32 = 3;
+
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// The name of the service in check_mk.
+const CHECKMK_SERVICE: &str = "Ollama_models";
+
+/// How `check` reports its result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CheckFormat {
+    /// Nagios/Icinga plugin output, with the state as the exit status
+    Nagios,
+    /// A check_mk local check line, for the agent's local/ directory
+    Checkmk,
+    /// One line of key=value pairs, e.g. for a Zabbix UserParameter
+    Keyvalue,
+}
+
+/// Thresholds on the space taken by models unused for `stale_days`, in GB.
+pub struct Thresholds {
+    pub stale_days: i64,
+    pub warn_unused_gb: Option<f64>,
+    pub crit_unused_gb: Option<f64>,
+}
+
+/// The line to print when the check couldn't run.
+pub fn unknown(format: CheckFormat, err: &anyhow::Error) -> String {
+    match format {
+        CheckFormat::Nagios => format!("OMAR UNKNOWN - {:#}", err),
+        CheckFormat::Checkmk => format!("{} {} - {:#}", UNKNOWN, CHECKMK_SERVICE, err),
+        CheckFormat::Keyvalue => {
+            format!("status=unknown error=\"{}\"", format!("{:#}", err).replace(['"', '\n'], " "))
+        }
+    }
+}
+
+/// Prints one line in `format` and returns the exit status to go with it. Only
+/// Nagios output carries the state in the exit status; the agents read it from
+/// the line and expect omar itself to succeed.
+pub fn run(
+    installed: &[InstalledModel],
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+    model_usage: &HashMap<String, ModelUsage>,
+    thresholds: &Thresholds,
+    format: CheckFormat,
+) -> Result<i32> {
+    let warn = thresholds.warn_unused_gb.map(|gb| (gb * GB) as u64);
+    let crit = thresholds.crit_unused_gb.map(|gb| (gb * GB) as u64);
+    if let (Some(warn), Some(crit)) = (warn, crit) {
+        if crit < warn {
+            bail!("--crit-unused-gb is below --warn-unused-gb");
+        }
+    }
+
+    let stale = notify::stale_models(installed, hash_to_name_size, model_usage, thresholds.stale_days);
+    let unused = stale.unused_bytes;
+    let total = total_size(installed);
+    let models = hash_to_name_size.len();
+    let (status, label, threshold) = match (warn, crit) {
+        (_, Some(crit)) if unused >= crit => (CRITICAL, "CRITICAL", Some(("critical", crit))),
+        (Some(warn), _) if unused >= warn => (WARNING, "WARNING", Some(("warning", warn))),
+        _ => (OK, "OK", None),
+    };
+
+    let mut summary = format!(
+        "{} in {} model(s) unused for {}+ days",
+        format_size(unused),
+        stale.models.len(),
+        thresholds.stale_days
+    );
+    if let Some((level, bytes)) = threshold {
+        summary.push_str(&format!(" ({} at {})", level, format_size(bytes)));
+    }
+    let limit = |bytes: Option<u64>| bytes.map_or(String::new(), |b| b.to_string());
+
+    let line = match format {
+        CheckFormat::Nagios => format!(
+            "OMAR {} - {} | unused={}B;{};{};0; total={}B;;;0; stale_models={};;;0; models={};;;0;",
+            label,
+            summary,
+            unused,
+            limit(warn),
+            limit(crit),
+            total,
+            stale.models.len(),
+            models
+        ),
+        CheckFormat::Checkmk => format!(
+            "{} {} unused={};{};{};0|total={};;;0|stale_models={}|models={} {}",
+            status,
+            CHECKMK_SERVICE,
+            unused,
+            limit(warn),
+            limit(crit),
+            total,
+            stale.models.len(),
+            models,
+            summary
+        ),
+        CheckFormat::Keyvalue => format!(
+            "status={} total_bytes={} unused_bytes={} stale_models={} models={} stale_days={}",
+            label.to_lowercase(),
+            total,
+            unused,
+            stale.models.len(),
+            models,
+            thresholds.stale_days
+        ),
+    };
+    println!("{}", line);
+    Ok(if format == CheckFormat::Nagios { status } else { OK })
+}
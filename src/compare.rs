@@ -0,0 +1,122 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+use crate::{
+    api, format_size, prune::normalize_name,
+    table::{self, Align},
+    InstalledModel, LogCache, ModelConfig, ModelUsage,
+};
+
+/// One model's column of the comparison, one cell per row in [`FIELDS`].
+fn column(model: &InstalledModel, usage: Option<&ModelUsage>, cache: &LogCache) -> Vec<String> {
+    let unknown = || "unknown".to_string();
+    let config = ModelConfig::read(model);
+    let (family, parameters, quantization) = match config {
+        Some(c) => (
+            c.model_family.unwrap_or_else(unknown),
+            c.model_type.unwrap_or_else(unknown),
+            c.file_type.unwrap_or_else(unknown),
+        ),
+        None => (unknown(), unknown(), unknown()),
+    };
+    // Only the server reads the GGUF metadata the context length is in
+    let context = match api::context_length(&model.name) {
+        Ok(Some(length)) => length.to_string(),
+        _ => "n/a".to_string(),
+    };
+
+    let stats = cache.runner_stats().remove(&model.digest).unwrap_or_default();
+    let load_time = if stats.timed_loads == 0 {
+        "n/a".to_string()
+    } else {
+        format!("{:.1}s", stats.load_seconds / stats.timed_loads as f64)
+    };
+    let speed = if stats.timed_evals == 0 {
+        "n/a".to_string()
+    } else {
+        format!("{:.1}", stats.tokens_per_second / stats.timed_evals as f64)
+    };
+
+    vec![
+        format_size(model.size),
+        family,
+        parameters,
+        quantization,
+        context,
+        usage.map_or("never".to_string(), |u| u.last_used.format("%Y-%m-%d").to_string()),
+        usage.map_or(0, |u| u.usage_count).to_string(),
+        stats.embeddings.to_string(),
+        stats.tool_requests.to_string(),
+        stats.structured_requests.to_string(),
+        stats.clients.len().to_string(),
+        if stats.oom_failures == 0 {
+            stats.failures.to_string()
+        } else {
+            format!("{} ({} out of memory)", stats.failures, stats.oom_failures)
+        },
+        usage.and_then(|u| u.recorded_count).map_or("n/a".to_string(), |c| c.to_string()),
+        usage.map_or(0, |u| u.days_used).to_string(),
+        usage.map_or("-".to_string(), |u| u.trend().as_str().to_string()),
+        load_time,
+        speed,
+        stats.errors.to_string(),
+    ]
+}
+
+const FIELDS: [&str; 18] = [
+    "Size",
+    "Family",
+    "Parameters",
+    "Quantization",
+    "Context length",
+    "Last used",
+    "Loads in logs",
+    "Embedding requests",
+    "Tool requests",
+    "JSON requests",
+    "Clients",
+    "Load failures",
+    "All-time loads",
+    "Days used",
+    "Trend",
+    "Avg load time",
+    "Tokens/sec",
+    "Errors logged",
+];
+
+/// Prints two installed models side by side: what they are, how much they're used,
+/// and how they run.
+pub fn run(
+    installed: &[InstalledModel],
+    model_usage: &HashMap<String, ModelUsage>,
+    cache: &LogCache,
+    a: &str,
+    b: &str,
+) -> Result<()> {
+    let find = |name: &str| {
+        let name = normalize_name(name);
+        match installed.iter().find(|m| m.name == name) {
+            Some(model) => Ok(model),
+            None => bail!("{} is not installed", name),
+        }
+    };
+    let (a, b) = (find(a)?, find(b)?);
+    if a.digest == b.digest {
+        println!("\n{} and {} share the same weights, so they only differ in name.", a.name, b.name);
+    }
+
+    let (left, right) = (
+        column(a, model_usage.get(&a.digest), cache),
+        column(b, model_usage.get(&b.digest), cache),
+    );
+    let rows: Vec<_> = FIELDS
+        .iter()
+        .zip(left.into_iter().zip(right))
+        .map(|(field, (l, r))| vec![field.to_string(), l, r])
+        .collect();
+
+    println!();
+    table::print(&[("", Align::Left), (a.name.as_str(), Align::Right), (b.name.as_str(), Align::Right)], &rows);
+    println!();
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+use crate::{report::SortOrder, source::SourceKind, Attribution};
+
+/// Settings read from `config.toml`. Every section is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defaults: Defaults,
+    pub notify: NotifyConfig,
+    pub sources: SourcesConfig,
+    pub plugins: Vec<PluginConfig>,
+}
+
+/// Defaults for command-line flags and environment variables. Anything given on the
+/// command line or in the environment wins.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    /// Models directory, used when `OLLAMA_MODELS` isn't set. See
+    /// [`Paths::apply_defaults`](crate::Paths::apply_defaults).
+    pub model_dir: Option<PathBuf>,
+    /// Directory holding the `server*.log` files, used when `OMAR_LOG_DIR` isn't set.
+    pub log_dir: Option<PathBuf>,
+    /// Ollama server address, used when `OLLAMA_HOST` isn't set.
+    pub host: Option<String>,
+    /// Report output format, as given to `--format`.
+    pub format: Option<String>,
+    pub sort: Option<SortOrder>,
+    /// Rolling window for the extra usage column, as given to `--window`, e.g. "30d".
+    pub window: Option<String>,
+    /// Days after being pulled that a never-used model is flagged, as given to
+    /// `--never-used-days`.
+    pub never_used_days: Option<i64>,
+    /// Which tag gets the usage of weights several share, as given to `--attribute`.
+    pub attribution: Option<Attribution>,
+    /// Include LM Studio's models in the report, as with `--lmstudio`.
+    pub lmstudio: bool,
+    /// LM Studio's models directory, as given to `--lmstudio-dir`.
+    pub lmstudio_dir: Option<PathBuf>,
+    /// Include the Hugging Face hub cache in the report, as with `--huggingface`.
+    pub huggingface: bool,
+    /// The Hugging Face hub cache, as given to `--huggingface-dir`.
+    pub huggingface_dir: Option<PathBuf>,
+    /// Include the Ollama store on the other side of WSL in the report, as with `--wsl`.
+    pub wsl: bool,
+    /// Space `recommend` frees when `--free` isn't given, e.g. "50GB".
+    pub free: Option<String>,
+    /// Tags `recommend` never suggests and `dedupe` always keeps.
+    pub keep: Vec<String>,
+}
+
+impl Defaults {
+    /// Points the API client at the configured server, unless `OLLAMA_HOST`
+    /// already does.
+    pub fn apply_to_env(&self) {
+        if let Some(host) = self.host.as_ref().filter(|_| env::var_os("OLLAMA_HOST").is_none()) {
+            env::set_var("OLLAMA_HOST", host);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourcesConfig {
+    /// Where model loads are read from, merged in this order.
+    pub usage: Vec<SourceKind>,
+    /// Open WebUI's `webui.db`, for the `openwebui` source.
+    pub openwebui_db: Option<PathBuf>,
+    /// Glob patterns matching the JSON-lines logs of the `proxy` source.
+    pub proxy_logs: Vec<String>,
+    /// Names clients ask the proxy for, mapped to the Ollama tags that serve them.
+    pub proxy_aliases: HashMap<String, String>,
+}
+
+impl Default for SourcesConfig {
+    fn default() -> Self {
+        SourcesConfig {
+            // Linux installs log to the journal rather than a file
+            usage: if cfg!(target_os = "linux") { vec![SourceKind::Logs, SourceKind::Journald] } else { vec![SourceKind::Logs] },
+            openwebui_db: None,
+            proxy_logs: Vec::new(),
+            proxy_aliases: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Models not loaded for this many days count as unused.
+    pub stale_days: i64,
+    /// Only notify once unused models take up at least this much space.
+    pub min_unused_gb: f64,
+    pub webhooks: Vec<Webhook>,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            stale_days: 30,
+            min_unused_gb: 0.0,
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub kind: WebhookKind,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    Slack,
+    Discord,
+    #[default]
+    Generic,
+}
+
+/// An external executable that reports usage events or models as JSON on stdout.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub kind: PluginKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginKind {
+    /// Reports model loads
+    Usage,
+    /// Reports models installed somewhere omar can't scan
+    Inventory,
+}
+
+/// `$XDG_CONFIG_HOME/omar/config.toml`, falling back to `~/.config/omar/config.toml`
+/// on every platform so the location is the same everywhere.
+pub fn default_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+        .map(|dir| dir.join("omar").join("config.toml"))
+}
+
+/// Loads the config file at `path`, which has to exist, or else the one at
+/// `fallback` if there is one, or else the defaults.
+pub fn load(path: Option<&Path>, fallback: Option<&Path>) -> Result<Config> {
+    let path = match path.or(fallback.filter(|p| p.is_file())) {
+        Some(path) => path,
+        None => return Ok(Config::default()),
+    };
+    debug!(path = %path.display(), "reading config");
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use chrono::Local;
+use rusqlite::Connection;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use crate::{api, scan_models, get_log_paths, history, index_by_digest, prune, LogScanner};
+
+/// Reads whatever has been appended to `path` since the last pass and records each
+/// model load. A file that got shorter was truncated or rotated, so it's read again
+/// from the start; events already recorded are ignored by the database.
+fn tail_log(
+    conn: &Connection,
+    path: &Path,
+    scanner: &mut LogScanner,
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+) -> Result<usize> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut offset = history::log_offset(conn, path)?;
+    if len < offset {
+        *scanner = LogScanner::default();
+        offset = 0;
+    }
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut recorded = 0;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        // Leave a partially written last line for the next pass.
+        if read == 0 || !line.ends_with(b"\n") {
+            break;
+        }
+        offset += read as u64;
+
+        let text = String::from_utf8_lossy(&line);
+        if let Some(hash) = scanner.scan_line(text.trim_end()) {
+            let name = hash_to_name_size.get(&hash).map(|(tags, _)| tags.join(", "));
+            let name = name.as_deref();
+            let loaded_at = scanner.last_timestamp.unwrap_or_else(Local::now);
+            if history::record_event(conn, &hash, name, loaded_at, "log")? {
+                println!("{}  {}", loaded_at.format("%Y-%m-%d %H:%M:%S"), name.unwrap_or(&hash));
+                recorded += 1;
+            }
+        }
+    }
+
+    history::set_log_offset(conn, path, offset)?;
+    Ok(recorded)
+}
+
+/// Records a load for every model that appeared in `/api/ps` since the last poll.
+fn poll_loaded(
+    conn: &Connection,
+    previous: &mut HashSet<String>,
+    digests: &HashMap<String, String>,
+) -> Result<()> {
+    let loaded: HashSet<String> = api::loaded_models()?
+        .unwrap_or_default()
+        .iter()
+        .map(|n| prune::normalize_name(n))
+        .collect();
+
+    for name in loaded.difference(previous) {
+        if let Some(digest) = digests.get(name) {
+            let now = Local::now();
+            if history::record_event(conn, digest, Some(name), now, "api")? {
+                println!("{}  {} (loaded)", now.format("%Y-%m-%d %H:%M:%S"), name);
+            }
+        }
+    }
+
+    *previous = loaded;
+    Ok(())
+}
+
+/// Runs until killed, recording usage events from the logs and the running server.
+///
+/// Log and API events are stored under separate sources, since a single load
+/// usually shows up in both.
+pub fn run(db: &Path, interval: Duration) -> Result<()> {
+    let conn = history::open(db)?;
+    let mut scanners: HashMap<PathBuf, LogScanner> = HashMap::new();
+    let mut previous = HashSet::new();
+    // A scan can fail while the models directory is briefly unavailable, such as
+    // on a network mount, so the last good one is kept until the next tick
+    let mut installed = Vec::new();
+
+    println!(
+        "Recording model usage to {} every {}s. Press Ctrl-C to stop.",
+        db.display(),
+        interval.as_secs()
+    );
+
+    loop {
+        match scan_models() {
+            Ok(models) => installed = models,
+            Err(err) => eprintln!("Failed to scan the models, using the last scan: {:#}", err),
+        }
+        let hash_to_name_size = index_by_digest(&installed);
+        let digests: HashMap<_, _> = installed
+            .iter()
+            .map(|m| (m.name.clone(), m.digest.clone()))
+            .collect();
+
+        for path in get_log_paths() {
+            let scanner = scanners.entry(path.clone()).or_default();
+            if let Err(err) = tail_log(&conn, &path, scanner, &hash_to_name_size) {
+                eprintln!("Failed to read {}: {:#}", path.display(), err);
+            }
+        }
+
+        if let Err(err) = poll_loaded(&conn, &mut previous, &digests) {
+            eprintln!("Failed to query the Ollama server: {:#}", err);
+        }
+
+        thread::sleep(interval);
+    }
+}
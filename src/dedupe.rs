@@ -0,0 +1,188 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{adapter, format_size, get_model_dir, prune, table::{self, Align}, InstalledModel};
+
+/// Tags whose manifests reference exactly the same blobs.
+struct AliasGroup<'a> {
+    canonical: &'a InstalledModel,
+    /// Other tags in the group the user asked to keep.
+    kept: Vec<&'a InstalledModel>,
+    aliases: Vec<&'a InstalledModel>,
+}
+
+/// Picks the tag to keep from a group of aliases: an explicitly requested one,
+/// then `latest` since that's what an untagged name resolves to, then the first by name.
+fn pick_canonical<'a>(tags: &[&'a InstalledModel], keep: &[String]) -> &'a InstalledModel {
+    tags.iter()
+        .find(|m| keep.contains(&m.name))
+        .or_else(|| tags.iter().find(|m| m.name.ends_with(":latest")))
+        .unwrap_or(&tags[0])
+}
+
+fn find_alias_groups<'a>(installed: &'a [InstalledModel], keep: &[String]) -> Vec<AliasGroup<'a>> {
+    let mut by_blobs: BTreeMap<Vec<&str>, Vec<&InstalledModel>> = BTreeMap::new();
+    for model in installed {
+        let mut blobs: Vec<&str> = model.blobs.iter().map(|(d, _)| d.as_str()).collect();
+        blobs.sort_unstable();
+        by_blobs.entry(blobs).or_default().push(model);
+    }
+
+    let mut groups: Vec<_> = by_blobs
+        .into_values()
+        .filter(|tags| tags.len() > 1)
+        .map(|mut tags| {
+            tags.sort_by(|a, b| a.name.cmp(&b.name));
+            let canonical = pick_canonical(&tags, keep);
+            let (kept, aliases) = tags
+                .into_iter()
+                .filter(|m| m.name != canonical.name)
+                .partition(|m| keep.contains(&m.name));
+            AliasGroup { canonical, kept, aliases }
+        })
+        .collect();
+    groups.sort_by(|a, b| a.canonical.name.cmp(&b.canonical.name));
+    groups
+}
+
+/// Tags sharing model weights but carrying a different template, system prompt or
+/// parameters. These are real variants, not aliases, and are never removed.
+fn find_variants(installed: &[InstalledModel], groups: &[AliasGroup]) -> Vec<Vec<String>> {
+    let mut by_digest: BTreeMap<&str, Vec<&InstalledModel>> = BTreeMap::new();
+    for model in installed {
+        by_digest.entry(model.digest.as_str()).or_default().push(model);
+    }
+
+    by_digest
+        .into_values()
+        .filter_map(|tags| {
+            // Collapse each alias group to its canonical tag before comparing.
+            let mut names: Vec<String> = tags
+                .iter()
+                .filter(|m| !groups.iter().any(|g| g.kept.iter().chain(&g.aliases).any(|a| a.name == m.name)))
+                .map(|m| m.name.clone())
+                .collect();
+            names.sort();
+            (names.len() > 1).then_some(names)
+        })
+        .collect()
+}
+
+pub fn run(installed: &[InstalledModel], keep: &[String], apply: bool, yes: bool) -> Result<()> {
+    let groups = find_alias_groups(installed, keep);
+    let variants = find_variants(installed, &groups);
+
+    if groups.is_empty() {
+        println!("\nNo duplicate tags found.");
+    } else {
+        let rows: Vec<_> = groups
+            .iter()
+            .map(|g| {
+                let kept: Vec<_> =
+                    std::iter::once(g.canonical).chain(g.kept.iter().copied()).map(|m| m.name.as_str()).collect();
+                let aliases: Vec<_> = g.aliases.iter().map(|a| a.name.as_str()).collect();
+                let aliases = if aliases.is_empty() { "-".to_string() } else { aliases.join(", ") };
+                vec![kept.join(", "), aliases, format_size(g.canonical.size)]
+            })
+            .collect();
+
+        println!("\nDuplicate Tags (identical manifests, removing an alias frees no space):");
+        table::print(&[("Keep", Align::Left), ("Aliases", Align::Left), ("Size", Align::Right)], &rows);
+    }
+
+    if !variants.is_empty() {
+        println!("\nShared Weights (same model layer, different configuration; not aliases):");
+        for names in &variants {
+            println!("  {}", names.join(", "));
+        }
+    }
+    println!();
+
+    if !apply || groups.is_empty() {
+        return Ok(());
+    }
+
+    let loaded = prune::loaded_digests(installed)?.unwrap_or_else(|| {
+        eprintln!("Warning: {}", prune::NO_SERVER);
+        HashSet::new()
+    });
+    let mut to_remove = Vec::new();
+    for alias in groups.iter().flat_map(|g| g.aliases.iter()) {
+        if loaded.contains(&alias.digest) {
+            println!("Skipping {}: currently loaded by the Ollama server", alias.name);
+        } else {
+            to_remove.push(*alias);
+        }
+    }
+
+    if to_remove.is_empty() {
+        return Ok(());
+    }
+    for warning in adapter::orphaned(installed, &to_remove) {
+        println!("Warning: {}", warning);
+    }
+    if !yes && !prune::confirm(&format!("Remove {} alias tag(s)?", to_remove.len()))? {
+        println!("Nothing deleted.");
+        return Ok(());
+    }
+
+    for name in prune::delete_models(&get_model_dir(), &to_remove)?.names {
+        println!("Deleted {}", name);
+    }
+    println!();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn names(models: &[&InstalledModel]) -> Vec<String> {
+        models.iter().map(|m| m.name.clone()).collect()
+    }
+
+    #[test]
+    fn alias_groups_keep_latest_unless_told_otherwise() {
+        let (dir, installed) = prune::test_models_dir(
+            "dedupe",
+            &[
+                ("llama3:8b", &["config", "weights"]),
+                ("llama3:latest", &["config", "weights"]),
+                ("llama3:tuned", &["tuned-config", "weights"]),
+            ],
+        );
+
+        let groups = find_alias_groups(&installed, &[]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].canonical.name, "llama3:latest");
+        assert_eq!(names(&groups[0].aliases), ["llama3:8b"]);
+        assert_eq!(find_variants(&installed, &groups), [["llama3:latest", "llama3:tuned"]]);
+
+        let groups = find_alias_groups(&installed, &["llama3:8b".to_string()]);
+        assert_eq!(groups[0].canonical.name, "llama3:8b");
+        assert_eq!(names(&groups[0].aliases), ["llama3:latest"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    /// Removing an alias leaves every blob, as the tag it aliases still uses them.
+    #[test]
+    fn removing_aliases_keeps_their_blobs() {
+        let (dir, installed) = prune::test_models_dir(
+            "dedupe",
+            &[("llama3:8b", &["config", "weights"]), ("llama3:latest", &["config", "weights"])],
+        );
+
+        let groups = find_alias_groups(&installed, &[]);
+        let deleted = prune::delete_models(&dir, &groups[0].aliases).unwrap();
+
+        assert_eq!(deleted.freed, 0);
+        assert!(!installed[0].path.exists());
+        assert!(installed[1].path.exists());
+        for digest in ["config", "weights"] {
+            assert!(dir.join("blobs").join(format!("sha256-{}", digest)).exists(), "{}", digest);
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::{format_size, notify::StaleSummary};
+
+// Shows a toast as PowerShell itself, the one app Windows lets scripts notify as
+// without registering first. The text comes in through the environment so it
+// needs no quoting.
+const WINDOWS_TOAST: &str = r#"
+$ErrorActionPreference = 'Stop'
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null
+$xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $xml.GetElementsByTagName('text')
+$text.Item(0).AppendChild($xml.CreateTextNode($env:OMAR_TITLE)) > $null
+$text.Item(1).AppendChild($xml.CreateTextNode($env:OMAR_BODY)) > $null
+$app = '{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe'
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($app).Show([Windows.UI.Notifications.ToastNotification]::new($xml))
+"#;
+
+/// Shows a native desktop notification with the tool each platform ships for it:
+/// `osascript` on macOS, PowerShell on Windows and `notify-send` elsewhere.
+pub fn show(title: &str, body: &str) -> Result<()> {
+    let (program, mut command) = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command
+            .args(["-e", "on run argv", "-e", "display notification (item 2 of argv) with title (item 1 of argv)"])
+            .args(["-e", "end run", title, body]);
+        ("osascript", command)
+    } else if cfg!(windows) {
+        let mut command = Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command", WINDOWS_TOAST])
+            .env("OMAR_TITLE", title)
+            .env("OMAR_BODY", body);
+        ("powershell", command)
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name=omar", title, body]);
+        ("notify-send", command)
+    };
+
+    let output = command.output().with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} failed with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// The clipboard tools to try in order, each with its arguments. On Linux it
+/// depends on the display server and what's installed.
+fn clipboard_commands() -> Vec<(&'static str, Vec<&'static str>)> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", vec![])]
+    } else if cfg!(windows) {
+        let script = "[Console]::InputEncoding = [Text.Encoding]::UTF8; [Console]::In.ReadToEnd() | Set-Clipboard";
+        vec![("powershell", vec!["-NoProfile", "-NonInteractive", "-Command", script])]
+    } else {
+        let mut commands = vec![
+            ("xclip", vec!["-selection", "clipboard"]),
+            ("xsel", vec!["--clipboard", "--input"]),
+        ];
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.insert(0, ("wl-copy", vec![]));
+        }
+        commands
+    }
+}
+
+/// Puts `text` on the system clipboard with the first clipboard tool that's
+/// installed.
+pub fn copy(text: &str) -> Result<()> {
+    let commands = clipboard_commands();
+    for (program, args) in &commands {
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).with_context(|| format!("Failed to run {}", program)),
+        };
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(text.as_bytes())
+            .with_context(|| format!("Failed to write to {}", program))?;
+        let status = child.wait()?;
+        if !status.success() {
+            bail!("{} failed with {}", program, status);
+        }
+        return Ok(());
+    }
+    let names: Vec<_> = commands.iter().map(|(program, _)| *program).collect();
+    bail!("No clipboard tool found; install one of {}", names.join(", "))
+}
+
+/// Notifies about models unused for the summary's `stale_days`, if there are any.
+/// Returns whether there was anything to notify about.
+pub fn notify_stale(summary: &StaleSummary) -> Result<bool> {
+    if summary.models.is_empty() {
+        return Ok(false);
+    }
+    let body = format!(
+        "{} model(s) unused for {}+ days, {} reclaimable",
+        summary.models.len(),
+        summary.stale_days,
+        format_size(summary.unused_bytes)
+    );
+    show("Ollama model report", &body)?;
+    Ok(true)
+}
@@ -0,0 +1,167 @@
+use anyhow::{bail, Context, Result};
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{
+    format_size,
+    history::{self, Snapshot, SnapshotModel, Status},
+    table::{self, Align},
+};
+
+/// Loads a diff operand: a snapshot id from the history database, or the path of a
+/// snapshot exported with `snapshot --output`.
+fn resolve(db: &Path, operand: &str) -> Result<(String, Snapshot)> {
+    if let Ok(id) = operand.parse::<i64>() {
+        let conn = history::open(db)?;
+        return Ok((format!("snapshot {}", id), history::load(&conn, id)?));
+    }
+
+    let content = fs::read_to_string(operand)
+        .with_context(|| format!("'{}' is neither a snapshot id nor a readable file", operand))?;
+    let snapshot = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse snapshot {}", operand))?;
+    Ok((operand.to_string(), snapshot))
+}
+
+fn installed(snapshot: &Snapshot) -> BTreeMap<&str, &SnapshotModel> {
+    snapshot
+        .models
+        .iter()
+        .filter(|m| m.status != Status::Deleted)
+        .map(|m| (m.name.as_str(), m))
+        .collect()
+}
+
+fn signed_size(before: u64, after: u64) -> String {
+    if after >= before {
+        format!("+{}", format_size(after - before))
+    } else {
+        format!("-{}", format_size(before - after))
+    }
+}
+
+pub fn run(db: &Path, a: Option<&str>, b: Option<&str>) -> Result<()> {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a.to_string(), b.to_string()),
+        (Some(_), None) | (None, Some(_)) => bail!("Give two snapshots to compare, or none for the latest two"),
+        (None, None) => {
+            let ids = history::latest_ids(&history::open(db)?, 2)?;
+            if ids.len() < 2 {
+                bail!("Need at least two snapshots in {} to compare", db.display());
+            }
+            (ids[1].to_string(), ids[0].to_string())
+        }
+    };
+
+    let (a_label, before) = resolve(db, &a)?;
+    let (b_label, after) = resolve(db, &b)?;
+    let (old, new) = (installed(&before), installed(&after));
+
+    println!(
+        "\nComparing {} ({}) with {} ({})",
+        a_label,
+        before.taken_at.format("%Y-%m-%d %H:%M"),
+        b_label,
+        after.taken_at.format("%Y-%m-%d %H:%M")
+    );
+
+    let added: Vec<_> = new
+        .values()
+        .filter(|m| !old.contains_key(m.name.as_str()))
+        .map(|m| vec![m.name.clone(), format_size(m.size)])
+        .collect();
+    if !added.is_empty() {
+        println!("\nAdded Models:");
+        table::print(&[("Model", Align::Left), ("Size", Align::Right)], &added);
+    }
+
+    let removed: Vec<_> = old
+        .values()
+        .filter(|m| !new.contains_key(m.name.as_str()))
+        .map(|m| vec![m.name.clone(), format_size(m.size)])
+        .collect();
+    if !removed.is_empty() {
+        println!("\nRemoved Models:");
+        table::print(&[("Model", Align::Left), ("Size", Align::Right)], &removed);
+    }
+
+    let resized: Vec<_> = new
+        .values()
+        .filter_map(|m| old.get(m.name.as_str()).map(|o| (*o, *m)))
+        .filter(|(o, m)| o.size != m.size || o.digest != m.digest)
+        .map(|(o, m)| {
+            vec![
+                m.name.clone(),
+                format_size(o.size),
+                format_size(m.size),
+                signed_size(o.size, m.size),
+            ]
+        })
+        .collect();
+    if !resized.is_empty() {
+        println!("\nChanged Models (re-pulled or rebuilt):");
+        table::print(
+            &[
+                ("Model", Align::Left),
+                ("Before", Align::Right),
+                ("After", Align::Right),
+                ("Change", Align::Right),
+            ],
+            &resized,
+        );
+    }
+
+    // Usage is compared across every row, so deleted models' final uses show up too.
+    let old_usage: BTreeMap<_, _> = before.models.iter().map(|m| (m.name.as_str(), m)).collect();
+    let usage: Vec<_> = after
+        .models
+        .iter()
+        .filter_map(|m| {
+            let (count, last_used) = old_usage
+                .get(m.name.as_str())
+                .map_or((0, None), |o| (o.usage_count, o.last_used));
+            (count != m.usage_count || last_used != m.last_used).then(|| {
+                vec![
+                    m.name.clone(),
+                    count.to_string(),
+                    m.usage_count.to_string(),
+                    format!("{:+}", m.usage_count as i64 - count as i64),
+                    m.last_used.map_or("never".to_string(), |t| t.format("%Y-%m-%d").to_string()),
+                ]
+            })
+        })
+        .collect();
+    if !usage.is_empty() {
+        println!("\nUsage Changes:");
+        table::print(
+            &[
+                ("Model", Align::Left),
+                ("Uses Before", Align::Right),
+                ("Uses After", Align::Right),
+                ("Change", Align::Right),
+                ("Last Used", Align::Left),
+            ],
+            &usage,
+        );
+    }
+
+    if added.is_empty() && removed.is_empty() && resized.is_empty() && usage.is_empty() {
+        println!("\nNo changes.");
+    }
+
+    let total = |models: &BTreeMap<&str, &SnapshotModel>| -> u64 {
+        let mut by_digest = BTreeMap::new();
+        for m in models.values() {
+            by_digest.insert(m.digest.as_str(), m.size);
+        }
+        by_digest.values().sum()
+    };
+    let (old_total, new_total) = (total(&old), total(&new));
+    println!(
+        "\nTotal model storage: {} -> {} ({})\n",
+        format_size(old_total),
+        format_size(new_total),
+        signed_size(old_total, new_total)
+    );
+
+    Ok(())
+}
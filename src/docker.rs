@@ -0,0 +1,95 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::debug;
+
+/// Where the official image keeps its models unless `OLLAMA_MODELS` says otherwise.
+const IMAGE_MODELS_DIR: &str = "/root/.ollama/models";
+
+/// A running container that looks like Ollama's.
+pub struct Container {
+    pub id: String,
+    pub name: String,
+}
+
+fn docker(args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("docker").args(args).output().context("Failed to run docker")?;
+    if !output.status.success() {
+        bail!("docker {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+/// The first running container of the `ollama/ollama` image, or named after
+/// Ollama, if a Docker daemon is running at all.
+pub fn find() -> Option<Container> {
+    let listing = match docker(&["ps", "--format", "{{.ID}}\t{{.Image}}\t{{.Names}}"]) {
+        Ok(listing) => listing,
+        Err(err) => {
+            debug!(%err, "not looking for an Ollama container");
+            return None;
+        }
+    };
+    String::from_utf8_lossy(&listing).lines().find_map(|line| {
+        let mut fields = line.split('\t');
+        let (id, image, name) = (fields.next()?, fields.next()?, fields.next()?);
+        let repository = image.rsplit_once(':').map_or(image, |(repository, _)| repository);
+        (repository.ends_with("ollama/ollama") || name.contains("ollama"))
+            .then(|| Container { id: id.to_string(), name: name.to_string() })
+    })
+}
+
+/// The host directory mounted where the container keeps its models, if it's
+/// a bind mount omar can read. Named volumes under Docker Desktop live inside its
+/// virtual machine and can't be.
+pub fn models_dir(container: &Container) -> Result<Option<PathBuf>> {
+    let inspected: Value = serde_json::from_slice(&docker(&["inspect", "--format", "{{json .}}", &container.id])?)?;
+    let inside = inspected["Config"]["Env"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .find_map(|var| var.strip_prefix("OLLAMA_MODELS="))
+        .unwrap_or(IMAGE_MODELS_DIR)
+        .to_string();
+
+    // The mount deepest into the models directory's path is the one holding it
+    let mount = inspected["Mounts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| Some((m["Source"].as_str()?, m["Destination"].as_str()?)))
+        .filter_map(|(source, destination)| {
+            let rest = Path::new(&inside).strip_prefix(destination).ok()?;
+            Some((destination.len(), Path::new(source).join(rest)))
+        })
+        .max_by_key(|(depth, _)| *depth);
+    let Some((_, dir)) = mount else {
+        debug!(container = %container.name, "no mount holds the container's models");
+        return Ok(None);
+    };
+    debug!(container = %container.name, dir = %dir.display(), "found the container's models directory");
+    Ok(dir.join("manifests").is_dir().then_some(dir))
+}
+
+/// Where the logs of `container` are kept while the report is made.
+pub fn log_dir(container: &Container) -> PathBuf {
+    env::temp_dir().join(format!("omar-docker-{}", container.name))
+}
+
+/// Saves what the container has logged as `server.log` in `dir`. Ollama logs to
+/// stderr, so that's what's kept, after anything on stdout.
+pub fn collect_logs(container: &Container, dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let output = Command::new("docker").args(["logs", &container.id]).output().context("Failed to run docker")?;
+    if !output.status.success() {
+        bail!("docker logs {} failed: {}", container.name, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let mut logs = output.stdout;
+    logs.extend(output.stderr);
+    fs::write(dir.join("server.log"), logs).with_context(|| format!("Failed to write {}", dir.display()))
+}
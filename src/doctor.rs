@@ -0,0 +1,261 @@
+use anyhow::{bail, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{api, config, paths, scan_models_with_warnings, wsl, LogCache, OmarError, Paths};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// The result of one check, with what to do about it when it didn't pass.
+struct Check {
+    outcome: Outcome,
+    what: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(what: impl Into<String>) -> Check {
+        Check { outcome: Outcome::Pass, what: what.into(), hint: None }
+    }
+
+    fn warn(what: impl Into<String>, hint: impl Into<String>) -> Check {
+        Check { outcome: Outcome::Warn, what: what.into(), hint: Some(hint.into()) }
+    }
+
+    fn fail(what: impl Into<String>, hint: impl Into<String>) -> Check {
+        Check { outcome: Outcome::Fail, what: what.into(), hint: Some(hint.into()) }
+    }
+}
+
+const MODELS_HINT: &str =
+    "Pass --models-dir, set OLLAMA_MODELS, or set model_dir under [defaults] to the directory holding manifests and blobs.";
+const LOGS_HINT: &str =
+    "Pass --logs-dir, set OMAR_LOG_DIR, or set log_dir under [defaults] to the directory holding server.log.";
+
+fn check_models(paths: &Paths) -> Vec<Check> {
+    let dir = &paths.model_dir;
+    let mut checks = vec![match scan_models_with_warnings() {
+        Ok((models, warnings)) if warnings.is_empty() && models.is_empty() => Check::warn(
+            format!("Models directory {} has no models", dir.display()),
+            "Pull a model with `ollama pull`, or point omar at the directory Ollama is actually using. ".to_string()
+                + MODELS_HINT,
+        ),
+        Ok((models, warnings)) if warnings.is_empty() => {
+            Check::pass(format!("Models directory {} has {} tag(s)", dir.display(), models.len()))
+        }
+        Ok((models, warnings)) => Check::warn(
+            format!(
+                "Models directory {} has {} tag(s), but {} manifest(s) couldn't be read:\n{}",
+                dir.display(),
+                models.len(),
+                warnings.len(),
+                warnings.iter().map(|w| format!("  {}", w)).collect::<Vec<_>>().join("\n"),
+            ),
+            "Check the permissions of the files listed; on Linux the models usually belong to the ollama user, \
+             so run omar as that user or add yourself to its group.",
+        ),
+        Err(OmarError::ModelsDirNotFound(_)) => {
+            Check::fail(format!("Models directory {} doesn't exist", dir.display()), MODELS_HINT)
+        }
+        Err(OmarError::NotAModelsDir { missing, .. }) => Check::fail(
+            format!("{} has no {} folder, so it isn't a models directory", dir.display(), missing),
+            MODELS_HINT,
+        ),
+        Err(err) => Check::fail(format!("Models directory {} couldn't be scanned: {}", dir.display(), err), MODELS_HINT),
+    }];
+
+    // Models in a directory omar isn't using usually mean Ollama runs as another user
+    for other in paths::default_model_dirs() {
+        if other != *dir && other.join("manifests").is_dir() {
+            checks.push(Check::warn(
+                format!("{} also has manifests", other.display()),
+                "Ollama may be keeping its models there; pass --models-dir to report on them.",
+            ));
+        }
+    }
+    for other in wsl::other_model_dirs() {
+        checks.push(Check::warn(
+            format!("{} has the models of the Ollama on the other side of WSL", other.display()),
+            "Pass --wsl to list them in the report too, or --models-dir to report on them instead.",
+        ));
+    }
+    checks
+}
+
+/// Which of the formats Ollama has logged in over the years a log's lines are in.
+#[derive(Default)]
+struct LogFormats {
+    structured: usize,
+    legacy: usize,
+    loads: usize,
+}
+
+fn log_formats(path: &Path) -> std::io::Result<LogFormats> {
+    let content = fs::read(path)?;
+    let mut formats = LogFormats::default();
+    for line in String::from_utf8_lossy(&content).lines() {
+        if line.starts_with("time=") {
+            formats.structured += 1;
+        } else if line.get(4..5) == Some("/") && line.get(7..8) == Some("/") {
+            formats.legacy += 1;
+        } else if line.starts_with("llama_model_loader: loaded meta data") {
+            formats.loads += 1;
+        }
+    }
+    Ok(formats)
+}
+
+fn check_logs(paths: &Paths) -> Vec<Check> {
+    let Some(dir) = &paths.log_dir else {
+        // Ollama on Linux logs to the systemd journal rather than a file
+        let mut cache = LogCache::default();
+        cache.read_journal();
+        let units = paths::journal_units().join(", ");
+        return vec![if cache.read_any_journal() {
+            Check::pass(format!(
+                "No log directory; reading the journal of {} instead, with {} model load(s)",
+                units,
+                cache.journal_events().len()
+            ))
+        } else {
+            Check::fail(
+                format!("No log directory, and the journal of {} has none of its lines", units),
+                format!(
+                    "Run omar as a user in the systemd-journal group, or save the journal with \
+                     `journalctl -u {} -o cat --no-pager > ~/ollama-logs/server.log`. {}",
+                    paths::journal_unit(),
+                    LOGS_HINT
+                ),
+            )
+        }];
+    };
+    if !dir.is_dir() {
+        return vec![Check::fail(format!("Log directory {} doesn't exist", dir.display()), LOGS_HINT)];
+    }
+    let logs = paths.log_paths();
+    if logs.is_empty() {
+        return vec![Check::fail(format!("No server*.log files in {}", dir.display()), LOGS_HINT)];
+    }
+
+    logs.iter()
+        .map(|log| match log_formats(log) {
+            Err(err) => Check::fail(
+                format!("{} couldn't be read: {}", log.display(), err),
+                "Check the file's permissions, or copy it somewhere readable and pass --logs-dir.",
+            ),
+            Ok(f) if f.structured == 0 && f.legacy == 0 => Check::fail(
+                format!("{} has no timestamps in a format omar recognizes", log.display()),
+                "omar reads `time=` lines and older `YYYY/MM/DD hh:mm:ss` lines; \
+                 make sure this is an Ollama server log and not the app or journal output.",
+            ),
+            Ok(f) => {
+                let format = match (f.structured > 0, f.legacy > 0) {
+                    (true, true) => "structured and older timestamps",
+                    (true, false) => "structured timestamps",
+                    _ => "older timestamps",
+                };
+                if f.loads == 0 {
+                    Check::warn(
+                        format!("{} has {}, but no model loads", log.display(), format),
+                        "Loads only appear once a model is run; if models have been run, \
+                         the server may be writing a different log.",
+                    )
+                } else {
+                    Check::pass(format!("{} has {} and {} model load(s)", log.display(), format, f.loads))
+                }
+            }
+        })
+        .collect()
+}
+
+fn check_config(path: Option<&Path>, fallback: Option<&Path>) -> Check {
+    let path = match (path, fallback) {
+        (Some(path), _) => path,
+        (None, Some(fallback)) if fallback.is_file() => fallback,
+        (None, Some(fallback)) => return Check::pass(format!("No config file at {}, using the defaults", fallback.display())),
+        (None, None) => return Check::pass("No config file location on this platform, using the defaults"),
+    };
+    match config::load(Some(path), None) {
+        Ok(_) => Check::pass(format!("Config file {} is valid", path.display())),
+        Err(err) => Check::fail(format!("{:#}", err), "Fix the setting named in the error, or move the file aside."),
+    }
+}
+
+/// The closest directory at or above `path` that exists.
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find(|p| p.exists()).map(Path::to_path_buf)
+}
+
+fn check_history(paths: &Paths) -> Check {
+    let db = &paths.history_db;
+    let target = if db.exists() { Some(db.clone()) } else { existing_ancestor(db) };
+    match target.map(|p| fs::metadata(&p).map(|m| (p, m.permissions().readonly()))) {
+        Some(Ok((_, false))) if db.exists() => Check::pass(format!("History database {} is writable", db.display())),
+        Some(Ok((_, false))) => Check::pass(format!("History database {} will be created on first use", db.display())),
+        Some(Ok((p, true))) => Check::warn(
+            format!("{} is read-only, so usage history can't be recorded", p.display()),
+            "Pass --no-history, or --db with a writable location for the commands that take one.",
+        ),
+        Some(Err(err)) => Check::warn(
+            format!("History database {} can't be checked: {}", db.display(), err),
+            "Pass --no-history if the history isn't needed.",
+        ),
+        None => Check::warn(
+            format!("No directory above {} exists", db.display()),
+            "Pass --no-history if the history isn't needed.",
+        ),
+    }
+}
+
+fn check_server() -> Check {
+    let host = api::ollama_host();
+    match api::loaded_models() {
+        Ok(Some(loaded)) => Check::pass(format!("Ollama server at {} is up with {} model(s) loaded", host, loaded.len())),
+        Ok(None) => Check::warn(
+            format!("No Ollama server is listening at {}", host),
+            "Start it with `ollama serve`, or set OLLAMA_HOST or host under [defaults] if it runs elsewhere. \
+             The report works without it, but --api-only, loaded-model checks and deletions need it.",
+        ),
+        Err(err) => Check::fail(
+            format!("{:#}", err),
+            "Something other than Ollama may be answering on that address; check OLLAMA_HOST.",
+        ),
+    }
+}
+
+/// Checks everything omar depends on and prints what passed, what didn't, and what
+/// to do about it. The config file is checked the way [`config::load`] picks it. Fails
+/// when any check fails, so scripts can tell.
+pub fn run(config: Option<&Path>, config_fallback: Option<&Path>) -> Result<()> {
+    let paths = Paths::current();
+    let mut checks = check_models(&paths);
+    checks.extend(check_logs(&paths));
+    checks.push(check_config(config, config_fallback));
+    checks.push(check_history(&paths));
+    checks.push(check_server());
+
+    for check in &checks {
+        let label = match check.outcome {
+            Outcome::Pass => "PASS",
+            Outcome::Warn => "WARN",
+            Outcome::Fail => "FAIL",
+        };
+        println!("[{}] {}", label, check.what);
+        if let Some(hint) = &check.hint {
+            println!("       {}", hint);
+        }
+    }
+
+    let failed = checks.iter().filter(|c| c.outcome == Outcome::Fail).count();
+    if failed > 0 {
+        bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
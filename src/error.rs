@@ -0,0 +1,71 @@
+use serde::Serialize;
+use std::{fmt, io, path::PathBuf};
+use thiserror::Error;
+use tracing::warn;
+
+/// What can go wrong while scanning the models directory and the server logs.
+#[derive(Debug, Error)]
+pub enum OmarError {
+    #[error("Ollama models directory {} not found (set OLLAMA_MODELS if it lives elsewhere)", .0.display())]
+    ModelsDirNotFound(PathBuf),
+
+    #[error("{} is not an Ollama models directory: it has no {missing} folder (set OLLAMA_MODELS to the directory holding manifests and blobs)", .path.display())]
+    NotAModelsDir { path: PathBuf, missing: &'static str },
+
+    #[error("Failed to read manifest {}", .path.display())]
+    ManifestUnreadable {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Failed to parse manifest {}", .path.display())]
+    ManifestParse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("Failed to read log {}", .path.display())]
+    LogUnreadable {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Invalid path pattern")]
+    Pattern(#[from] glob::PatternError),
+}
+
+pub type Result<T, E = OmarError> = std::result::Result<T, E>;
+
+/// A file that was skipped, reported alongside whatever could still be read.
+#[derive(Debug, Clone, Serialize)]
+pub struct Warning {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+impl Warning {
+    /// The warning for an error about a single file, or `None` for an error that
+    /// should stop the scan.
+    pub(crate) fn from_error(err: &OmarError) -> Option<Warning> {
+        let (path, reason) = match err {
+            OmarError::ManifestUnreadable { path, source } => (path, format!("manifest could not be read: {}", source)),
+            OmarError::ManifestParse { path, source } => (path, format!("not a valid manifest: {}", source)),
+            OmarError::LogUnreadable { path, source } => (path, format!("log could not be read: {}", source)),
+            OmarError::ModelsDirNotFound(_) | OmarError::NotAModelsDir { .. } | OmarError::Pattern(_) => return None,
+        };
+        warn!(path = %path.display(), %reason, "skipping file");
+        Some(Warning {
+            path: path.clone(),
+            reason,
+        })
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.reason)
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Result};
+use std::{sync::Mutex, thread, time::Instant};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::{metrics, scan_report};
+
+/// The last scan's metrics, or its error, and when it finished.
+type LastScan = Mutex<Option<(Instant, Result<String, String>)>>;
+
+/// Threads answering requests. A few, so a slow scrape doesn't hold up `/`, but a
+/// fixed number however many requests pile up.
+const WORKERS: usize = 4;
+
+/// Serves `/metrics` for Prometheus, rescanning the models and logs on every scrape.
+/// Requests are answered by [`WORKERS`] threads, and scans run one at a time:
+/// scrapes that arrive while one is running share its result.
+pub fn run(listen: &str) -> Result<()> {
+    let server = Server::http(listen).map_err(|e| anyhow!("Failed to listen on {}: {}", listen, e))?;
+    println!("Serving metrics on http://{}/metrics", listen);
+
+    let last_scan = LastScan::default();
+    thread::scope(|scope| {
+        for _ in 0..WORKERS {
+            scope.spawn(|| {
+                for request in server.incoming_requests() {
+                    respond(request, &last_scan);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+/// Returns the metrics from a scan that finished after `arrived`, scanning again
+/// unless a scrape that was waiting alongside this one already did.
+fn scrape(arrived: Instant, last_scan: &LastScan) -> Result<String, String> {
+    let mut last = last_scan.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((finished, result)) = last.as_ref() {
+        if *finished >= arrived {
+            return result.clone();
+        }
+    }
+    let result = scan_report().map(|models| metrics::render(&models)).map_err(|err| format!("{:#}", err));
+    *last = Some((Instant::now(), result.clone()));
+    result
+}
+
+fn respond(request: Request, last_scan: &LastScan) {
+    let arrived = Instant::now();
+    // Prometheus can be set up to add parameters to the scrape URL
+    let path = request.url().split('?').next().unwrap_or("");
+    let response = match path {
+        "/metrics" => match scrape(arrived, last_scan) {
+            Ok(body) => Response::from_string(body).with_header(
+                "Content-Type: text/plain; version=0.0.4"
+                    .parse::<Header>()
+                    .unwrap(),
+            ),
+            Err(err) => Response::from_string(err).with_status_code(500),
+        },
+        "/" => Response::from_string("omar exporter: metrics are at /metrics\n"),
+        _ => Response::from_string("not found\n").with_status_code(404),
+    };
+
+    if let Err(err) = request.respond(response) {
+        eprintln!("Failed to send response: {}", err);
+    }
+}
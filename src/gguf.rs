@@ -0,0 +1,250 @@
+use anyhow::{bail, Context, Result};
+use glob::glob;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+use crate::{
+    format_size, paths,
+    table::{self, Align},
+};
+
+/// What a GGUF file's header says about the model in it.
+#[derive(Debug, Clone, Default)]
+pub struct Header {
+    pub name: Option<String>,
+    pub architecture: Option<String>,
+    /// `general.file_type`, the quantization most of the weights are in.
+    pub file_type: Option<u32>,
+    /// `general.size_label`, such as "8B", when the converter wrote one.
+    pub size_label: Option<String>,
+}
+
+impl Header {
+    /// The quantization as llama.cpp names it, e.g. "Q4_K_M".
+    pub fn quantization(&self) -> Option<String> {
+        let name = match self.file_type? {
+            0 => "F32",
+            1 => "F16",
+            2 => "Q4_0",
+            3 => "Q4_1",
+            7 => "Q8_0",
+            8 => "Q5_0",
+            9 => "Q5_1",
+            10 => "Q2_K",
+            11 => "Q3_K_S",
+            12 => "Q3_K_M",
+            13 => "Q3_K_L",
+            14 => "Q4_K_S",
+            15 => "Q4_K_M",
+            16 => "Q5_K_S",
+            17 => "Q5_K_M",
+            18 => "Q6_K",
+            19 => "IQ2_XXS",
+            20 => "IQ2_XS",
+            21 => "Q2_K_S",
+            22 => "IQ3_XS",
+            23 => "IQ3_XXS",
+            24 => "IQ1_S",
+            25 => "IQ4_NL",
+            26 => "IQ3_S",
+            27 => "IQ3_M",
+            28 => "IQ2_S",
+            29 => "IQ2_M",
+            30 => "IQ4_XS",
+            31 => "IQ1_M",
+            32 => "BF16",
+            36 => "TQ1_0",
+            37 => "TQ2_0",
+            other => return Some(format!("type {}", other)),
+        };
+        Some(name.to_string())
+    }
+}
+
+/// Reads GGUF values, whose lengths are 32-bit in version 1 of the format and
+/// 64-bit since.
+struct Reader<R> {
+    inner: R,
+    version: u32,
+}
+
+impl<R: Read> Reader<R> {
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.inner.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn length(&mut self) -> io::Result<u64> {
+        if self.version == 1 {
+            Ok(self.u32()? as u64)
+        } else {
+            Ok(u64::from_le_bytes(self.bytes()?))
+        }
+    }
+
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.inner).take(len), &mut io::sink())?;
+        if skipped < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.length()?;
+        // Keys and the values omar wants are short; anything this long is corruption
+        if len > 1 << 20 {
+            bail!("string of {} bytes", len);
+        }
+        let mut buf = vec![0; len as usize];
+        self.inner.read_exact(&mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    /// Reads a value of the given type, keeping it if it's a string or an integer
+    /// that fits a `u32` and skipping it otherwise.
+    fn value(&mut self, kind: u32) -> Result<Option<Value>> {
+        Ok(match kind {
+            0 | 1 | 7 => Some(Value::Int(self.bytes::<1>()?[0] as u32)),
+            2 | 3 => Some(Value::Int(u16::from_le_bytes(self.bytes()?) as u32)),
+            4 | 5 => Some(Value::Int(self.u32()?)),
+            6 => self.skip(4).map(|_| None)?,
+            8 => Some(Value::Str(self.string()?)),
+            9 => {
+                let (element, count) = (self.u32()?, self.length()?);
+                for _ in 0..count {
+                    self.value(element)?;
+                }
+                None
+            }
+            10..=12 => self.skip(8).map(|_| None)?,
+            other => bail!("unknown value type {}", other),
+        })
+    }
+}
+
+enum Value {
+    Int(u32),
+    Str(String),
+}
+
+/// Reads the metadata at the start of a GGUF file, stopping before the tensors.
+pub fn read_header(path: &Path) -> Result<Header> {
+    let mut reader = Reader { inner: BufReader::new(File::open(path)?), version: 0 };
+    if &reader.bytes::<4>()? != b"GGUF" {
+        bail!("not a GGUF file");
+    }
+    reader.version = reader.u32()?;
+    if !(1..=3).contains(&reader.version) {
+        bail!("unsupported GGUF version {}", reader.version);
+    }
+    let _tensors = reader.length()?;
+    let entries = reader.length()?;
+
+    let mut header = Header::default();
+    for _ in 0..entries {
+        let key = reader.string()?;
+        let kind = reader.u32()?;
+        let value = reader.value(kind).with_context(|| format!("reading {}", key))?;
+        match (key.as_str(), value) {
+            ("general.name", Some(Value::Str(s))) => header.name = Some(s),
+            ("general.architecture", Some(Value::Str(s))) => header.architecture = Some(s),
+            ("general.size_label", Some(Value::Str(s))) => header.size_label = Some(s),
+            ("general.file_type", Some(Value::Int(n))) => header.file_type = Some(n),
+            _ => {}
+        }
+    }
+    Ok(header)
+}
+
+/// A GGUF file found on disk, with its header if it could be read.
+pub struct Found {
+    pub path: PathBuf,
+    pub size: u64,
+    pub header: Result<Header>,
+}
+
+/// Finds every `.gguf` file under `dir`, largest first.
+pub fn find(dir: &Path) -> Result<Vec<Found>> {
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+    let mut found = Vec::new();
+    for path in glob(&paths::glob_pattern(dir, "**/*.gguf"))?.filter_map(Result::ok) {
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => {
+                let header = read_header(&path);
+                found.push(Found { size: metadata.len(), header, path });
+            }
+            Ok(_) => {}
+            Err(err) => debug!(path = %path.display(), %err, "skipping unreadable GGUF file"),
+        }
+    }
+    found.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    Ok(found)
+}
+
+/// Prints the GGUF files under each of `dirs` with what their headers say, so
+/// downloads that never made it into Ollama can be found and cleaned up.
+pub fn run(dirs: &[PathBuf]) -> Result<()> {
+    let mut found = Vec::new();
+    for dir in dirs {
+        found.extend(find(dir)?);
+    }
+    found.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    if found.is_empty() {
+        println!("\nNo GGUF files found.\n");
+        return Ok(());
+    }
+
+    let unknown = || "?".to_string();
+    let rows: Vec<_> = found
+        .iter()
+        .map(|f| match &f.header {
+            Ok(h) => vec![
+                f.path.display().to_string(),
+                h.name.clone().unwrap_or_else(unknown),
+                h.architecture.clone().unwrap_or_else(unknown),
+                h.size_label.clone().unwrap_or_else(unknown),
+                h.quantization().unwrap_or_else(unknown),
+                format_size(f.size),
+            ],
+            Err(err) => vec![
+                f.path.display().to_string(),
+                format!("unreadable: {:#}", err),
+                unknown(),
+                unknown(),
+                unknown(),
+                format_size(f.size),
+            ],
+        })
+        .collect();
+
+    println!();
+    table::print(
+        &[
+            ("File", Align::Left),
+            ("Name", Align::Left),
+            ("Architecture", Align::Left),
+            ("Parameters", Align::Left),
+            ("Quantization", Align::Left),
+            ("Size", Align::Right),
+        ],
+        &rows,
+    );
+    println!(
+        "\n{} GGUF file(s), {} in total\n",
+        found.len(),
+        format_size(found.iter().map(|f| f.size).sum())
+    );
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+
+use crate::{format_size, InstalledModel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz, e.g. piped to `dot -Tsvg`
+    Dot,
+    /// Mermaid flowchart, for Markdown that renders it
+    Mermaid,
+}
+
+/// A blob and the manifests that reference it, by index into the installed models.
+struct BlobNode {
+    digest: String,
+    size: u64,
+    referenced_by: Vec<usize>,
+}
+
+/// Every blob the manifests reference, largest first.
+fn blob_nodes(installed: &[InstalledModel]) -> Vec<BlobNode> {
+    let mut blobs: BTreeMap<&str, BlobNode> = BTreeMap::new();
+    for (i, model) in installed.iter().enumerate() {
+        for (digest, size) in &model.blobs {
+            let node = blobs.entry(digest).or_insert_with(|| BlobNode {
+                digest: digest.clone(),
+                size: *size,
+                referenced_by: Vec::new(),
+            });
+            if !node.referenced_by.contains(&i) {
+                node.referenced_by.push(i);
+            }
+        }
+    }
+    let mut blobs: Vec<_> = blobs.into_values().collect();
+    blobs.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.digest.cmp(&b.digest)));
+    blobs
+}
+
+/// The label of a manifest: its name and what deleting it alone would free, which
+/// is only the blobs no other manifest references.
+fn model_label(model: &InstalledModel, index: usize, blobs: &[BlobNode]) -> String {
+    let frees: u64 = blobs
+        .iter()
+        .filter(|b| b.referenced_by == [index])
+        .map(|b| b.size)
+        .sum();
+    format!("{}\\nfrees {}", model.name, format_size(frees))
+}
+
+fn blob_label(blob: &BlobNode) -> String {
+    let short = blob.digest.get(..12).unwrap_or(&blob.digest);
+    match blob.referenced_by.len() {
+        1 => format!("{}\\n{}", short, format_size(blob.size)),
+        n => format!("{}\\n{}, shared by {}", short, format_size(blob.size), n),
+    }
+}
+
+fn render_dot(installed: &[InstalledModel], blobs: &[BlobNode]) -> String {
+    let mut out = String::from("digraph models {\n  rankdir=LR;\n  node [fontname=\"sans-serif\"];\n");
+    for (i, model) in installed.iter().enumerate() {
+        let label = model_label(model, i, blobs).replace('"', "\\\"");
+        out.push_str(&format!("  m{} [shape=box, label=\"{}\"];\n", i, label));
+    }
+    for (j, blob) in blobs.iter().enumerate() {
+        let style = if blob.referenced_by.len() > 1 { ", style=filled, fillcolor=\"#ffe082\"" } else { "" };
+        out.push_str(&format!("  b{} [shape=ellipse, label=\"{}\"{}];\n", j, blob_label(blob), style));
+    }
+    for (j, blob) in blobs.iter().enumerate() {
+        for i in &blob.referenced_by {
+            out.push_str(&format!("  m{} -> b{};\n", i, j));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_mermaid(installed: &[InstalledModel], blobs: &[BlobNode]) -> String {
+    // Mermaid breaks lines with <br> and has no escape for quotes inside labels
+    let label = |text: String| text.replace("\\n", "<br>").replace('"', "#quot;");
+    let mut out = String::from("flowchart LR\n");
+    for (i, model) in installed.iter().enumerate() {
+        out.push_str(&format!("  m{}[\"{}\"]\n", i, label(model_label(model, i, blobs))));
+    }
+    for (j, blob) in blobs.iter().enumerate() {
+        out.push_str(&format!("  b{}([\"{}\"])\n", j, label(blob_label(blob))));
+    }
+    for (j, blob) in blobs.iter().enumerate() {
+        for i in &blob.referenced_by {
+            out.push_str(&format!("  m{} --> b{}\n", i, j));
+        }
+    }
+    let shared: Vec<_> = (0..blobs.len())
+        .filter(|j| blobs[*j].referenced_by.len() > 1)
+        .map(|j| format!("b{}", j))
+        .collect();
+    if !shared.is_empty() {
+        out.push_str("  classDef shared fill:#ffe082\n");
+        out.push_str(&format!("  class {} shared\n", shared.join(",")));
+    }
+    out
+}
+
+/// The graph of manifests and the blobs they reference, in `format`.
+pub fn render(installed: &[InstalledModel], format: GraphFormat) -> String {
+    let blobs = blob_nodes(installed);
+    match format {
+        GraphFormat::Dot => render_dot(installed, &blobs),
+        GraphFormat::Mermaid => render_mermaid(installed, &blobs),
+    }
+}
+
+/// Prints the graph of which manifests share which blobs.
+pub fn run(installed: &[InstalledModel], format: GraphFormat) -> Result<()> {
+    print!("{}", render(installed, format));
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Local};
+use rusqlite::Connection;
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{
+    format_size,
+    history::{self, Snapshot},
+    table::{self, Align},
+};
+
+/// How far back snapshots are looked at to work out the growth rate.
+const FORECAST_DAYS: i64 = 90;
+
+/// When the volume holding the models will run out of space at the recent rate of
+/// growth.
+#[derive(Debug)]
+pub struct Forecast {
+    /// Bytes added per day on average, negative when storage is shrinking.
+    pub bytes_per_day: f64,
+    /// Space left on the volume.
+    pub available: u64,
+    /// The day it runs out, unless storage isn't growing.
+    pub full_on: Option<DateTime<Local>>,
+}
+
+impl Forecast {
+    pub fn describe(&self) -> String {
+        let free = format_size(self.available);
+        match self.full_on {
+            Some(full_on) => format!(
+                "growing {}/day, so the {} free on the models volume runs out around {} (in {} days)",
+                format_size(self.bytes_per_day as u64),
+                free,
+                full_on.format("%Y-%m-%d"),
+                (full_on - Local::now()).num_days()
+            ),
+            None => format!("not growing over the last {} days, {} free on the models volume", FORECAST_DAYS, free),
+        }
+    }
+}
+
+/// Fits a straight line through the total size of every snapshot from the last
+/// [`FORECAST_DAYS`] days and extends it until it meets the space left in
+/// `model_dir`. Needs at least two snapshots a day or more apart.
+pub fn forecast(conn: &Connection, model_dir: &Path) -> Result<Option<Forecast>> {
+    let since = Local::now() - Duration::days(FORECAST_DAYS);
+    let mut points = Vec::new();
+    for (id, taken_at) in history::snapshot_times(conn)? {
+        if taken_at >= since {
+            let days = (taken_at - since).num_seconds() as f64 / 86_400.0;
+            points.push((days, history::load(conn, id)?.total_size() as f64));
+        }
+    }
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) if last.0 - first.0 >= 1.0 => (first.0, last.0),
+        _ => return Ok(None),
+    };
+    debug_assert!(last > first);
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let variance: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let bytes_per_day = covariance / variance;
+
+    let available = fs2::available_space(model_dir)
+        .with_context(|| format!("Failed to read the free space on {}", model_dir.display()))?;
+    let full_on = (bytes_per_day > 0.0)
+        .then(|| Local::now() + Duration::seconds((available as f64 / bytes_per_day * 86_400.0).min(1e11) as i64));
+
+    Ok(Some(Forecast { bytes_per_day, available, full_on }))
+}
+
+/// How storage changed between the end of one month and the end of the next.
+struct Month {
+    label: String,
+    total: u64,
+    change: i128,
+    pulled: (usize, u64),
+    removed: (usize, u64),
+}
+
+fn signed_size(change: i128) -> String {
+    let size = format_size(change.unsigned_abs() as u64);
+    if change < 0 {
+        format!("-{}", size)
+    } else {
+        format!("+{}", size)
+    }
+}
+
+/// Compares each month's last snapshot with the previous month's. Weights pulled or
+/// removed and brought back within the same month don't show up.
+fn months(snapshots: &[(String, Snapshot)]) -> Vec<Month> {
+    let mut months = Vec::new();
+    let mut previous: Option<&Snapshot> = None;
+
+    for (label, snapshot) in snapshots {
+        let now = snapshot.installed_digests();
+        let before = previous.map(Snapshot::installed_digests).unwrap_or_default();
+        let tally = |sizes: Vec<u64>| (sizes.len(), sizes.iter().sum());
+        let pulled = tally(now.iter().filter(|(d, _)| !before.contains_key(*d)).map(|(_, s)| *s).collect());
+        let removed = tally(before.iter().filter(|(d, _)| !now.contains_key(*d)).map(|(_, s)| *s).collect());
+        let total = snapshot.total_size();
+
+        months.push(Month {
+            label: label.clone(),
+            total,
+            change: total as i128 - previous.map_or(0, Snapshot::total_size) as i128,
+            pulled,
+            removed,
+        });
+        previous = Some(snapshot);
+    }
+    months
+}
+
+fn tally((count, size): (usize, u64)) -> String {
+    if count == 0 {
+        "-".to_string()
+    } else {
+        format!("{} ({})", format_size(size), count)
+    }
+}
+
+pub fn run(db: &Path, model_dir: &Path) -> Result<()> {
+    let conn = history::open(db)?;
+
+    // The last snapshot of each month stands for that month
+    let mut month_ends: BTreeMap<String, i64> = BTreeMap::new();
+    for (id, taken_at) in history::snapshot_times(&conn)? {
+        month_ends.insert(taken_at.format("%Y-%m").to_string(), id);
+    }
+    if month_ends.is_empty() {
+        bail!("No snapshots in {}; record some with `snapshot`, e.g. from cron, first", db.display());
+    }
+
+    let snapshots = month_ends
+        .into_iter()
+        .map(|(label, id)| Ok((label, history::load(&conn, id)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let months = months(&snapshots);
+
+    println!("\nModel storage at the end of each month (the first month counts everything as pulled):");
+    let rows: Vec<_> = months
+        .iter()
+        .map(|m| {
+            vec![
+                m.label.clone(),
+                format_size(m.total),
+                signed_size(m.change),
+                tally(m.pulled),
+                tally(m.removed),
+            ]
+        })
+        .collect();
+    table::print(
+        &[
+            ("Month", Align::Left),
+            ("Total", Align::Right),
+            ("Change", Align::Right),
+            ("Pulled (models)", Align::Right),
+            ("Removed (models)", Align::Right),
+        ],
+        &rows,
+    );
+
+    if let (Some(first), Some(last)) = (months.first(), months.last()) {
+        let pulled: u64 = months.iter().skip(1).map(|m| m.pulled.1).sum();
+        let removed: u64 = months.iter().skip(1).map(|m| m.removed.1).sum();
+        println!(
+            "\nSince {}: {} pulled, {} removed, {} net.\n",
+            first.label,
+            format_size(pulled),
+            format_size(removed),
+            signed_size(last.total as i128 - first.total as i128)
+        );
+    }
+    match forecast(&conn, model_dir)? {
+        Some(forecast) => println!("Forecast: {}.\n", forecast.describe()),
+        None => println!("Forecast: needs snapshots at least a day apart within the last {} days.\n", FORECAST_DAYS),
+    }
+    Ok(())
+}
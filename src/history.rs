@@ -0,0 +1,415 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::{collections::{HashMap, HashSet}, fs, path::Path, path::PathBuf};
+
+pub use crate::Status;
+use crate::{InstalledModel, ModelUsage, Paths};
+
+const SCHEMA_VERSION: i32 = 2;
+
+/// One row of a snapshot: a single tag, or a deleted digest seen in the logs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotModel {
+    pub name: String,
+    pub digest: String,
+    pub size: u64,
+    pub status: Status,
+    pub last_used: Option<DateTime<Local>>,
+    pub usage_count: usize,
+    /// Manifest of an installed tag, telling apart the same tag in two of the
+    /// directories in `OLLAMA_MODELS`. Not stored or exported.
+    #[serde(skip)]
+    pub manifest: Option<PathBuf>,
+}
+
+/// The report at one point in time, as stored in the database or exported to JSON.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub taken_at: DateTime<Local>,
+    /// The machine the snapshot was taken on, for snapshots written by `collect`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    pub models: Vec<SnapshotModel>,
+}
+
+impl Snapshot {
+    /// Sizes of the installed weights, one entry per digest.
+    pub fn installed_digests(&self) -> HashMap<&str, u64> {
+        self.models
+            .iter()
+            .filter(|m| m.status != Status::Deleted)
+            .map(|m| (m.digest.as_str(), m.size))
+            .collect()
+    }
+
+    /// Total size of the installed weights, counting each digest once.
+    pub fn total_size(&self) -> u64 {
+        self.installed_digests().values().sum()
+    }
+}
+
+/// The history database used when none is given, from [`Paths::history_db`].
+pub fn default_db_path() -> PathBuf {
+    Paths::current().history_db
+}
+
+pub fn open(path: &Path) -> Result<Connection> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open history database {}", path.display()))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS snapshots (
+            id INTEGER PRIMARY KEY,
+            taken_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS snapshot_models (
+            snapshot_id INTEGER NOT NULL REFERENCES snapshots(id),
+            name TEXT NOT NULL,
+            digest TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            last_used TEXT,
+            usage_count INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS snapshot_models_snapshot ON snapshot_models(snapshot_id);
+        CREATE TABLE IF NOT EXISTS usage_events (
+            digest TEXT NOT NULL,
+            name TEXT,
+            loaded_at TEXT NOT NULL,
+            source TEXT NOT NULL,
+            UNIQUE (digest, loaded_at, source)
+        );
+        CREATE TABLE IF NOT EXISTS log_offsets (
+            path TEXT PRIMARY KEY,
+            offset INTEGER NOT NULL
+        );",
+    )?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+    Ok(conn)
+}
+
+/// Flattens the report into one row per installed tag plus one per deleted digest.
+pub fn current_models(
+    installed: &[InstalledModel],
+    model_usage: &HashMap<String, ModelUsage>,
+) -> Vec<SnapshotModel> {
+    let mut models: Vec<_> = installed
+        .iter()
+        .map(|model| {
+            let usage = model_usage.get(&model.digest);
+            SnapshotModel {
+                name: model.name.clone(),
+                digest: model.digest.clone(),
+                size: model.size,
+                status: usage.map_or(Status::Unlogged, |u| u.status),
+                last_used: usage.map(|u| u.last_used),
+                usage_count: usage.map_or(0, |u| u.usage_count),
+                manifest: Some(model.path.clone()),
+            }
+        })
+        .collect();
+
+    models.extend(
+        model_usage
+            .values()
+            .filter(|u| u.status == Status::Deleted)
+            .map(|u| SnapshotModel {
+                name: u.name.clone(),
+                digest: u.digest.clone(),
+                size: 0,
+                status: Status::Deleted,
+                last_used: Some(u.last_used),
+                usage_count: u.usage_count,
+                manifest: None,
+            }),
+    );
+
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    models
+}
+
+/// Appends a snapshot of `models` and returns its id.
+pub fn record(conn: &mut Connection, models: &[SnapshotModel]) -> Result<i64> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO snapshots (taken_at) VALUES (?1)",
+        params![Local::now().to_rfc3339()],
+    )?;
+    let id = tx.last_insert_rowid();
+
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO snapshot_models (snapshot_id, name, digest, size, status, last_used, usage_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for model in models {
+            insert.execute(params![
+                id,
+                model.name,
+                model.digest,
+                model.size as i64,
+                model.status.as_str(),
+                model.last_used.map(|t| t.to_rfc3339()),
+                model.usage_count as i64,
+            ])?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(id)
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Local>> {
+    Ok(DateTime::parse_from_rfc3339(s)
+        .with_context(|| format!("Invalid timestamp '{}' in history database", s))?
+        .with_timezone(&Local))
+}
+
+/// Every snapshot's id and when it was taken, oldest first.
+pub fn snapshot_times(conn: &Connection) -> Result<Vec<(i64, DateTime<Local>)>> {
+    let mut query = conn.prepare("SELECT id, taken_at FROM snapshots ORDER BY id")?;
+    let rows = query.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+    let mut times = Vec::new();
+    for row in rows {
+        let (id, taken_at) = row?;
+        times.push((id, parse_time(&taken_at)?));
+    }
+    Ok(times)
+}
+
+/// Ids of the most recent `count` snapshots, newest first.
+pub fn latest_ids(conn: &Connection, count: usize) -> Result<Vec<i64>> {
+    let mut query = conn.prepare("SELECT id FROM snapshots ORDER BY id DESC LIMIT ?1")?;
+    let ids = query
+        .query_map(params![count as i64], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(ids)
+}
+
+pub fn load(conn: &Connection, id: i64) -> Result<Snapshot> {
+    let taken_at: String = conn
+        .query_row("SELECT taken_at FROM snapshots WHERE id = ?1", params![id], |row| row.get(0))
+        .with_context(|| format!("No snapshot with id {}", id))?;
+
+    let mut query = conn.prepare(
+        "SELECT name, digest, size, status, last_used, usage_count
+         FROM snapshot_models WHERE snapshot_id = ?1 ORDER BY name",
+    )?;
+    let rows = query.query_map(params![id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, i64>(5)?,
+        ))
+    })?;
+
+    let mut models = Vec::new();
+    for row in rows {
+        let (name, digest, size, status, last_used, usage_count) = row?;
+        models.push(SnapshotModel {
+            name,
+            digest,
+            size: size as u64,
+            status: Status::parse(&status),
+            last_used: last_used.as_deref().map(parse_time).transpose()?,
+            usage_count: usage_count as usize,
+            manifest: None,
+        });
+    }
+
+    Ok(Snapshot {
+        taken_at: parse_time(&taken_at)?,
+        host: None,
+        models,
+    })
+}
+
+/// Records a model load seen by `source` ("log" or "api"), ignoring one already recorded.
+/// Returns whether the event was new.
+pub fn record_event(
+    conn: &Connection,
+    digest: &str,
+    name: Option<&str>,
+    loaded_at: DateTime<Local>,
+    source: &str,
+) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO usage_events (digest, name, loaded_at, source) VALUES (?1, ?2, ?3, ?4)",
+        params![digest, name, loaded_at.to_rfc3339(), source],
+    )?;
+    Ok(inserted > 0)
+}
+
+/// How far into `path` the daemon has already read.
+pub fn log_offset(conn: &Connection, path: &Path) -> Result<u64> {
+    let offset: Option<i64> = conn
+        .query_row(
+            "SELECT offset FROM log_offsets WHERE path = ?1",
+            params![path.to_string_lossy()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(offset.unwrap_or(0) as u64)
+}
+
+pub fn set_log_offset(conn: &Connection, path: &Path, offset: u64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO log_offsets (path, offset) VALUES (?1, ?2)
+         ON CONFLICT(path) DO UPDATE SET offset = excluded.offset",
+        params![path.to_string_lossy(), offset as i64],
+    )?;
+    Ok(())
+}
+
+/// All-time usage of one digest according to the history database.
+pub struct RecordedUsage {
+    pub count: usize,
+    /// Distinct calendar days with at least one load.
+    pub days_used: usize,
+    pub first_used: DateTime<Local>,
+    pub last_used: DateTime<Local>,
+}
+
+/// Records model loads found in the logs. Each is keyed by digest and timestamp,
+/// so loads still present in the logs on the next run aren't counted twice.
+pub fn record_loads<'a>(
+    conn: &mut Connection,
+    loads: impl Iterator<Item = (&'a str, Option<&'a str>, DateTime<Local>)>,
+) -> Result<usize> {
+    let tx = conn.transaction()?;
+    let mut recorded = 0;
+    for (digest, name, loaded_at) in loads {
+        if record_event(&tx, digest, name, loaded_at, "log")? {
+            recorded += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(recorded)
+}
+
+/// All-time usage per digest. Loads seen in the logs are preferred; loads only
+/// observed through the API by the daemon count where no log ever mentioned them.
+pub fn recorded_usage(conn: &Connection) -> Result<HashMap<String, RecordedUsage>> {
+    let mut query = conn.prepare("SELECT digest, source, loaded_at FROM usage_events")?;
+    let rows = query.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+    })?;
+
+    let mut by_source: HashMap<(String, bool), RecordedUsage> = HashMap::new();
+    let mut days = HashSet::new();
+    for row in rows {
+        let (digest, source, loaded_at) = row?;
+        let loaded_at = parse_time(&loaded_at)?;
+        let key = (digest, source == "log");
+        let new_day = days.insert((key.clone(), loaded_at.date_naive()));
+        let entry = by_source
+            .entry(key)
+            .or_insert(RecordedUsage { count: 0, days_used: 0, first_used: loaded_at, last_used: loaded_at });
+        entry.count += 1;
+        if new_day {
+            entry.days_used += 1;
+        }
+        entry.first_used = entry.first_used.min(loaded_at);
+        entry.last_used = entry.last_used.max(loaded_at);
+    }
+
+    let mut usage = HashMap::new();
+    for ((digest, from_log), recorded) in by_source {
+        if from_log || !usage.contains_key(&digest) {
+            usage.insert(digest, recorded);
+        }
+    }
+    Ok(usage)
+}
+
+/// Loads per digest since `since`, preferring log events over API events the same
+/// way [`recorded_usage`] does.
+pub fn usage_since(conn: &Connection, since: DateTime<Local>) -> Result<HashMap<String, usize>> {
+    let mut query = conn.prepare("SELECT digest, source, loaded_at FROM usage_events")?;
+    let rows = query.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+    })?;
+
+    let mut by_source: HashMap<(String, bool), usize> = HashMap::new();
+    for row in rows {
+        let (digest, source, loaded_at) = row?;
+        if parse_time(&loaded_at)? >= since {
+            *by_source.entry((digest, source == "log")).or_default() += 1;
+        }
+    }
+
+    let mut usage = HashMap::new();
+    for ((digest, from_log), count) in by_source {
+        if from_log || !usage.contains_key(&digest) {
+            usage.insert(digest, count);
+        }
+    }
+    Ok(usage)
+}
+
+/// The newest snapshot taken at or before `at`, if any.
+pub fn snapshot_before(conn: &Connection, at: DateTime<Local>) -> Result<Option<Snapshot>> {
+    let mut query = conn.prepare("SELECT id, taken_at FROM snapshots ORDER BY id DESC")?;
+    let rows = query.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+    for row in rows {
+        let (id, taken_at) = row?;
+        if parse_time(&taken_at)? <= at {
+            return load(conn, id).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Loads per day over the last `days` days for each digest, oldest day first, with
+/// today's loads in the last slot. Log events are preferred over API events the same
+/// way [`recorded_usage`] does.
+pub fn daily_usage(conn: &Connection, days: usize) -> Result<HashMap<String, Vec<u64>>> {
+    let mut query = conn.prepare("SELECT digest, source, loaded_at FROM usage_events")?;
+    let rows = query.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+    })?;
+
+    let today = Local::now().date_naive();
+    let mut by_source: HashMap<(String, bool), Vec<u64>> = HashMap::new();
+    for row in rows {
+        let (digest, source, loaded_at) = row?;
+        let age = (today - parse_time(&loaded_at)?.date_naive()).num_days();
+        if (0..days as i64).contains(&age) {
+            let series = by_source.entry((digest, source == "log")).or_insert_with(|| vec![0; days]);
+            series[days - 1 - age as usize] += 1;
+        }
+    }
+
+    let mut usage = HashMap::new();
+    for ((digest, from_log), series) in by_source {
+        if from_log || !usage.contains_key(&digest) {
+            usage.insert(digest, series);
+        }
+    }
+    Ok(usage)
+}
+
+/// Every recorded load with the source that saw it.
+pub fn usage_events(conn: &Connection) -> Result<Vec<(String, String, DateTime<Local>)>> {
+    let mut query = conn.prepare("SELECT digest, source, loaded_at FROM usage_events")?;
+    let rows = query.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+    })?;
+
+    let mut events = Vec::new();
+    for row in rows {
+        let (digest, source, loaded_at) = row?;
+        events.push((digest, source, parse_time(&loaded_at)?));
+    }
+    Ok(events)
+}
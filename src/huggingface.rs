@@ -0,0 +1,86 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tracing::debug;
+
+use crate::report::ReportRow;
+
+/// The Hugging Face hub cache, where `HF_HUB_CACHE` or `HF_HOME` say or in
+/// `~/.cache/huggingface/hub`, as the `huggingface_hub` library picks it.
+pub fn default_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("HF_HUB_CACHE") {
+        return PathBuf::from(dir);
+    }
+    let home = match env::var_os("HF_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".cache").join("huggingface"),
+    };
+    home.join("hub")
+}
+
+/// `models--org--name` as `org/name`, with datasets and spaces marked as such.
+fn repo_name(folder: &str) -> Option<String> {
+    let (kind, rest) = folder.split_once("--")?;
+    let name = rest.replace("--", "/");
+    match kind {
+        "models" => Some(name),
+        "datasets" => Some(format!("{} (dataset)", name)),
+        "spaces" => Some(format!("{} (space)", name)),
+        _ => None,
+    }
+}
+
+/// Lists the repositories in the hub cache, largest first. A repository's files
+/// live in its `blobs` folder once however many revisions link to them, so that's
+/// what is counted, and it was last used when a blob was last read.
+pub fn scan(dir: &Path) -> Result<Vec<ReportRow>> {
+    if !dir.is_dir() {
+        bail!("Hugging Face cache {} not found", dir.display());
+    }
+
+    let mut rows = Vec::new();
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let Some(name) = repo_name(&entry.file_name().to_string_lossy()) else {
+            continue;
+        };
+        let mut size = 0;
+        let mut accessed: Option<SystemTime> = None;
+        for blob in fs::read_dir(entry.path().join("blobs")).into_iter().flatten().filter_map(Result::ok) {
+            match blob.metadata() {
+                Ok(metadata) => {
+                    size += metadata.len();
+                    accessed = accessed.max(metadata.accessed().ok());
+                }
+                Err(err) => debug!(path = %blob.path().display(), %err, "skipping unreadable blob"),
+            }
+        }
+        rows.push(ReportRow {
+            name,
+            tags: Vec::new(),
+            digest: String::new(),
+            last_used: accessed.map(DateTime::<Local>::from),
+            usage_count: 0,
+            days_used: 0,
+            window_count: None,
+            all_time: None,
+            trend: None,
+            embeddings: None,
+            tool_requests: None,
+            structured_requests: None,
+            clients: None,
+            failures: None,
+            oom_failures: None,
+            kind: None,
+            license: None,
+            created: None,
+            pulled: None,
+            size,
+        });
+    }
+    rows.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    Ok(rows)
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::{fmt::Write, time::Duration};
+
+use crate::history::{SnapshotModel, Status};
+
+/// Escapes a tag value for the line protocol.
+fn escape_tag(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+/// Renders one `ollama_model` point per tag, all stamped with the current time.
+pub fn render(models: &[SnapshotModel]) -> String {
+    let timestamp = Local::now().timestamp_nanos_opt().unwrap_or_default();
+    let mut out = String::new();
+
+    for m in models {
+        write!(
+            out,
+            "ollama_model,model={},status={} usage_count={}i",
+            escape_tag(&m.name),
+            m.status.as_str(),
+            m.usage_count
+        )
+        .unwrap();
+        if m.status != Status::Deleted {
+            write!(out, ",size_bytes={}i", m.size).unwrap();
+        }
+        if let Some(last_used) = m.last_used {
+            write!(out, ",last_used={}i", last_used.timestamp()).unwrap();
+        }
+        writeln!(out, " {}", timestamp).unwrap();
+    }
+
+    out
+}
+
+/// Writes `body` to an InfluxDB write endpoint, e.g.
+/// `http://localhost:8086/api/v2/write?org=lab&bucket=ollama` or `http://localhost:8086/write?db=ollama`.
+pub fn push(url: &str, token: Option<&str>, body: &str) -> Result<()> {
+    let mut request = ureq::post(url)
+        .timeout(Duration::from_secs(15))
+        .set("Content-Type", "text/plain; charset=utf-8");
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Token {}", token));
+    }
+
+    request
+        .send_string(body)
+        .with_context(|| format!("Failed to write to {}", url))?;
+    Ok(())
+}
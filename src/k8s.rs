@@ -0,0 +1,102 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+};
+use tracing::debug;
+
+/// Pods carrying this label are taken to be Ollama's, as the community Helm chart
+/// labels them.
+pub const DEFAULT_SELECTOR: &str = "app.kubernetes.io/name=ollama";
+
+/// Runs kubectl with `args` and returns what it printed.
+fn kubectl(args: &[&str]) -> Result<String> {
+    let output = Command::new("kubectl")
+        .args(args)
+        .output()
+        .context("Failed to run kubectl; is it installed and on the PATH?")?;
+    if !output.status.success() {
+        bail!("kubectl {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The Ollama pods in `namespace`.
+pub fn pods(namespace: &str, selector: &str) -> Result<Vec<String>> {
+    let names = kubectl(&["get", "pods", "-n", namespace, "-l", selector, "-o", "jsonpath={.items[*].metadata.name}"])?;
+    let pods: Vec<String> = names.split_whitespace().map(str::to_string).collect();
+    if pods.is_empty() {
+        bail!("No pods in namespace {} match {}; pass --k8s-selector with the label your Ollama pods have", namespace, selector);
+    }
+    Ok(pods)
+}
+
+/// Where the logs fetched for `namespace` are kept while the report is made.
+pub fn log_dir(namespace: &str) -> PathBuf {
+    env::temp_dir().join(format!("omar-k8s-{}", namespace))
+}
+
+/// Saves the logs of every Ollama pod into `dir` as `server-<pod>.log`, so they're
+/// read like any other server logs. The logs of a pod's previous container are
+/// saved too when it has restarted, since the loads before the restart are in them.
+pub fn collect_logs(namespace: &str, pods: &[String], dir: &Path) -> Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir).with_context(|| format!("Failed to clear {}", dir.display()))?;
+    }
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    for pod in pods {
+        let logs = kubectl(&["logs", "-n", namespace, pod, "--all-containers"])?;
+        fs::write(dir.join(format!("server-{}.log", pod)), logs)?;
+        match kubectl(&["logs", "-n", namespace, pod, "--all-containers", "--previous"]) {
+            Ok(logs) => fs::write(dir.join(format!("server-{}-previous.log", pod)), logs)?,
+            Err(err) => debug!(pod, %err, "no logs from a previous container"),
+        }
+    }
+    Ok(())
+}
+
+/// A `kubectl port-forward` to a pod's Ollama API, stopped when dropped.
+pub struct PortForward {
+    child: Child,
+}
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Forwards a free local port to Ollama's API in `pod` and points `OLLAMA_HOST` at
+/// it, so the installed models can be listed when the model volume isn't mounted.
+pub fn port_forward(namespace: &str, pod: &str) -> Result<PortForward> {
+    let child = Command::new("kubectl")
+        .args(["port-forward", "-n", namespace, &format!("pod/{}", pod), ":11434"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run kubectl; is it installed and on the PATH?")?;
+    let mut forward = PortForward { child };
+
+    // kubectl prints "Forwarding from 127.0.0.1:PORT -> 11434" once it's listening
+    let mut stdout = BufReader::new(forward.child.stdout.take().expect("stdout is piped"));
+    let mut line = String::new();
+    stdout.read_line(&mut line)?;
+    // It goes on to print a line per connection, and dies if no one reads them
+    thread::spawn(move || io::copy(&mut stdout, &mut io::sink()));
+    let port = line
+        .split(" -> ")
+        .next()
+        .and_then(|from| from.rsplit(':').next())
+        .map(str::trim)
+        .filter(|port| port.parse::<u16>().is_ok());
+    let Some(port) = port else {
+        bail!("kubectl port-forward to {} didn't start", pod);
+    };
+    env::set_var("OLLAMA_HOST", format!("http://127.0.0.1:{}", port));
+    Ok(forward)
+}
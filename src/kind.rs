@@ -0,0 +1,111 @@
+//! What a model is for, for the report's Type column, `--type` and
+//! `--group-by type`: turning text into vectors, reading images, writing code or
+//! chatting. Worked out from the layers in its manifest and the architectures in
+//! its config blob, and failing those from its name.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::{read_manifest, report::ReportRow, InstalledModel, ModelConfig};
+
+/// Media type of the image encoder vision models carry next to their weights.
+const PROJECTOR: &str = "application/vnd.ollama.image.projector";
+
+/// Architectures that only produce embeddings.
+pub(crate) const EMBEDDING_FAMILIES: [&str; 5] = ["bert", "nomic-bert", "nomic-bert-moe", "jina-bert-v2", "xlm-roberta"];
+
+/// Architectures that read images, alone or next to a language model's.
+const VISION_FAMILIES: [&str; 5] = ["clip", "mllama", "qwen2vl", "qwen25vl", "llava"];
+
+/// Words in a name that give its kind away, checked in this order.
+const NAME_HINTS: [(&str, ModelKind); 11] = [
+    ("embed", ModelKind::Embedding),
+    ("minilm", ModelKind::Embedding),
+    ("bge", ModelKind::Embedding),
+    ("llava", ModelKind::Vision),
+    ("vision", ModelKind::Vision),
+    ("moondream", ModelKind::Vision),
+    ("minicpm-v", ModelKind::Vision),
+    ("-vl", ModelKind::Vision),
+    ("code", ModelKind::Code),
+    ("devstral", ModelKind::Code),
+    ("coder", ModelKind::Code),
+];
+
+/// What a model is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelKind {
+    /// General text generation
+    Chat,
+    /// Text to vectors, for search and retrieval
+    Embedding,
+    /// Takes images as well as text
+    Vision,
+    /// Tuned for writing code
+    Code,
+}
+
+impl ModelKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModelKind::Chat => "chat",
+            ModelKind::Embedding => "embedding",
+            ModelKind::Vision => "vision",
+            ModelKind::Code => "code",
+        }
+    }
+}
+
+/// The kind a name suggests, if any.
+fn from_name(name: &str) -> Option<ModelKind> {
+    let name = name.to_ascii_lowercase();
+    NAME_HINTS.iter().find(|(hint, _)| name.contains(hint)).map(|(_, kind)| *kind)
+}
+
+/// The kind of an installed model. The architectures outrank the name, except
+/// that a coding model is a language model like any other to them.
+fn of(model: &InstalledModel) -> ModelKind {
+    let manifest = read_manifest(&model.path).ok();
+    let families = manifest
+        .as_ref()
+        .and_then(|manifest| ModelConfig::read_from(model, manifest))
+        .map(|config| config.model_families.into_iter().chain(config.model_family).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let projector = manifest.iter().flat_map(|manifest| &manifest.layers).any(|layer| layer.media_type == PROJECTOR);
+
+    if families.iter().any(|family| EMBEDDING_FAMILIES.contains(&family.as_str())) {
+        ModelKind::Embedding
+    } else if projector || families.iter().any(|family| VISION_FAMILIES.contains(&family.as_str())) {
+        ModelKind::Vision
+    } else {
+        from_name(&model.name).unwrap_or(ModelKind::Chat)
+    }
+}
+
+/// Kinds of the installed models, by digest.
+pub struct Lookup {
+    by_digest: HashMap<String, ModelKind>,
+}
+
+impl Lookup {
+    /// Reads the manifest and config blob of one tag of each digest.
+    pub fn new(installed: &[InstalledModel]) -> Lookup {
+        let mut by_digest = HashMap::new();
+        for model in installed {
+            by_digest.entry(model.digest.clone()).or_insert_with(|| of(model));
+        }
+        Lookup { by_digest }
+    }
+
+    /// The kind of a row's model. Deleted models are named after their digest and
+    /// can't be told apart; files from other tools only have their names to go on.
+    pub fn kind(&self, row: &ReportRow) -> Option<ModelKind> {
+        if let Some(kind) = self.by_digest.get(&row.digest) {
+            return Some(*kind);
+        }
+        let named_by_digest = row.digest.get(..8).is_some_and(|short| row.name.starts_with(short));
+        (!named_by_digest).then(|| from_name(&row.name).unwrap_or(ModelKind::Chat))
+    }
+}
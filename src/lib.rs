@@ -0,0 +1,1516 @@
+//! Reports which Ollama models are installed and how they've been used, from the
+//! manifests in the models directory and the loads recorded in the server logs.
+//!
+//! [`scan_models`] finds installed tags and [`parse_usage`] tallies their loads; the
+//! `omar` binary is a command-line front end over these and the modules below.
+
+pub mod adapter;
+mod api;
+pub mod backup;
+mod cache;
+pub mod chart;
+pub mod check;
+pub mod compare;
+pub mod config;
+pub mod daemon;
+pub mod dedupe;
+pub mod desktop;
+pub mod diff;
+pub mod digest;
+pub mod docker;
+pub mod doctor;
+mod error;
+pub mod exporter;
+pub mod gguf;
+pub mod graph;
+pub mod growth;
+pub mod history;
+pub mod huggingface;
+pub mod influx;
+pub mod k8s;
+pub mod kind;
+pub mod license;
+pub mod lmstudio;
+pub mod mcp;
+pub mod merge;
+pub mod metrics;
+pub mod plugin;
+pub mod progress;
+pub mod notify;
+pub mod openwebui;
+pub mod pairs;
+pub mod params;
+pub mod paths;
+mod prune;
+pub mod proxy;
+pub mod recommend;
+pub mod report;
+pub mod restore;
+pub mod schema;
+pub mod serve;
+pub mod show;
+pub mod source;
+pub mod ssh;
+pub mod summarize;
+mod table;
+pub mod timing;
+pub mod tui;
+pub mod users;
+pub mod wsl;
+
+use chrono::{DateTime, Local, LocalResult, NaiveDateTime, TimeZone};
+use glob::glob;
+use memchr::{memchr, memmem};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Instant,
+};
+use tracing::{debug, info, trace};
+
+pub use api::RegistryTls;
+pub use error::{OmarError, Result, Warning};
+pub use paths::Paths;
+use source::{UsageEvent, UsageSource};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelLayer {
+    #[serde(rename = "mediaType")]
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub config: Option<ModelLayer>,
+    pub layers: Vec<ModelLayer>,
+}
+
+/// A single tag found in the manifests tree, with every blob it references.
+#[derive(Debug, Clone)]
+pub struct InstalledModel {
+    pub name: String,
+    pub path: PathBuf,
+    pub digest: String,
+    pub size: u64,
+    pub blobs: Vec<(String, u64)>,
+}
+
+impl InstalledModel {
+    /// The manifest's modification time, which is when the tag was pulled or created.
+    pub fn pulled_at(&self) -> Option<DateTime<Local>> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok().map(Into::into)
+    }
+
+    /// The models directory the tag is in, from where its manifest is below
+    /// `manifests/<registry>/<namespace>/<model>/<tag>`. Tags from the API have no
+    /// manifest path and are taken to be in the one in use.
+    pub fn models_dir(&self) -> PathBuf {
+        self.path.ancestors().nth(5).map(Path::to_path_buf).unwrap_or_else(get_model_dir)
+    }
+}
+
+/// Where a model stands: loaded at some point while installed, installed but
+/// never seen loaded, or seen loaded but no longer installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Active,
+    Unlogged,
+    Deleted,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Active => "active",
+            Status::Unlogged => "unlogged",
+            Status::Deleted => "deleted",
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Status {
+        match s {
+            "active" => Status::Active,
+            "deleted" => Status::Deleted,
+            _ => Status::Unlogged,
+        }
+    }
+}
+
+/// Days in each of the two windows a [`Trend`] compares: the most recent ones and
+/// the same number before them.
+pub const TREND_DAYS: i64 = 14;
+
+/// Whether a model is loaded more or less often than it used to be, from its loads
+/// in the last [`TREND_DAYS`] days against the [`TREND_DAYS`] days before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Up,
+    Down,
+    Flat,
+}
+
+impl Trend {
+    /// Counts within a quarter of each other are flat, so a single extra load
+    /// doesn't flip a busy model's trend.
+    pub fn from_counts(recent: usize, prior: usize) -> Trend {
+        if recent * 4 > prior * 5 {
+            Trend::Up
+        } else if recent * 4 < prior * 3 {
+            Trend::Down
+        } else {
+            Trend::Flat
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Trend::Up => "up",
+            Trend::Down => "down",
+            Trend::Flat => "flat",
+        }
+    }
+
+    /// The arrow shown in tables.
+    pub fn marker(self) -> &'static str {
+        match self {
+            Trend::Up => "↑",
+            Trend::Down => "↓",
+            Trend::Flat => "→",
+        }
+    }
+}
+
+/// How often a model was loaded according to the server logs. Tags sharing a
+/// digest are reported together under their joined names.
+#[derive(Debug)]
+pub struct ModelUsage {
+    /// `tags` joined, or a shortened digest for a deleted model. See [`display_name`].
+    pub name: String,
+    /// The installed tags the usage is credited to; none for a deleted model.
+    pub tags: Vec<String>,
+    pub digest: String,
+    /// [`Status::Active`] for an installed digest, [`Status::Deleted`] otherwise.
+    pub status: Status,
+    pub last_used: DateTime<Local>,
+    pub usage_count: usize,
+    /// Loads recorded in the history database across every log seen so far,
+    /// or `None` when history isn't being kept.
+    pub recorded_count: Option<usize>,
+    /// Distinct calendar days with at least one load, so a burst of loads in one
+    /// afternoon counts once.
+    pub days_used: usize,
+    /// Loads within the `--window`, when one was given. See [`apply_window`].
+    pub window_count: Option<usize>,
+    /// When each load counted here happened, in no particular order.
+    pub loads: Vec<DateTime<Local>>,
+    /// Loads in the last [`TREND_DAYS`] days.
+    pub recent_count: usize,
+    /// Loads in the [`TREND_DAYS`] days before those.
+    pub prior_count: usize,
+    /// Embedding requests the server logs credit the model with. A model kept
+    /// loaded to serve embeddings is loaded rarely, so loads alone undersell it.
+    /// See [`LogCache::add_requests`].
+    pub embeddings: usize,
+    /// When the last of those requests was made.
+    pub last_embedding: Option<DateTime<Local>>,
+    /// Chat and generate requests that offered the model tools to call, as far as
+    /// the prompts in a debug log show. See [`LogCache::add_requests`].
+    pub tool_requests: usize,
+    /// Requests for JSON or output following a schema, as far as a debug log shows.
+    /// See [`LogCache::add_requests`].
+    pub structured_requests: usize,
+    /// The addresses the access log shows requests for the model coming from, and
+    /// when each last made one. See [`LogCache::add_requests`] and [`apply_window`].
+    pub clients: HashMap<String, DateTime<Local>>,
+    /// Times the model failed to load or its runner fell over, as the server logs
+    /// show, and how many of those ran out of memory. See [`LogCache::add_requests`].
+    pub failures: usize,
+    pub oom_failures: usize,
+    pub size: u64,
+}
+
+impl ModelUsage {
+    /// Usage of `digest` under `tags` with no loads counted yet, last used at `last_used`.
+    pub fn new(digest: &str, tags: Vec<String>, status: Status, size: u64, last_used: DateTime<Local>) -> ModelUsage {
+        ModelUsage {
+            name: display_name(digest, &tags),
+            tags,
+            digest: digest.to_string(),
+            status,
+            last_used,
+            usage_count: 0,
+            recorded_count: None,
+            days_used: 0,
+            window_count: None,
+            loads: Vec::new(),
+            recent_count: 0,
+            prior_count: 0,
+            embeddings: 0,
+            last_embedding: None,
+            tool_requests: 0,
+            structured_requests: 0,
+            clients: HashMap::new(),
+            failures: 0,
+            oom_failures: 0,
+            size,
+        }
+    }
+
+    pub fn trend(&self) -> Trend {
+        Trend::from_counts(self.recent_count, self.prior_count)
+    }
+}
+
+pub fn get_model_dir() -> PathBuf {
+    Paths::current().model_dir
+}
+
+pub fn get_log_paths() -> Vec<PathBuf> {
+    Paths::current().log_paths()
+}
+
+fn parse_manifest_path(path: &Path) -> Option<String> {
+    let components: Vec<_> = path.components().collect();
+    let len = components.len();
+    if len >= 4 {
+        let _registry = components[len - 4].as_os_str().to_string_lossy();
+        let user = components[len - 3].as_os_str().to_string_lossy();
+        let model = components[len - 2].as_os_str().to_string_lossy();
+        let tag = path.file_name()?.to_string_lossy();
+
+        let prefix = if user == "library" {
+            String::new()
+        } else {
+            format!("{}/", user)
+        };
+
+        Some(format!("{}{}:{}", prefix, model, tag))
+    } else {
+        None
+    }
+}
+
+fn strip_digest(digest: &str) -> String {
+    digest.strip_prefix("sha256:").unwrap_or(digest).to_string()
+}
+
+/// Reads and parses one manifest file.
+pub fn read_manifest(path: &Path) -> Result<ModelManifest> {
+    let content = fs::read_to_string(path).map_err(|source| OmarError::ManifestUnreadable {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    serde_json::from_str(&content).map_err(|source| OmarError::ManifestParse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Lists the installed models through the Ollama HTTP API instead of the models
+/// directory. Manifest paths are left empty.
+pub fn scan_models_from_api() -> anyhow::Result<Vec<InstalledModel>> {
+    api::installed_models()
+}
+
+/// Finds every tag in the models directory that has a model layer, skipping any
+/// manifest that can't be read. Use [`scan_models_with_warnings`] to find out which.
+pub fn scan_models() -> Result<Vec<InstalledModel>> {
+    scan_models_with_warnings().map(|(models, _)| models)
+}
+
+/// Like [`scan_models`], also returning a warning for each manifest that was
+/// skipped because it couldn't be read or parsed. Hidden files are skipped
+/// without one. Only a missing models directory, or one without `manifests` and
+/// `blobs` folders, is an error.
+///
+/// The directories listed after the first in `OLLAMA_MODELS` are scanned too. One
+/// of those that's missing, such as a drive that isn't attached, is a warning.
+pub fn scan_models_with_warnings() -> Result<(Vec<InstalledModel>, Vec<Warning>)> {
+    let paths = Paths::current();
+    let (mut models, mut warnings) = scan_models_in(paths.model_dir)?;
+    for dir in paths.extra_model_dirs {
+        match scan_models_in(dir.clone()) {
+            Ok((more, skipped)) => {
+                models.extend(more);
+                warnings.extend(skipped);
+            }
+            Err(err) => warnings.push(Warning { path: dir, reason: err.to_string() }),
+        }
+    }
+    Ok((models, warnings))
+}
+
+/// Like [`scan_models_with_warnings`], for a models directory other than the one in
+/// use, such as the store of an Ollama on the other side of WSL.
+pub fn scan_models_in(model_dir: PathBuf) -> Result<(Vec<InstalledModel>, Vec<Warning>)> {
+    let mut models = Vec::new();
+    let mut warnings = Vec::new();
+
+    debug!(dir = %model_dir.display(), "looking for models");
+    if !model_dir.is_dir() {
+        return Err(OmarError::ModelsDirNotFound(model_dir));
+    }
+    for missing in ["manifests", "blobs"] {
+        if !model_dir.join(missing).is_dir() {
+            return Err(OmarError::NotAModelsDir { path: model_dir, missing });
+        }
+    }
+    let manifest_dir = model_dir.join("manifests");
+    let started = Instant::now();
+    let mut cache = cache::ManifestCache::open(Paths::current().manifest_cache);
+
+    let entries: Vec<_> = glob(&paths::glob_pattern(&manifest_dir, "**/*"))?.collect();
+    let progress = progress::manifests(entries.len() as u64);
+    let found = parallel_map(&entries, |entry| {
+        progress.inc(1);
+        let path = entry.as_ref().ok()?;
+        let metadata = fs::metadata(path).ok().filter(|metadata| metadata.is_file())?;
+        if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+            debug!(path = %path.display(), "skipping hidden file");
+            return None;
+        }
+        progress.set_message(path.strip_prefix(&manifest_dir).unwrap_or(path).display().to_string());
+        let manifest = cache.read(path, &metadata);
+        Some((metadata, manifest))
+    });
+
+    let mut files = 0;
+    for (entry, found) in entries.into_iter().zip(found) {
+        let path = match entry {
+            Ok(path) => path,
+            Err(err) => {
+                let err = OmarError::ManifestUnreadable {
+                    path: err.path().to_path_buf(),
+                    source: err.into_error(),
+                };
+                warnings.extend(Warning::from_error(&err));
+                continue;
+            }
+        };
+        let Some((metadata, manifest)) = found else {
+            continue;
+        };
+        files += 1;
+        let manifest = match manifest {
+            Ok(manifest) => manifest,
+            Err(err) => match Warning::from_error(&err) {
+                Some(warning) => {
+                    warnings.push(warning);
+                    continue;
+                }
+                None => return Err(err),
+            },
+        };
+        cache.insert(&path, &metadata, &manifest);
+        let Some(model_layer) = manifest
+            .layers
+            .iter()
+            .find(|l| l.media_type == "application/vnd.ollama.image.model")
+        else {
+            debug!(path = %path.display(), "skipping manifest without a model layer");
+            continue;
+        };
+        let Some(model_name) = parse_manifest_path(&path) else {
+            debug!(path = %path.display(), "skipping manifest outside a registry/namespace/model/tag path");
+            continue;
+        };
+
+        let blobs = manifest
+            .config
+            .iter()
+            .chain(manifest.layers.iter())
+            .map(|l| (strip_digest(&l.digest), l.size))
+            .collect();
+        let digest = strip_digest(&model_layer.digest);
+        debug!(path = %path.display(), name = %model_name, %digest, size = model_layer.size, "found model");
+
+        models.push(InstalledModel {
+            name: model_name,
+            path: path.clone(),
+            digest,
+            size: model_layer.size,
+            blobs,
+        });
+    }
+
+    progress.finish_and_clear();
+    cache.save(&manifest_dir);
+    timing::record(|| format!("Manifests in {} ({} files)", model_dir.display(), files), started.elapsed());
+    info!(count = models.len(), skipped = warnings.len(), "finished scanning manifests");
+    Ok((models, warnings))
+}
+
+/// Calls `f` on every item on a pool of threads and returns the results in the
+/// order of `items`. Reading manifests is mostly waiting on the disk, a network
+/// share especially, so there are more threads than cores.
+fn parallel_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+    parallel_map_on(items, (cores * 4).clamp(1, 32), f)
+}
+
+/// [`parallel_map`] on at most `threads` threads.
+pub(crate) fn parallel_map_on<T: Sync, R: Send>(items: &[T], threads: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = threads.max(1).min(items.len());
+    let next = AtomicUsize::new(0);
+    let work = || {
+        let mut done = Vec::new();
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(item) = items.get(index) else {
+                return done;
+            };
+            done.push((index, f(item)));
+        }
+    };
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads).map(|_| scope.spawn(work)).collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+    results.sort_unstable_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// The tags, size and status usage of `digest` is reported under: the installed
+/// tags with that digest, or none once it's deleted.
+pub(crate) fn resolve_digest(
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+    digest: &str,
+) -> (Vec<String>, u64, Status) {
+    match hash_to_name_size.get(digest) {
+        Some((tags, size)) => (tags.clone(), *size, Status::Active),
+        None => (Vec::new(), 0, Status::Deleted),
+    }
+}
+
+/// The name usage of `digest` is shown under: its `tags` joined, or a shortened
+/// digest when there are none.
+pub fn display_name(digest: &str, tags: &[String]) -> String {
+    if tags.is_empty() {
+        format!("{}...-deleted", &digest[..8.min(digest.len())])
+    } else {
+        tags.join(", ")
+    }
+}
+
+/// Which tag usage is reported under when several share a digest, as they do after
+/// `ollama cp`. The logs only record the weights that were loaded, so they can't
+/// tell the tags apart.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Attribution {
+    /// Under all of the tags, joined
+    #[default]
+    AllTags,
+    /// Under the first tag in manifest order
+    FirstTag,
+    /// Under the tag whose manifest was written last, usually the newest copy
+    NewestManifest,
+}
+
+/// Groups installed tags by model digest, with the size of the weights.
+pub fn index_by_digest(models: &[InstalledModel]) -> HashMap<String, (Vec<String>, u64)> {
+    let mut by_digest: HashMap<&str, Vec<&InstalledModel>> = HashMap::new();
+    for model in models {
+        by_digest.entry(&model.digest).or_default().push(model);
+    }
+    by_digest
+        .into_iter()
+        .map(|(digest, tags)| (digest.to_string(), (tag_names(&tags), tags[tags.len() - 1].size)))
+        .collect()
+}
+
+/// The names of `tags`, each once: the same tag can be in more than one models
+/// directory.
+fn tag_names(tags: &[&InstalledModel]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.iter().filter(|m| seen.insert(m.name.as_str())).map(|m| m.name.clone()).collect()
+}
+
+/// The tags usage of each installed digest goes under, as `attribution` says.
+/// The other tags sharing the digest stay installed, with no usage of their own.
+pub fn attributed_names(models: &[InstalledModel], attribution: Attribution) -> HashMap<String, Vec<String>> {
+    let mut by_digest: HashMap<&str, Vec<&InstalledModel>> = HashMap::new();
+    for model in models {
+        by_digest.entry(&model.digest).or_default().push(model);
+    }
+
+    by_digest
+        .into_iter()
+        .map(|(digest, tags)| {
+            let names = match attribution {
+                Attribution::AllTags => tag_names(&tags),
+                Attribution::FirstTag => vec![tags[0].name.clone()],
+                Attribution::NewestManifest => {
+                    // Tags from the API have no manifest path and count as oldest
+                    let written = |m: &InstalledModel| fs::metadata(&m.path).and_then(|meta| meta.modified()).ok();
+                    let mut newest = tags[0];
+                    for &tag in &tags[1..] {
+                        if written(tag) > written(newest) {
+                            newest = tag;
+                        }
+                    }
+                    vec![newest.name.clone()]
+                }
+            };
+            if tags.len() > 1 {
+                debug!(digest, tags = tags.len(), ?attribution, ?names, "tags share a digest");
+            }
+            (digest.to_string(), names)
+        })
+        .collect()
+}
+
+/// Puts the usage of each installed digest under its tags from [`attributed_names`].
+pub fn attribute(model_usage: &mut HashMap<String, ModelUsage>, names: &HashMap<String, Vec<String>>) {
+    for (digest, usage) in model_usage.iter_mut() {
+        if let (Status::Active, Some(tags)) = (usage.status, names.get(digest)) {
+            usage.name = display_name(digest, tags);
+            usage.tags = tags.clone();
+        }
+    }
+}
+
+/// How the runner fared for one model, as far as the server log tells.
+#[derive(Debug, Default, Clone)]
+struct RunnerStats {
+    timed_loads: usize,
+    load_seconds: f64,
+    /// Generations whose speed was logged, and their tokens per second summed.
+    timed_evals: usize,
+    tokens_per_second: f64,
+    errors: usize,
+    /// Requests to the embedding endpoints, and when the last was made.
+    embeddings: usize,
+    last_embedding: Option<DateTime<Local>>,
+    /// Prompts logged with tool definitions or tool calls in them.
+    tool_requests: usize,
+    /// Requests logged with a JSON format or the grammar it's enforced with.
+    structured_requests: usize,
+    /// Each address requests came from, and when it last made one.
+    clients: HashMap<String, DateTime<Local>>,
+    /// Loads that failed or runners that crashed, and those that ran out of memory.
+    failures: usize,
+    oom_failures: usize,
+}
+
+/// What a prompt rendered by a model's template has in it when tools were offered
+/// or called, in the formats of the common tool-calling model families.
+const TOOL_MARKERS: [&str; 9] = [
+    "<tools>",
+    "<tool_call>",
+    "<tool_response>",
+    "[AVAILABLE_TOOLS]",
+    "[TOOL_CALLS]",
+    "[TOOL_RESULTS]",
+    "Environment: ipython",
+    "<|python_tag|>",
+    "<｜tool▁calls▁begin｜>",
+];
+
+/// What a logged request has in it when it asked for JSON, plain or following a
+/// schema: the format itself, as an attribute or in a request's JSON, escaped when
+/// it's quoted in another attribute, or the GBNF grammar the runner enforces it with.
+const STRUCTURED_MARKERS: [&str; 8] = [
+    "format=json",
+    "format=\"{",
+    "\"format\":\"json\"",
+    "\"format\":{",
+    "\\\"format\\\":\\\"json\\\"",
+    "\\\"format\\\":{",
+    "json_schema",
+    "root ::=",
+];
+
+/// What the runner or the server logs when a model fails to load or its runner
+/// dies, besides running out of memory.
+const FAILURE_MARKERS: [&str; 6] = [
+    "llama runner process has terminated",
+    "llama runner process no longer running",
+    "error loading model",
+    "failed to load model",
+    "SIGSEGV",
+    "SIGABRT",
+];
+
+/// What the runner logs when it can't get the memory a model needs, from the GPU
+/// or the system.
+const OOM_MARKERS: [&str; 4] = ["out of memory", "unable to allocate", "failed to allocate", "cudaMalloc failed"];
+
+/// The generation speed from a llama.cpp timing line such as
+/// `eval time = 1234.56 ms / 100 runs ( 12.35 ms per token, 81.00 tokens per second)`.
+/// Prompt processing is timed the same way but says nothing about generation.
+fn eval_rate(line: &str) -> Option<f64> {
+    if !line.contains("eval time") || line.contains("prompt eval time") {
+        return None;
+    }
+    let (before, _) = line.split_once(" tokens per second")?;
+    before.split_whitespace().last()?.parse().ok()
+}
+
+/// The parts of a model's config blob worth showing.
+#[derive(Deserialize)]
+pub(crate) struct ModelConfig {
+    pub(crate) model_family: Option<String>,
+    pub(crate) model_type: Option<String>,
+    pub(crate) file_type: Option<String>,
+    /// Every architecture in the model, such as `["llama", "clip"]` for LLaVA.
+    #[serde(default)]
+    pub(crate) model_families: Vec<String>,
+    /// When the model was created, in RFC 3339, where the config records it.
+    #[serde(default)]
+    pub(crate) created: Option<String>,
+}
+
+impl ModelConfig {
+    /// Reads the config blob of `model` from the models directory it's in.
+    pub(crate) fn read(model: &InstalledModel) -> Option<ModelConfig> {
+        Self::read_from(model, &read_manifest(&model.path).ok()?)
+    }
+
+    /// Reads the config blob `manifest`, the manifest of `model`, points to.
+    pub(crate) fn read_from(model: &InstalledModel, manifest: &ModelManifest) -> Option<ModelConfig> {
+        let layer = manifest.config.as_ref()?;
+        let blob = model.models_dir().join("blobs").join(format!("sha256-{}", strip_digest(&layer.digest)));
+        serde_json::from_str(&fs::read_to_string(blob).ok()?).ok()
+    }
+
+    /// [`ModelConfig::created`] in local time, if it's there and parses.
+    pub(crate) fn created_at(&self) -> Option<DateTime<Local>> {
+        let created = DateTime::parse_from_rfc3339(self.created.as_deref()?).ok()?;
+        Some(created.with_timezone(&Local))
+    }
+}
+
+/// The default options `model` was created with, such as `num_ctx`, `temperature`
+/// or `stop`, from the params layer of `manifest`, its manifest. Empty when it has
+/// none or it can't be read.
+pub(crate) fn read_params(model: &InstalledModel, manifest: &ModelManifest) -> BTreeMap<String, serde_json::Value> {
+    let Some(layer) = manifest.layers.iter().find(|l| l.media_type == "application/vnd.ollama.image.params") else {
+        return BTreeMap::new();
+    };
+    let blob = model.models_dir().join("blobs").join(format!("sha256-{}", strip_digest(&layer.digest)));
+    fs::read_to_string(blob).ok().and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default()
+}
+
+/// Follows the timestamps in an Ollama server log and picks out model loads.
+#[derive(Default)]
+struct LogScanner {
+    last_timestamp: Option<DateTime<Local>>,
+    /// The model loaded most recently, which later runner messages are about.
+    current: Option<String>,
+    /// The embedding model loaded most recently, which embedding requests are
+    /// credited to when there's been one, since a chat model loaded in between
+    /// isn't the one serving them.
+    embedder: Option<String>,
+    /// Whether the current model's runner has failed since it was loaded or last
+    /// started, and whether it ran out of memory, so the several lines one failure
+    /// logs count once.
+    failed: Option<bool>,
+    /// Whether the request being served has been counted as asking for structured
+    /// output, since one can log both its format and its grammar.
+    structured: bool,
+    stats: HashMap<String, RunnerStats>,
+}
+
+impl LogScanner {
+    /// Feeds every complete line of `bytes`, adding the loads found to `loads`, and
+    /// returns how many bytes that was, leaving a partially written last line for
+    /// the next pass. Only lines that can matter are decoded: runner messages once a
+    /// model is loaded, loads, and the last timestamp before a load, found by
+    /// searching the raw bytes. `progress` hears how far it got every megabyte.
+    fn scan_bytes(
+        &mut self,
+        bytes: &[u8],
+        loads: &mut Vec<(String, Option<DateTime<Local>>)>,
+        mut progress: impl FnMut(u64),
+    ) -> usize {
+        let eval = memmem::Finder::new("eval time");
+        let started = memmem::Finder::new("llama runner started in ");
+        let error = memmem::Finder::new("level=ERROR");
+        let architecture = memmem::Finder::new("general.architecture");
+        let embed = memmem::Finder::new("/embed");
+        let generate = memmem::Finder::new("/generate");
+        let chat = memmem::Finder::new("/chat");
+        let completions = memmem::Finder::new("/completions");
+        let prompt = memmem::Finder::new(" request\" images=");
+        let format = memmem::Finder::new("format");
+        let grammar = memmem::Finder::new("root ::=");
+        let failure: Vec<_> = FAILURE_MARKERS.iter().chain(&OOM_MARKERS).map(memmem::Finder::new).collect();
+        // Where the lines not fed to scan_line yet start, and where the newest
+        // timestamp among them ends; the time is only parsed once it's needed
+        let mut unfed = 0;
+        let mut stamp_end = None;
+        let mut pos = 0;
+        while let Some(end) = memchr(b'\n', &bytes[pos..]).map(|i| pos + i) {
+            let line = &bytes[pos..end];
+            if (end + 1) >> 20 != pos >> 20 {
+                progress((end + 1) as u64);
+            }
+            pos = end + 1;
+
+            let timed = line.starts_with(b"time=") || (line.get(4) == Some(&b'/') && line.get(7) == Some(&b'/'));
+            let runner = self.current.is_some()
+                && (eval.find(line).is_some()
+                    || failure.iter().any(|finder| finder.find(line).is_some())
+                    || (line.starts_with(b"time=")
+                        && [&started, &error, &prompt, &format, &grammar].iter().any(|finder| finder.find(line).is_some())));
+            if timed && !runner {
+                // Structured lines start the current model's stats, as in scan_line
+                let current = self.current.as_ref().filter(|current| !self.stats.contains_key(*current));
+                if let Some(current) = current.filter(|_| line.starts_with(b"time=")) {
+                    self.stats.insert(current.clone(), RunnerStats::default());
+                }
+                stamp_end = Some(end);
+                continue;
+            }
+            let loader = line.starts_with(b"llama_model_loader: loaded meta data")
+                || (self.current.is_some()
+                    && line.starts_with(b"llama_model_loader: - kv")
+                    && architecture.find(line).is_some());
+            let request = line.starts_with(b"[GIN]")
+                && [&embed, &generate, &chat, &completions].iter().any(|finder| finder.find(line).is_some());
+            if !runner && !loader && !request {
+                continue;
+            }
+
+            if let Some(stamp_end) = stamp_end.take() {
+                self.catch_up(&bytes[unfed..stamp_end]);
+            }
+            // Model metadata echoed into the log isn't always valid UTF-8
+            if let Some(hash) = self.scan_line(String::from_utf8_lossy(line).trim_end()) {
+                trace!(digest = %hash, at = ?self.last_timestamp, "model load");
+                loads.push((hash, self.last_timestamp));
+            }
+            unfed = pos;
+        }
+        if let Some(stamp_end) = stamp_end {
+            self.catch_up(&bytes[unfed..stamp_end]);
+        }
+        pos
+    }
+
+    /// Takes the time from the last line of `lines` that has a readable one, as
+    /// feeding them all to scan_line would.
+    fn catch_up(&mut self, lines: &[u8]) {
+        let newest = lines.rsplit(|&b| b == b'\n').find_map(|line| line_time(String::from_utf8_lossy(line).trim_end()));
+        if let Some(time) = newest {
+            self.last_timestamp = Some(time);
+        }
+    }
+
+    /// Feeds one log line, returning the model digest when the line records a model load.
+    fn scan_line(&mut self, line: &str) -> Option<String> {
+        // Timings come from the runner in whatever format it logs in
+        if let (Some(current), Some(rate)) = (&self.current, eval_rate(line)) {
+            let stats = self.stats.entry(current.clone()).or_default();
+            stats.timed_evals += 1;
+            stats.tokens_per_second += rate;
+            return None;
+        }
+        if let Some(time) = line_time(line) {
+            self.last_timestamp = Some(time);
+        }
+        self.failure(line);
+        if let Some(rest) = line.strip_prefix("time=") {
+            if let Some(current) = &self.current {
+                let stats = self.stats.entry(current.clone()).or_default();
+                let started = rest
+                    .split_once("llama runner started in ")
+                    .and_then(|(_, s)| s.split_whitespace().next()?.parse::<f64>().ok());
+                if let Some(seconds) = started {
+                    stats.timed_loads += 1;
+                    stats.load_seconds += seconds;
+                    self.failed = None;
+                }
+                if rest.contains("level=ERROR") {
+                    stats.errors += 1;
+                }
+                // Debug logs have each chat or generate request's whole prompt
+                let prompt = rest.contains("msg=\"chat request\"") || rest.contains("msg=\"generate request\"");
+                if prompt {
+                    self.structured = false;
+                }
+                if prompt && TOOL_MARKERS.iter().any(|marker| rest.contains(marker)) {
+                    stats.tool_requests += 1;
+                }
+                let structured = rest.contains("level=DEBUG") && STRUCTURED_MARKERS.iter().any(|marker| rest.contains(marker));
+                if structured && !self.structured {
+                    stats.structured_requests += 1;
+                    self.structured = true;
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("[GIN] ") {
+            self.request(rest);
+        } else if let Some((_, architecture)) = line.split_once("general.architecture") {
+            let architecture = architecture.rsplit('=').next().unwrap_or_default().trim();
+            if kind::EMBEDDING_FAMILIES.contains(&architecture) {
+                self.embedder = self.current.clone();
+            }
+        } else if line.starts_with("llama_model_loader: loaded meta data") {
+            let hash_start = line.find("sha256-")?;
+            let hash = line.get(hash_start + 7..hash_start + 71)?.to_string();
+            self.current = Some(hash.clone());
+            self.failed = None;
+            self.structured = false;
+            return Some(hash);
+        }
+        None
+    }
+
+    /// Notes a line saying the current model failed to load, crashed or ran out of
+    /// memory. A failure logs several such lines, from the runner and then the
+    /// server, so only the first counts, and it counts as running out of memory
+    /// when any of them says so.
+    fn failure(&mut self, line: &str) {
+        let oom = OOM_MARKERS.iter().any(|marker| line.contains(marker));
+        if !oom && !FAILURE_MARKERS.iter().any(|marker| line.contains(marker)) {
+            return;
+        }
+        let Some(current) = &self.current else {
+            return;
+        };
+        let stats = self.stats.entry(current.clone()).or_default();
+        if self.failed.is_none() {
+            stats.failures += 1;
+        }
+        if oom && self.failed != Some(true) {
+            stats.oom_failures += 1;
+        }
+        self.failed = Some(oom || self.failed == Some(true));
+    }
+
+    /// Notes a request from the access log, such as
+    /// `2024/05/01 - 12:00:00 | 200 | 1.2s | 127.0.0.1 | POST "/api/embed"`, against
+    /// the model serving it. Requests don't name their model, so embeddings go to
+    /// the embedding model loaded last, or failing that whichever model was, and
+    /// chat, generate and completion requests to whichever model was loaded last.
+    fn request(&mut self, rest: &str) {
+        // The access log has a request once it's done
+        self.structured = false;
+        let fields: Vec<&str> = rest.split('|').map(str::trim).collect();
+        let [stamp, _status, _latency, client, request] = fields[..] else {
+            return;
+        };
+        let path = request.split_whitespace().nth(1).unwrap_or_default().trim_matches('"');
+        let embedding = matches!(path, "/api/embed" | "/api/embeddings" | "/v1/embeddings");
+        let generation = matches!(path, "/api/generate" | "/api/chat" | "/v1/chat/completions" | "/v1/completions");
+        let model = if embedding { self.embedder.as_ref().or(self.current.as_ref()) } else { self.current.as_ref() };
+        let Some(model) = model.filter(|_| embedding || generation) else {
+            return;
+        };
+        let at = NaiveDateTime::parse_from_str(stamp, "%Y/%m/%d - %H:%M:%S").ok().and_then(local_time);
+        let stats = self.stats.entry(model.clone()).or_default();
+        if embedding {
+            stats.embeddings += 1;
+            stats.last_embedding = stats.last_embedding.max(at);
+        }
+        if let Some(at) = at.or(self.last_timestamp).filter(|_| !client.is_empty()) {
+            let last = stats.clients.entry(client.to_string()).or_insert(at);
+            *last = (*last).max(at);
+        }
+    }
+}
+
+/// The time a log line starts with, in either the structured `time=` format or the
+/// `2024/05/01 12:00:00` one older servers used.
+fn line_time(line: &str) -> Option<DateTime<Local>> {
+    if let Some(rest) = line.strip_prefix("time=") {
+        let stamp = rest.split_whitespace().next().unwrap_or(rest);
+        DateTime::parse_from_rfc3339(stamp).ok().map(|timestamp| timestamp.with_timezone(&Local))
+    } else if line.get(4..5) == Some("/") && line.get(7..8) == Some("/") {
+        let naive = line.get(0..19).and_then(|s| NaiveDateTime::parse_from_str(s, "%Y/%m/%d %H:%M:%S").ok());
+        naive.and_then(local_time)
+    } else {
+        None
+    }
+}
+
+/// Reads a wall-clock time from the log in the local time zone. When the clocks go
+/// back the hour happens twice and the earlier one is taken; when they go forward
+/// the skipped hour can't happen, so the time is read as an hour later.
+fn local_time(naive: NaiveDateTime) -> Option<DateTime<Local>> {
+    match Local.from_local_datetime(&naive) {
+        LocalResult::Single(time) => Some(time),
+        // chrono orders the pair by offset, not by instant
+        LocalResult::Ambiguous(a, b) => Some(a.min(b)),
+        LocalResult::None => {
+            trace!(%naive, "log time falls in a daylight saving gap");
+            Local.from_local_datetime(&(naive + chrono::Duration::hours(1))).earliest()
+        }
+    }
+}
+
+/// How much of a log is read and scanned at a time.
+const LOG_CHUNK: u64 = 16 << 20;
+
+/// What has been read from one log file so far.
+struct CachedLog {
+    offset: u64,
+    modified: DateTime<Local>,
+    scanner: LogScanner,
+    loads: Vec<(String, Option<DateTime<Local>>)>,
+}
+
+/// Remembers the model loads already found in each log file so that rescanning
+/// only reads what has been appended since. A file that shrank was truncated or
+/// rotated and is read again from the start.
+#[derive(Default)]
+pub struct LogCache {
+    files: HashMap<PathBuf, CachedLog>,
+    warnings: Vec<Warning>,
+    /// The Ollama service's journal as of the last time it was read, which is
+    /// read whole every time since it has no offset to pick up from.
+    journal: Option<CachedLog>,
+    journal_warning: Option<Warning>,
+}
+
+impl LogCache {
+    fn refresh(&mut self, log_path: &Path) -> Result<()> {
+        self.read_new_lines(log_path).map_err(|source| OmarError::LogUnreadable {
+            path: log_path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn read_new_lines(&mut self, log_path: &Path) -> io::Result<()> {
+        let file = File::open(log_path)?;
+        let metadata = file.metadata()?;
+        let modified = metadata.modified()?.into();
+
+        let cached = self.files.entry(log_path.to_path_buf()).or_insert_with(|| CachedLog {
+            offset: 0,
+            modified,
+            scanner: LogScanner::default(),
+            loads: Vec::new(),
+        });
+        if metadata.len() < cached.offset {
+            debug!(path = %log_path.display(), "log shrank, reading it again from the start");
+            cached.offset = 0;
+            cached.scanner = LogScanner::default();
+            cached.loads.clear();
+        }
+        cached.modified = modified;
+        debug!(path = %log_path.display(), offset = cached.offset, len = metadata.len(), "reading log");
+        let loads_before = cached.loads.len();
+
+        let name = log_path.file_name().unwrap_or(log_path.as_os_str()).to_string_lossy();
+        let progress = progress::log(metadata.len() - cached.offset, &name);
+        let start = cached.offset;
+        let started = Instant::now();
+        // Read in chunks rather than mapped: a log can be cut short while it's read,
+        // as logrotate's copytruncate does, and touching a mapping past the new end
+        // kills the process. Only the length seen above is read; anything written
+        // since waits for the next pass, and a log that shrank meanwhile just ends early.
+        let mut file = file;
+        file.seek(SeekFrom::Start(cached.offset))?;
+        let mut remaining = metadata.len() - cached.offset;
+        // The chunk being scanned, after whatever partial line the last one ended with
+        let mut buf = Vec::new();
+        while remaining > 0 {
+            let filled = buf.len();
+            buf.resize(filled + remaining.min(LOG_CHUNK) as usize, 0);
+            let read = file.read(&mut buf[filled..])?;
+            buf.truncate(filled + read);
+            if read == 0 {
+                break;
+            }
+            remaining -= read as u64;
+            let scanned = cached.offset - start;
+            let fed = cached.scanner.scan_bytes(&buf, &mut cached.loads, |read| {
+                progress.set_position(scanned + read);
+            });
+            cached.offset += fed as u64;
+            buf.drain(..fed);
+        }
+
+        progress.finish_and_clear();
+        timing::record(|| format!("Log {} ({})", name, format_size(cached.offset - start)), started.elapsed());
+        debug!(path = %log_path.display(), loads = cached.loads.len() - loads_before, "finished reading log");
+        Ok(())
+    }
+
+    /// Reads whatever each log gained since the last call and forgets logs that are
+    /// gone or can no longer be read, returning the ones that could.
+    fn read_logs(&mut self) -> Vec<PathBuf> {
+        let mut log_paths = get_log_paths();
+        debug!(logs = ?log_paths, "found server logs");
+        self.warnings.clear();
+        log_paths.retain(|log_path| match self.refresh(log_path) {
+            Ok(()) => true,
+            Err(err) => {
+                self.warnings.extend(Warning::from_error(&err));
+                false
+            }
+        });
+        self.files.retain(|path, _| log_paths.contains(path));
+        log_paths
+    }
+
+    /// Reads the journal of the Ollama service with `journalctl`, for Linux where
+    /// it logs there rather than to a file. A machine without `journalctl` just
+    /// has no journal; one that shows none of the service's lines, as when the user
+    /// isn't allowed to read the system journal, gets a warning.
+    pub(crate) fn read_journal(&mut self) {
+        self.journal_warning = None;
+        let mut command = std::process::Command::new("journalctl");
+        for unit in paths::journal_units() {
+            command.args(["-u", unit]);
+        }
+        command.args(["-o", "cat", "--no-pager"]);
+        debug!(?command, "reading the journal");
+
+        let started = Instant::now();
+        let output = match command.output() {
+            Ok(output) => output,
+            Err(err) => {
+                debug!(%err, "couldn't run journalctl");
+                self.journal = None;
+                return;
+            }
+        };
+        let mut bytes = output.stdout;
+        if bytes.is_empty() || !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = match stderr.lines().find(|line| !line.trim().is_empty()) {
+                Some(line) => format!("the Ollama service's journal could not be read: {}", line.trim()),
+                None => "the journal has no lines from the Ollama service".to_string(),
+            };
+            self.journal_warning = Some(Warning { path: PathBuf::from("journalctl"), reason });
+        }
+        if !bytes.is_empty() && !bytes.ends_with(b"\n") {
+            bytes.push(b'\n');
+        }
+
+        let mut scanner = LogScanner::default();
+        let mut loads = Vec::new();
+        let offset = scanner.scan_bytes(&bytes, &mut loads, |_| {}) as u64;
+        timing::record(|| format!("Journal ({})", format_size(offset)), started.elapsed());
+        debug!(loads = loads.len(), "finished reading the journal");
+        self.journal = Some(CachedLog { offset, modified: Local::now(), scanner, loads });
+    }
+
+    /// Every load in the journal as of the last [`LogCache::read_journal`].
+    fn journal_events(&self) -> Vec<UsageEvent> {
+        self.journal
+            .iter()
+            .flat_map(|journal| {
+                journal.loads.iter().map(|(hash, time)| UsageEvent {
+                    digest: hash.clone(),
+                    loaded_at: time.unwrap_or(journal.modified),
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the journal was read and had any of the Ollama service's lines.
+    pub fn read_any_journal(&self) -> bool {
+        self.journal.as_ref().is_some_and(|journal| journal.offset > 0)
+    }
+
+    /// Every log read so far, the journal included.
+    fn logs(&self) -> impl Iterator<Item = &CachedLog> {
+        self.files.values().chain(&self.journal)
+    }
+
+    /// The logs that couldn't be read on the last pass, and the journal if it
+    /// couldn't be.
+    pub fn warnings(&self) -> impl Iterator<Item = &Warning> {
+        self.warnings.iter().chain(&self.journal_warning)
+    }
+
+    /// Every load in the logs read so far. Loads logged before the first timestamp
+    /// count as happening when their log was last written.
+    fn load_events(&self, log_paths: &[PathBuf]) -> Vec<UsageEvent> {
+        log_paths
+            .iter()
+            .flat_map(|path| {
+                let cached = &self.files[path];
+                cached.loads.iter().map(|(hash, time)| UsageEvent {
+                    digest: hash.clone(),
+                    loaded_at: time.unwrap_or(cached.modified),
+                })
+            })
+            .collect()
+    }
+
+    pub fn parse_logs(&mut self, hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Result<HashMap<String, ModelUsage>> {
+        let log_paths = self.read_logs();
+        let mut events = self.load_events(&log_paths);
+        // Linux installs log to the journal rather than a file
+        if log_paths.is_empty() {
+            self.read_journal();
+            events.extend(self.journal_events());
+        } else {
+            self.journal = None;
+        }
+        let mut model_usage = source::usage_from_events(&events, hash_to_name_size);
+        self.add_requests(&mut model_usage);
+        Ok(model_usage)
+    }
+
+    /// Load times, generation speeds and errors per digest across every log read so far.
+    fn runner_stats(&self) -> HashMap<String, RunnerStats> {
+        let mut merged: HashMap<String, RunnerStats> = HashMap::new();
+        for (hash, stats) in self.logs().flat_map(|f| f.scanner.stats.iter()) {
+            let entry = merged.entry(hash.clone()).or_default();
+            entry.timed_loads += stats.timed_loads;
+            entry.load_seconds += stats.load_seconds;
+            entry.timed_evals += stats.timed_evals;
+            entry.tokens_per_second += stats.tokens_per_second;
+            entry.errors += stats.errors;
+            entry.embeddings += stats.embeddings;
+            entry.last_embedding = entry.last_embedding.max(stats.last_embedding);
+            entry.tool_requests += stats.tool_requests;
+            entry.structured_requests += stats.structured_requests;
+            entry.failures += stats.failures;
+            entry.oom_failures += stats.oom_failures;
+            for (client, at) in &stats.clients {
+                let last = entry.clients.entry(client.clone()).or_insert(*at);
+                *last = (*last).max(*at);
+            }
+        }
+        merged
+    }
+
+    /// Fills in the embedding, tool and structured output requests, the clients and
+    /// the failures the logs read so far credit each
+    /// model in `model_usage` with. Models without a load in the logs have nothing
+    /// to credit them to, so they get none.
+    pub fn add_requests(&self, model_usage: &mut HashMap<String, ModelUsage>) {
+        for (hash, stats) in self.runner_stats() {
+            if let Some(usage) = model_usage.get_mut(&hash) {
+                usage.embeddings = stats.embeddings;
+                usage.last_embedding = stats.last_embedding;
+                usage.tool_requests = stats.tool_requests;
+                usage.structured_requests = stats.structured_requests;
+                usage.clients = stats.clients;
+                usage.failures = stats.failures;
+                usage.oom_failures = stats.oom_failures;
+            }
+        }
+    }
+}
+
+impl UsageSource for LogCache {
+    fn name(&self) -> &'static str {
+        "logs"
+    }
+
+    fn events(&mut self, _: &HashMap<String, (Vec<String>, u64)>) -> anyhow::Result<Vec<UsageEvent>> {
+        let log_paths = self.read_logs();
+        Ok(self.load_events(&log_paths))
+    }
+
+    /// Each log read is timed on its own.
+    fn times_itself(&self) -> bool {
+        true
+    }
+}
+
+/// Reads every server log once and tallies model loads, keyed by digest.
+pub fn parse_usage(hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Result<HashMap<String, ModelUsage>> {
+    LogCache::default().parse_logs(hash_to_name_size)
+}
+
+/// Adds the loads found in the current logs to the history database, then fills in
+/// each model's all-time count from everything recorded there. Models whose loads
+/// have rotated out of the logs entirely are added back with their recorded usage.
+pub fn merge_history(
+    db: &Path,
+    cache: &LogCache,
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+    model_usage: &mut HashMap<String, ModelUsage>,
+) -> anyhow::Result<()> {
+    let mut conn = history::open(db)?;
+    let names: HashMap<&str, String> =
+        hash_to_name_size.iter().map(|(hash, (tags, _))| (hash.as_str(), tags.join(", "))).collect();
+    let loads = cache.logs().flat_map(|f| f.loads.iter()).filter_map(|(hash, time)| {
+        time.map(|t| (hash.as_str(), names.get(hash.as_str()).map(String::as_str), t))
+    });
+    history::record_loads(&mut conn, loads)?;
+
+    for (hash, recorded) in history::recorded_usage(&conn)? {
+        let (tags, size, status) = resolve_digest(hash_to_name_size, &hash);
+        let entry = model_usage.entry(hash.clone()).or_insert_with(|| ModelUsage::new(&hash, tags, status, size, recorded.last_used));
+        entry.recorded_count = Some(recorded.count.max(entry.usage_count));
+        entry.days_used = entry.days_used.max(recorded.days_used);
+        if recorded.last_used > entry.last_used {
+            entry.last_used = recorded.last_used;
+        }
+    }
+
+    // The history reaches back further than the logs, which may have rotated away
+    // the earlier window
+    let window = TREND_DAYS as usize;
+    for (hash, daily) in history::daily_usage(&conn, 2 * window)? {
+        if let Some(entry) = model_usage.get_mut(&hash) {
+            let (prior, recent) = daily.split_at(window);
+            entry.prior_count = entry.prior_count.max(prior.iter().sum::<u64>() as usize);
+            entry.recent_count = entry.recent_count.max(recent.iter().sum::<u64>() as usize);
+        }
+    }
+
+    for usage in model_usage.values_mut() {
+        usage.recorded_count.get_or_insert(usage.usage_count);
+    }
+
+    Ok(())
+}
+
+/// Scans manifests and logs and flattens the result into one row per tag.
+pub fn scan_report() -> Result<Vec<history::SnapshotModel>> {
+    let installed = scan_models()?;
+    let hash_to_name_size = index_by_digest(&installed);
+    let model_usage = parse_usage(&hash_to_name_size)?;
+    Ok(history::current_models(&installed, &model_usage))
+}
+
+/// Total size of installed weights, counting each digest once.
+pub(crate) fn total_size(installed: &[InstalledModel]) -> u64 {
+    let unique: HashMap<&str, u64> = installed.iter().map(|m| (m.digest.as_str(), m.size)).collect();
+    unique.values().sum()
+}
+
+/// Formats a byte count in GB, or MB when under a gigabyte.
+pub fn format_size(size: u64) -> String {
+    let gb = size as f64 / 1_024.0 / 1_024.0 / 1_024.0;
+    if gb >= 1.0 {
+        format!("{:.1} GB", gb)
+    } else {
+        let mb = size as f64 / 1_024.0 / 1_024.0;
+        format!("{:.1} MB", mb)
+    }
+}
+
+/// A rolling span of time ending now, such as the last 30 days.
+#[derive(Debug, Clone)]
+pub struct Window {
+    /// The span as it was given, e.g. `30d`, for column headers.
+    pub label: String,
+    pub length: chrono::Duration,
+}
+
+/// Parses a window such as `30d`, `2w` or `12h`. A plain number is days.
+pub fn parse_window(input: &str) -> Result<Window, String> {
+    let label = input.trim().to_ascii_lowercase();
+    let split = label.find(|c: char| !c.is_ascii_digit()).unwrap_or(label.len());
+    let (number, unit) = label.split_at(split);
+    let number: i64 = number.parse().map_err(|_| format!("invalid window '{}'", input))?;
+
+    let length = match unit.trim() {
+        "" | "d" | "day" | "days" => chrono::Duration::days(number),
+        "w" | "week" | "weeks" => chrono::Duration::weeks(number),
+        "h" | "hour" | "hours" => chrono::Duration::hours(number),
+        other => return Err(format!("unknown window unit '{}'", other)),
+    };
+    let label = if unit.is_empty() { format!("{}d", number) } else { label };
+    Ok(Window { label, length })
+}
+
+/// Counts each model's loads within `window` into [`ModelUsage::window_count`], and
+/// keeps only the [`ModelUsage::clients`] seen within it. With a history database
+/// the loads it recorded count too, for when the logs have rotated away part of
+/// the window.
+pub fn apply_window(
+    model_usage: &mut HashMap<String, ModelUsage>,
+    window: &Window,
+    db: Option<&Path>,
+) -> anyhow::Result<()> {
+    let since = Local::now() - window.length;
+    for usage in model_usage.values_mut() {
+        usage.window_count = Some(usage.loads.iter().filter(|t| **t >= since).count());
+        usage.clients.retain(|_, last| *last >= since);
+    }
+
+    if let Some(db) = db {
+        for (hash, count) in history::usage_since(&history::open(db)?, since)? {
+            if let Some(usage) = model_usage.get_mut(&hash) {
+                usage.window_count = usage.window_count.max(Some(count));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses a human size such as `50GB`, `1.5T`, `512 MB` or `1000` into bytes.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", input))?;
+
+    let multiplier = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1u64,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        other => return Err(format!("unknown size unit '{}'", other)),
+    };
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LLAMA3: &str = "1a9a388336073f25f143cdd39abe37b306a367d031d6c04a79bbb545232ae113";
+    const NOMIC: &str = "970aa74c0a90ef7482477cf803618e776e173c007bf957f635f1015bfcfef0e6";
+
+    type Loads = Vec<(String, Option<DateTime<Local>>)>;
+
+    /// Scans `log` in one pass, returning the loads and the scanner's stats.
+    fn scan(log: &[u8]) -> (Loads, HashMap<String, RunnerStats>) {
+        let mut scanner = LogScanner::default();
+        let mut loads = Vec::new();
+        assert_eq!(scanner.scan_bytes(log, &mut loads, |_| {}), log.len());
+        (loads, scanner.stats)
+    }
+
+    #[test]
+    fn parse_size_reads_binary_units() {
+        assert_eq!(parse_size("1000"), Ok(1000));
+        assert_eq!(parse_size("512 MB"), Ok(512 << 20));
+        assert_eq!(parse_size("50GB"), Ok(50 << 30));
+        assert_eq!(parse_size("1.5t"), Ok(3 << 39));
+        assert_eq!(parse_size("2GiB"), Ok(2 << 30));
+        assert!(parse_size("GB").is_err());
+        assert!(parse_size("5 parsecs").is_err());
+    }
+
+    #[test]
+    fn parse_window_reads_days_weeks_and_hours() {
+        let days = parse_window("30").unwrap();
+        assert_eq!((days.label.as_str(), days.length), ("30d", chrono::Duration::days(30)));
+        let weeks = parse_window("2W").unwrap();
+        assert_eq!((weeks.label.as_str(), weeks.length), ("2w", chrono::Duration::weeks(2)));
+        assert_eq!(parse_window("12 hours").unwrap().length, chrono::Duration::hours(12));
+        assert!(parse_window("d").is_err());
+        assert!(parse_window("3 months").is_err());
+    }
+
+    #[test]
+    fn trend_is_flat_within_a_quarter() {
+        assert_eq!(Trend::from_counts(0, 0), Trend::Flat);
+        assert_eq!(Trend::from_counts(5, 4), Trend::Flat);
+        assert_eq!(Trend::from_counts(3, 4), Trend::Flat);
+        assert_eq!(Trend::from_counts(6, 4), Trend::Up);
+        assert_eq!(Trend::from_counts(1, 0), Trend::Up);
+        assert_eq!(Trend::from_counts(2, 4), Trend::Down);
+        assert_eq!(Trend::from_counts(0, 1), Trend::Down);
+    }
+
+    #[test]
+    fn log_scanner_finds_loads_with_their_times() {
+        let (loads, _) = scan(include_bytes!("../tests/fixtures/home/.ollama/logs/server.log"));
+        let hashes: Vec<_> = loads.iter().map(|(hash, _)| &hash[..8]).collect();
+        assert_eq!(hashes, ["1a9a3883", "970aa74c", "00000000"]);
+        let first = DateTime::parse_from_rfc3339("2024-10-29T07:18:19-07:00").unwrap();
+        assert_eq!(loads[0].1, Some(first.with_timezone(&Local)));
+    }
+
+    /// A log read while a line is half written picks the line up on the next read,
+    /// and one cut short is read again from the start.
+    #[test]
+    fn log_cache_reads_what_a_log_gained() {
+        let log: &[u8] = include_bytes!("../tests/fixtures/home/.ollama/logs/server.log");
+        let path = std::env::temp_dir().join(format!("omar-log-{}.log", std::process::id()));
+        let loads = |cache: &LogCache| cache.files[&path].loads.iter().map(|(hash, _)| hash[..8].to_string()).collect::<Vec<_>>();
+        let mut cache = LogCache::default();
+
+        let cut = log.len() / 2;
+        fs::write(&path, &log[..cut]).unwrap();
+        cache.refresh(&path).unwrap();
+        assert!(cache.files[&path].offset <= cut as u64);
+        fs::write(&path, log).unwrap();
+        cache.refresh(&path).unwrap();
+        assert_eq!(loads(&cache), ["1a9a3883", "970aa74c", "00000000"]);
+        assert_eq!(cache.files[&path].offset, log.len() as u64);
+
+        fs::write(&path, &log[..cut]).unwrap();
+        cache.refresh(&path).unwrap();
+        assert_eq!(loads(&cache), ["1a9a3883"]);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn log_scanner_credits_embeddings_to_the_embedding_model() {
+        let (_, stats) = scan(include_bytes!("../tests/fixtures/home/.ollama/logs/server.log"));
+        assert_eq!(stats[NOMIC].embeddings, 2);
+        assert_eq!(stats[LLAMA3].embeddings, 0);
+    }
+
+    #[test]
+    fn log_scanner_counts_prompts_offering_tools() {
+        let (_, stats) = scan(include_bytes!("../tests/fixtures/home/.ollama/logs/server.log"));
+        assert_eq!(stats[LLAMA3].tool_requests, 1);
+        assert_eq!(stats[NOMIC].tool_requests, 0);
+    }
+
+    #[test]
+    fn log_scanner_counts_structured_output_requests() {
+        let (_, stats) = scan(include_bytes!("../tests/fixtures/home/.ollama/logs/server.log"));
+        assert_eq!(stats[LLAMA3].structured_requests, 1);
+        assert_eq!(stats[NOMIC].structured_requests, 0);
+    }
+
+    #[test]
+    fn log_scanner_counts_distinct_clients() {
+        let (_, stats) = scan(include_bytes!("../tests/fixtures/home/.ollama/logs/server.log"));
+        assert_eq!(stats[LLAMA3].clients.keys().collect::<Vec<_>>(), ["127.0.0.1"]);
+        assert_eq!(stats[NOMIC].clients.len(), 2);
+    }
+
+    #[test]
+    fn log_scanner_counts_failures_and_oom() {
+        let (_, stats) = scan(include_bytes!("../tests/fixtures/home/.ollama/logs/server.log"));
+        assert_eq!((stats[LLAMA3].failures, stats[LLAMA3].oom_failures), (1, 1));
+        assert_eq!((stats[NOMIC].failures, stats[NOMIC].oom_failures), (0, 0));
+    }
+
+    #[test]
+    fn log_scanner_counts_a_request_logging_format_and_grammar_once() {
+        let log = format!(
+            "time=2024-10-29T07:18:19.000-07:00 level=INFO msg=\"starting llama server\"\n\
+             llama_model_loader: loaded meta data with 29 key-value pairs from /blobs/sha256-{}\n\
+             time=2024-10-29T07:18:30.000-07:00 level=DEBUG msg=\"completion request\" format=json\n\
+             time=2024-10-29T07:18:30.100-07:00 level=DEBUG msg=\"grammar\" grammar=\"root ::= object\"\n\
+             [GIN] 2024/10/29 - 07:18:31 | 200 | 1.2s | 127.0.0.1 | POST \"/api/generate\"\n\
+             time=2024-10-29T07:19:30.000-07:00 level=DEBUG msg=\"completion request\" format=\"{{\\\"type\\\":\\\"object\\\"}}\"\n",
+            LLAMA3
+        );
+        let (_, stats) = scan(log.as_bytes());
+        assert_eq!(stats[LLAMA3].structured_requests, 2);
+    }
+
+    #[test]
+    fn log_scanner_counts_one_failure_per_crash() {
+        let log = format!(
+            "llama_model_loader: loaded meta data with 29 key-value pairs from /blobs/sha256-{}\n\
+             time=2024-10-29T07:19:00.000-07:00 level=ERROR msg=\"runner\" error=\"llama runner process has terminated: SIGSEGV\"\n\
+             time=2024-10-29T07:19:00.100-07:00 level=ERROR msg=\"load\" error=\"error loading model\"\n\
+             time=2024-10-29T07:20:00.000-07:00 level=INFO msg=\"llama runner started in 2.00 seconds\"\n\
+             time=2024-10-29T07:21:00.000-07:00 level=ERROR msg=\"load\" error=\"unable to allocate CUDA0 buffer\"\n",
+            LLAMA3
+        );
+        let (_, stats) = scan(log.as_bytes());
+        assert_eq!((stats[LLAMA3].failures, stats[LLAMA3].oom_failures), (2, 1));
+    }
+}
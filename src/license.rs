@@ -0,0 +1,131 @@
+//! The licenses models are distributed under, for the report's License column
+//! and `omar licenses`: read from the license layers Ollama keeps with each
+//! model, and named with an SPDX identifier where the text is recognised.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    format_size, read_manifest, report::ReportRow, strip_digest, table::{self, Align}, InstalledModel,
+};
+
+/// Media type of a layer holding license text.
+const LICENSE: &str = "application/vnd.ollama.image.license";
+
+/// Shown for a model with no license layer, as with most models built locally.
+pub const NONE: &str = "none";
+
+/// Shown for license text none of [`KNOWN`] matches.
+pub const UNIDENTIFIED: &str = "unidentified";
+
+/// Phrases that identify a license, all of which have to appear in its text,
+/// checked in this order so the more specific come first. Licenses missing from
+/// the SPDX list get a `LicenseRef-` identifier, as SPDX has for them.
+const KNOWN: [(&[&str], &str); 20] = [
+    (&["llama 4 community license"], "LicenseRef-Llama-4-Community"),
+    (&["llama 3.3 community license"], "LicenseRef-Llama-3.3-Community"),
+    (&["llama 3.2 community license"], "LicenseRef-Llama-3.2-Community"),
+    (&["llama 3.1 community license"], "LicenseRef-Llama-3.1-Community"),
+    (&["llama 3 community license"], "LicenseRef-Llama-3-Community"),
+    (&["llama 2 community license"], "LicenseRef-Llama-2-Community"),
+    (&["gemma terms of use"], "LicenseRef-Gemma"),
+    (&["tongyi qianwen research license"], "LicenseRef-Tongyi-Qianwen-Research"),
+    (&["tongyi qianwen license"], "LicenseRef-Tongyi-Qianwen"),
+    (&["deepseek license agreement"], "LicenseRef-DeepSeek"),
+    (&["mistral ai research license"], "LicenseRef-Mistral-AI-Research"),
+    (&["mistral ai non-production license"], "LicenseRef-Mistral-AI-Non-Production"),
+    (&["creativeml open rail-m"], "CreativeML-OpenRAIL-M"),
+    (&["attribution-noncommercial-sharealike 4.0"], "CC-BY-NC-SA-4.0"),
+    (&["attribution-noncommercial 4.0"], "CC-BY-NC-4.0"),
+    (&["attribution-sharealike 4.0"], "CC-BY-SA-4.0"),
+    (&["creative commons attribution 4.0"], "CC-BY-4.0"),
+    (&["apache license", "version 2.0"], "Apache-2.0"),
+    (&["permission is hereby granted, free of charge"], "MIT"),
+    (&["redistribution and use in source and binary forms", "neither the name"], "BSD-3-Clause"),
+];
+
+/// The SPDX identifier for license `text`, which may also be just an identifier,
+/// as some Modelfiles give.
+pub fn identify(text: &str) -> Option<&'static str> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    if let Some((_, id)) = KNOWN.iter().find(|(_, id)| id.to_lowercase() == text) {
+        return Some(id);
+    }
+    KNOWN.iter().find(|(phrases, _)| phrases.iter().all(|phrase| text.contains(phrase))).map(|(_, id)| *id)
+}
+
+/// The license of an installed model: the identifiers of its license layers
+/// joined with ` AND `, leaving out texts that weren't recognised when others
+/// were, such as the acceptable use policy Llama models carry as a second layer.
+fn of(model: &InstalledModel) -> String {
+    let Ok(manifest) = read_manifest(&model.path) else {
+        return UNIDENTIFIED.to_string();
+    };
+    let blobs = model.models_dir().join("blobs");
+    let mut ids: Vec<&str> = Vec::new();
+    let mut layers = 0;
+    for layer in manifest.layers.iter().filter(|layer| layer.media_type == LICENSE) {
+        layers += 1;
+        let text = std::fs::read_to_string(blobs.join(format!("sha256-{}", strip_digest(&layer.digest))));
+        if let Some(id) = text.ok().as_deref().and_then(identify) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    match (layers, ids.is_empty()) {
+        (0, _) => NONE.to_string(),
+        (_, true) => UNIDENTIFIED.to_string(),
+        (_, false) => ids.join(" AND "),
+    }
+}
+
+/// Licenses of the installed models, by digest.
+pub struct Lookup {
+    by_digest: HashMap<String, String>,
+}
+
+impl Lookup {
+    /// Reads the license layers of each installed tag. Tags sharing weights can
+    /// still have different licenses, so the first tag's is kept for the digest.
+    pub fn new(installed: &[InstalledModel]) -> Lookup {
+        let mut by_digest = HashMap::new();
+        for model in installed {
+            by_digest.entry(model.digest.clone()).or_insert_with(|| of(model));
+        }
+        Lookup { by_digest }
+    }
+
+    /// The license of a row's model, for installed models only.
+    pub fn license(&self, row: &ReportRow) -> Option<String> {
+        self.by_digest.get(&row.digest).cloned()
+    }
+}
+
+/// `omar licenses`: each license with the tags carrying it and the space their
+/// weights take, most widespread first.
+pub fn run(installed: &[InstalledModel]) -> String {
+    let mut by_license: BTreeMap<String, (Vec<&str>, HashSet<&str>, u64)> = BTreeMap::new();
+    for model in installed {
+        let (tags, digests, size) = by_license.entry(of(model)).or_default();
+        tags.push(&model.name);
+        if digests.insert(&model.digest) {
+            *size += model.size;
+        }
+    }
+    if by_license.is_empty() {
+        return "\nNo models are installed.\n\n".to_string();
+    }
+    let mut licenses: Vec<_> = by_license.into_iter().collect();
+    licenses.sort_by(|(a, (a_tags, ..)), (b, (b_tags, ..))| b_tags.len().cmp(&a_tags.len()).then_with(|| a.cmp(b)));
+
+    let rows: Vec<_> = licenses
+        .iter()
+        .map(|(license, (tags, _, size))| vec![license.clone(), tags.len().to_string(), format_size(*size), tags.join(", ")])
+        .collect();
+    let mut out = String::from("\nLicenses of the installed models:\n");
+    out.push_str(&table::render(
+        &[("License", Align::Left), ("Tags", Align::Right), ("Size", Align::Right), ("Models", Align::Left)],
+        &rows,
+    ));
+    out
+}
@@ -0,0 +1,53 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use glob::glob;
+use std::{fs, path::Path};
+use tracing::debug;
+
+use crate::{paths, report::ReportRow};
+
+/// Lists the GGUF files under LM Studio's models directory, largest first. LM
+/// Studio keeps them as `publisher/repository/file.gguf` and logs nothing omar can
+/// read, so a file's modification time is all there is to go on for when it was
+/// last used, and the usage counts stay at zero.
+pub fn scan(dir: &Path) -> Result<Vec<ReportRow>> {
+    if !dir.is_dir() {
+        bail!("LM Studio models directory {} not found", dir.display());
+    }
+
+    let mut rows = Vec::new();
+    for path in glob(&paths::glob_pattern(dir, "**/*.gguf"))?.filter_map(Result::ok) {
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                debug!(path = %path.display(), %err, "skipping unreadable GGUF file");
+                continue;
+            }
+        };
+        let name = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        rows.push(ReportRow {
+            name,
+            tags: Vec::new(),
+            digest: String::new(),
+            last_used: metadata.modified().ok().map(DateTime::<Local>::from),
+            usage_count: 0,
+            days_used: 0,
+            window_count: None,
+            all_time: None,
+            trend: None,
+            embeddings: None,
+            tool_requests: None,
+            structured_requests: None,
+            clients: None,
+            failures: None,
+            oom_failures: None,
+            kind: None,
+            license: None,
+            created: None,
+            pulled: None,
+            size: metadata.len(),
+        });
+    }
+    rows.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    Ok(rows)
+}
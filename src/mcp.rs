@@ -0,0 +1,237 @@
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Local};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    history::{self, SnapshotModel},
+    index_by_digest, merge_history, notify, read_manifest, read_params,
+    prune::normalize_name,
+    report::Report,
+    scan_models, total_size, InstalledModel, LogCache, ModelUsage, Status,
+};
+
+/// Protocol versions this server speaks, newest last. It only uses what they
+/// have in common: tools returning text.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "list_models",
+            "description": "List the Ollama models on this machine with their size in bytes, status (active, \
+                            unlogged = installed but never seen loaded, deleted = seen loaded but no longer \
+                            installed), last use, load count, days used and trend. Filter by status, or with \
+                            unused_for_days to find installed models not loaded lately.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "status": { "enum": ["active", "unlogged", "deleted"] },
+                    "unused_for_days": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Only installed models not loaded within this many days"
+                    }
+                }
+            }
+        },
+        {
+            "name": "model_detail",
+            "description": "Everything known about one model: size, usage, when it was pulled, the tags sharing \
+                            its weights, the blobs it references and the default parameters it was created \
+                            with.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "name": { "type": "string", "description": "Tag, e.g. llama3:8b" } },
+                "required": ["name"]
+            }
+        },
+        {
+            "name": "usage_stats",
+            "description": "Totals for the whole model store: disk used, how much of it sits in models unused \
+                            for stale_days, recent activity, and models pulled long ago and never used.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "stale_days": { "type": "integer", "minimum": 0, "description": "Defaults to 30" }
+                }
+            }
+        }
+    ])
+}
+
+/// Everything the tools answer from, as of the call.
+struct Scan {
+    installed: Vec<InstalledModel>,
+    hash_to_name_size: HashMap<String, (Vec<String>, u64)>,
+    model_usage: HashMap<String, ModelUsage>,
+}
+
+struct Server {
+    db: Option<PathBuf>,
+    cache: LogCache,
+}
+
+impl Server {
+    /// Rescans manifests and whatever the logs gained since the last call.
+    fn scan(&mut self) -> Result<Scan> {
+        let installed = scan_models()?;
+        let hash_to_name_size = index_by_digest(&installed);
+        let mut model_usage = self.cache.parse_logs(&hash_to_name_size)?;
+        if let Some(db) = &self.db {
+            merge_history(db, &self.cache, &hash_to_name_size, &mut model_usage)?;
+        }
+        Ok(Scan { installed, hash_to_name_size, model_usage })
+    }
+
+    fn list_models(&mut self, args: &Value) -> Result<Value> {
+        let scan = self.scan()?;
+        let status = args["status"].as_str();
+        let cutoff = args["unused_for_days"].as_i64().map(|days| Local::now() - Duration::days(days));
+        let models: Vec<_> = history::current_models(&scan.installed, &scan.model_usage)
+            .iter()
+            .filter(|m| status.is_none_or(|s| m.status.as_str() == s))
+            .filter(|m| match cutoff {
+                Some(cutoff) => m.status != Status::Deleted && m.last_used.is_none_or(|t| t < cutoff),
+                None => true,
+            })
+            .map(|m| model_json(m, scan.model_usage.get(&m.digest)))
+            .collect();
+        Ok(json!({ "models": models }))
+    }
+
+    fn model_detail(&mut self, args: &Value) -> Result<Value> {
+        let name = args["name"].as_str().ok_or_else(|| anyhow!("name is required"))?;
+        let name = normalize_name(name);
+        let scan = self.scan()?;
+        let models = history::current_models(&scan.installed, &scan.model_usage);
+        let model = models
+            .iter()
+            .find(|m| m.name == name)
+            .ok_or_else(|| anyhow!("No model named {}; list_models has the names", name))?;
+
+        let mut detail = model_json(model, scan.model_usage.get(&model.digest));
+        let installed = scan.installed.iter().find(|m| m.name == name);
+        detail["pulled_at"] = json!(installed.and_then(|m| m.pulled_at()));
+        detail["shared_with"] = json!(models
+            .iter()
+            .filter(|m| m.digest == model.digest && m.name != model.name)
+            .map(|m| &m.name)
+            .collect::<Vec<_>>());
+        detail["blobs"] = json!(installed.map_or(Vec::new(), |m| m
+            .blobs
+            .iter()
+            .map(|(digest, size)| json!({ "digest": digest, "size": size }))
+            .collect()));
+        detail["parameters"] = json!(installed
+            .and_then(|m| Some(read_params(m, &read_manifest(&m.path).ok()?)))
+            .unwrap_or_default());
+        Ok(detail)
+    }
+
+    fn usage_stats(&mut self, args: &Value) -> Result<Value> {
+        let stale_days = args["stale_days"].as_i64().unwrap_or(30);
+        let scan = self.scan()?;
+        let stale = notify::stale_models(&scan.installed, &scan.hash_to_name_size, &scan.model_usage, stale_days);
+        let mut report = Report::build(&scan.hash_to_name_size, &scan.model_usage);
+        report.flag_never_used(&scan.installed, stale_days);
+        Ok(json!({
+            "installed_tags": scan.installed.len(),
+            "installed_models": scan.hash_to_name_size.len(),
+            "total_bytes": total_size(&scan.installed),
+            "unused": stale,
+            "activity": report.activity,
+            "never_used": report.never_used,
+        }))
+    }
+
+    /// Handles one JSON-RPC message, returning the response unless it was a
+    /// notification.
+    fn handle(&mut self, message: &Value) -> Option<Value> {
+        let id = message.get("id")?.clone();
+        let params = &message["params"];
+        let result = match message["method"].as_str().unwrap_or_default() {
+            "initialize" => {
+                let requested = params["protocolVersion"].as_str().unwrap_or_default();
+                let version = PROTOCOL_VERSIONS
+                    .into_iter()
+                    .find(|v| *v == requested)
+                    .unwrap_or(PROTOCOL_VERSIONS[PROTOCOL_VERSIONS.len() - 1]);
+                Ok(json!({
+                    "protocolVersion": version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "omar", "version": env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => self.call(params),
+            method => Err((METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+        })
+    }
+
+    /// Runs a tool. Failures of the tool itself go back to the model as text, so it
+    /// can tell the user or try again.
+    fn call(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let args = &params["arguments"];
+        let result = match params["name"].as_str().unwrap_or_default() {
+            "list_models" => self.list_models(args),
+            "model_detail" => self.model_detail(args),
+            "usage_stats" => self.usage_stats(args),
+            name => return Err((INVALID_PARAMS, format!("Unknown tool {}", name))),
+        };
+        let (text, is_error) = match result {
+            Ok(value) => (serde_json::to_string_pretty(&value).unwrap_or_default(), false),
+            Err(err) => (format!("{:#}", err), true),
+        };
+        Ok(json!({ "content": [{ "type": "text", "text": text }], "isError": is_error }))
+    }
+}
+
+fn model_json(model: &SnapshotModel, usage: Option<&ModelUsage>) -> Value {
+    let mut value = json!(model);
+    value["days_used"] = json!(usage.map_or(0, |u| u.days_used));
+    value["all_time"] = json!(usage.and_then(|u| u.recorded_count));
+    value["trend"] = json!(usage.map(|u| u.trend()));
+    value
+}
+
+/// Serves the Model Context Protocol over stdin and stdout, one JSON-RPC message
+/// per line, until stdin closes. Every tool call rescans, so answers are current;
+/// with `db` the usage history is merged in as in the report.
+pub fn run(db: Option<PathBuf>) -> Result<()> {
+    let mut server = Server { db, cache: LogCache::default() };
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => server.handle(&message),
+            Err(err) => Some(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": PARSE_ERROR, "message": err.to_string() },
+            })),
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::PathBuf,
+    process::Command,
+};
+
+use crate::{
+    format_size,
+    history::{Snapshot, Status},
+    table::{self, Align},
+};
+
+/// This machine's name, to label what `collect` writes.
+pub fn hostname() -> String {
+    if let Some(name) = env::var_os("COMPUTERNAME") {
+        return name.to_string_lossy().into_owned();
+    }
+    Command::new("hostname")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Reads a snapshot file, labelled with the host it was collected on, or with the
+/// file's name for snapshots written by `snapshot --output`.
+fn read(path: &PathBuf) -> Result<(String, Snapshot)> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let snapshot: Snapshot =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse snapshot {}", path.display()))?;
+    let host = snapshot
+        .host
+        .clone()
+        .unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+    Ok((host, snapshot))
+}
+
+/// Prints the models installed across several machines in one table, with each
+/// host's loads of every tag, then what each host uses on disk and what the weights
+/// would take if every distinct one were stored once.
+pub fn run(files: &[PathBuf]) -> Result<()> {
+    let mut hosts = Vec::new();
+    for path in files {
+        let (host, snapshot) = read(path)?;
+        if hosts.iter().any(|(h, _)| *h == host) {
+            bail!("{} has a second snapshot in {}; merge one per host", host, path.display());
+        }
+        hosts.push((host, snapshot));
+    }
+
+    // Loads per host for every tag, which is installed wherever it has an entry
+    let mut tags: BTreeMap<&str, (u64, Vec<Option<usize>>)> = BTreeMap::new();
+    let mut distinct: HashMap<&str, u64> = HashMap::new();
+    let mut per_host = Vec::new();
+    for (i, (_, snapshot)) in hosts.iter().enumerate() {
+        let installed = snapshot.installed_digests();
+        per_host.push(installed.values().sum::<u64>());
+        distinct.extend(installed);
+        for model in snapshot.models.iter().filter(|m| m.status != Status::Deleted) {
+            let (size, loads) = tags.entry(&model.name).or_insert_with(|| (model.size, vec![None; hosts.len()]));
+            *size = (*size).max(model.size);
+            loads[i] = Some(model.usage_count);
+        }
+    }
+
+    let mut rows: Vec<_> = tags
+        .into_iter()
+        .map(|(name, (size, loads))| {
+            let mut row = vec![name.to_string(), format_size(size)];
+            row.extend(loads.iter().map(|l| l.map_or("-".to_string(), |l| l.to_string())));
+            row.push(loads.iter().flatten().count().to_string());
+            row.push(loads.iter().flatten().sum::<usize>().to_string());
+            (size, row)
+        })
+        .collect();
+    rows.sort_by(|(a, x), (b, y)| b.cmp(a).then_with(|| x[0].cmp(&y[0])));
+
+    let mut headers = vec![("Model", Align::Left), ("Size", Align::Right)];
+    headers.extend(hosts.iter().map(|(host, _)| (host.as_str(), Align::Right)));
+    headers.extend([("Hosts", Align::Right), ("Total Loads", Align::Right)]);
+    println!("\nModels on {} host(s), with loads on each (- where not installed):", hosts.len());
+    table::print(&headers, &rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>());
+
+    println!("\nDisk used:");
+    for ((host, snapshot), used) in hosts.iter().zip(&per_host) {
+        println!("  {}: {} (as of {})", host, format_size(*used), snapshot.taken_at.format("%Y-%m-%d %H:%M"));
+    }
+    println!(
+        "  Total: {} across all hosts, {} of distinct weights ({} model(s))\n",
+        format_size(per_host.iter().sum()),
+        format_size(distinct.values().sum()),
+        distinct.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::history::{SnapshotModel, Status};
+
+/// Escapes a label value for the Prometheus text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Renders the report in the Prometheus text exposition format, one series per tag.
+pub fn render(models: &[SnapshotModel]) -> String {
+    render_as(models, false)
+}
+
+/// Renders the report as OpenMetrics, which names counter families without their
+/// `_total` suffix and ends with `# EOF`.
+pub fn render_openmetrics(models: &[SnapshotModel]) -> String {
+    render_as(models, true)
+}
+
+fn render_as(models: &[SnapshotModel], openmetrics: bool) -> String {
+    let mut out = String::new();
+
+    let labels = |m: &SnapshotModel| format!("model=\"{}\"", escape(&m.name));
+
+    out.push_str("# HELP ollama_model_size_bytes Size of the model weights layer.\n");
+    out.push_str("# TYPE ollama_model_size_bytes gauge\n");
+    for m in models.iter().filter(|m| m.status != Status::Deleted) {
+        writeln!(out, "ollama_model_size_bytes{{{}}} {}", labels(m), m.size).unwrap();
+    }
+
+    out.push_str("# HELP ollama_model_last_used_timestamp_seconds Unix time the model was last loaded.\n");
+    out.push_str("# TYPE ollama_model_last_used_timestamp_seconds gauge\n");
+    for m in models {
+        if let Some(last_used) = m.last_used {
+            writeln!(
+                out,
+                "ollama_model_last_used_timestamp_seconds{{{}}} {}",
+                labels(m),
+                last_used.timestamp()
+            )
+            .unwrap();
+        }
+    }
+
+    let loads = if openmetrics { "ollama_model_loads" } else { "ollama_model_loads_total" };
+    writeln!(out, "# HELP {} Model loads found in the server logs.", loads).unwrap();
+    writeln!(out, "# TYPE {} counter", loads).unwrap();
+    for m in models {
+        writeln!(out, "ollama_model_loads_total{{{}}} {}", labels(m), m.usage_count).unwrap();
+    }
+
+    // Tags sharing a digest share their blobs, so count each digest once.
+    let unique: HashMap<&str, u64> = models
+        .iter()
+        .filter(|m| m.status != Status::Deleted)
+        .map(|m| (m.digest.as_str(), m.size))
+        .collect();
+    out.push_str("# HELP ollama_models_size_bytes Total size of all installed model weights.\n");
+    out.push_str("# TYPE ollama_models_size_bytes gauge\n");
+    writeln!(out, "ollama_models_size_bytes {}", unique.values().sum::<u64>()).unwrap();
+
+    if openmetrics {
+        out.push_str("# EOF\n");
+    }
+    out
+}
@@ -0,0 +1,132 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local};
+use serde::Serialize;
+use serde_json::json;
+use std::{collections::HashMap, time::Duration as StdDuration};
+
+use crate::{
+    api,
+    config::{NotifyConfig, WebhookKind},
+    format_size, InstalledModel, ModelUsage,
+};
+
+/// An installed model (all tags sharing its weights) that hasn't been used lately.
+#[derive(Debug, Serialize)]
+pub struct StaleModel {
+    pub name: String,
+    pub size: u64,
+    pub last_used: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StaleSummary {
+    pub stale_days: i64,
+    pub models: Vec<StaleModel>,
+    pub unused_bytes: u64,
+}
+
+impl StaleSummary {
+    /// A short plain-text summary suitable for a chat message.
+    pub fn text(&self) -> String {
+        let mut text = format!(
+            "omar: {} model(s) unused for {}+ days take up {}",
+            self.models.len(),
+            self.stale_days,
+            format_size(self.unused_bytes)
+        );
+        for model in &self.models {
+            let last_used = model
+                .last_used
+                .map_or("never used".to_string(), |t| format!("last used {}", t.format("%Y-%m-%d")));
+            text.push_str(&format!("\n• {} ({}, {})", model.name, format_size(model.size), last_used));
+        }
+        text
+    }
+}
+
+/// Installed models not loaded within `stale_days`, largest first. Tags sharing
+/// weights are grouped so their size is only counted once.
+pub fn stale_models(
+    installed: &[InstalledModel],
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+    model_usage: &HashMap<String, ModelUsage>,
+    stale_days: i64,
+) -> StaleSummary {
+    let cutoff = Local::now() - Duration::days(stale_days);
+    let mut models: Vec<_> = hash_to_name_size
+        .iter()
+        .filter(|(digest, _)| installed.iter().any(|m| &m.digest == *digest))
+        .filter_map(|(digest, (tags, size))| {
+            let last_used = model_usage.get(digest).map(|u| u.last_used);
+            match last_used {
+                Some(t) if t >= cutoff => None,
+                _ => Some(StaleModel {
+                    name: tags.join(", "),
+                    size: *size,
+                    last_used,
+                }),
+            }
+        })
+        .collect();
+    models.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+    StaleSummary {
+        stale_days,
+        unused_bytes: models.iter().map(|m| m.size).sum(),
+        models,
+    }
+}
+
+fn send(url: &str, kind: WebhookKind, summary: &StaleSummary) -> Result<()> {
+    let payload = match kind {
+        WebhookKind::Slack => json!({ "text": summary.text() }),
+        WebhookKind::Discord => json!({ "content": summary.text() }),
+        WebhookKind::Generic => json!({ "text": summary.text(), "summary": summary }),
+    };
+
+    ureq::post(url)
+        .timeout(StdDuration::from_secs(15))
+        .send_json(payload)
+        .with_context(|| format!("Failed to post to {}", url))?;
+    Ok(())
+}
+
+/// Posts the stale-model summary to every configured webhook once unused space
+/// reaches the configured threshold. With `dry_run` the summary is only printed.
+pub fn run(
+    config: &NotifyConfig,
+    installed: &[InstalledModel],
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+    model_usage: &HashMap<String, ModelUsage>,
+    dry_run: bool,
+) -> Result<()> {
+    let summary = stale_models(installed, hash_to_name_size, model_usage, config.stale_days);
+    println!("{}", summary.text());
+
+    let threshold = (config.min_unused_gb * 1024.0 * 1024.0 * 1024.0) as u64;
+    if summary.models.is_empty() || summary.unused_bytes < threshold {
+        println!("\nBelow the notification threshold of {}; nothing sent.", format_size(threshold));
+        return Ok(());
+    }
+    if config.webhooks.is_empty() {
+        println!("\nNo webhooks configured under [notify] in the config file.");
+        return Ok(());
+    }
+
+    if dry_run {
+        for webhook in &config.webhooks {
+            println!("\nWould notify {}", webhook.url);
+        }
+        return Ok(());
+    }
+
+    let results = api::concurrently(&config.webhooks, |webhook| send(&webhook.url, webhook.kind, &summary));
+    for (webhook, result) in config.webhooks.iter().zip(results) {
+        match result {
+            Ok(()) => println!("\nNotified {}", webhook.url),
+            Err(err) => eprintln!("\n{:#}", err),
+        }
+    }
+
+    Ok(())
+}
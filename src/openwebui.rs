@@ -0,0 +1,99 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Local, TimeZone};
+use rusqlite::{Connection, OpenFlags};
+use serde::Deserialize;
+use std::{collections::HashMap, env, path::PathBuf};
+use tracing::debug;
+
+use crate::{
+    prune::normalize_name,
+    source::{UsageEvent, UsageSource},
+};
+
+/// Where Open WebUI keeps its database when `DATA_DIR` says, as it does in its
+/// Docker image (`/app/backend/data`).
+pub fn default_db() -> Option<PathBuf> {
+    env::var_os("DATA_DIR").map(|dir| PathBuf::from(dir).join("webui.db"))
+}
+
+/// The parts of a chat omar reads. Newer versions keep the messages as a tree under
+/// `history`, older ones as a list.
+#[derive(Deserialize)]
+struct Chat {
+    #[serde(default)]
+    history: Option<ChatHistory>,
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Deserialize)]
+struct ChatHistory {
+    #[serde(default)]
+    messages: HashMap<String, Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(default)]
+    role: String,
+    model: Option<String>,
+    timestamp: Option<i64>,
+}
+
+/// Requests made through Open WebUI, one per answer in its chats, counted as a load
+/// of the model that answered at the time it did. Models Open WebUI reached some
+/// other way, such as OpenAI's, match no installed tag and are left out.
+pub struct OpenWebUiSource {
+    pub db: Option<PathBuf>,
+}
+
+impl UsageSource for OpenWebUiSource {
+    fn name(&self) -> &'static str {
+        "openwebui"
+    }
+
+    fn events(&mut self, hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Result<Vec<UsageEvent>> {
+        let Some(path) = self.db.clone().or_else(default_db) else {
+            bail!("Set openwebui_db under [sources] to the path of Open WebUI's webui.db");
+        };
+        let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open Open WebUI database {}", path.display()))?;
+
+        let digests: HashMap<&str, &str> = hash_to_name_size
+            .iter()
+            .flat_map(|(digest, (names, _))| names.iter().map(move |name| (name.as_str(), digest.as_str())))
+            .collect();
+
+        let mut statement = conn
+            .prepare("SELECT chat FROM chat")
+            .with_context(|| format!("{} isn't an Open WebUI database", path.display()))?;
+        let chats = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut events = Vec::new();
+        for chat in chats {
+            let chat: Chat = match serde_json::from_str(&chat?) {
+                Ok(chat) => chat,
+                Err(err) => {
+                    debug!(%err, "skipping unparseable Open WebUI chat");
+                    continue;
+                }
+            };
+            let messages = chat.history.map(|h| h.messages.into_values().collect()).unwrap_or(chat.messages);
+            for message in messages.into_iter().filter(|m| m.role == "assistant") {
+                let (Some(model), Some(timestamp)) = (message.model, message.timestamp) else {
+                    continue;
+                };
+                let Some(digest) = digests.get(normalize_name(&model).as_str()) else {
+                    debug!(model, "Open WebUI model matches no installed tag");
+                    continue;
+                };
+                // Some versions wrote milliseconds
+                let seconds = if timestamp > 100_000_000_000 { timestamp / 1000 } else { timestamp };
+                if let Some(loaded_at) = Local.timestamp_opt(seconds, 0).single() {
+                    events.push(UsageEvent { digest: digest.to_string(), loaded_at });
+                }
+            }
+        }
+        Ok(events)
+    }
+}
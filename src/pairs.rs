@@ -0,0 +1,128 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    table::{self, Align},
+    ModelUsage,
+};
+
+/// Loads this close together are one session unless told otherwise.
+pub const DEFAULT_GAP_MINUTES: i64 = 30;
+
+/// Two models that were loaded in the same sessions.
+#[derive(Debug, Clone)]
+pub struct Pair {
+    pub a: String,
+    pub b: String,
+    pub a_digest: String,
+    pub b_digest: String,
+    /// Sessions in which both were loaded.
+    pub together: usize,
+    /// Sessions in which each was loaded at all.
+    pub a_sessions: usize,
+    pub b_sessions: usize,
+}
+
+impl Pair {
+    /// The share of the less used model's sessions that included the other one.
+    pub fn strength(&self) -> f64 {
+        self.together as f64 / self.a_sessions.min(self.b_sessions) as f64
+    }
+}
+
+/// Splits every load into sessions, starting a new one wherever nothing was loaded
+/// for longer than `gap`, and returns the digests loaded in each.
+fn sessions(model_usage: &HashMap<String, ModelUsage>, gap: Duration) -> Vec<BTreeSet<&str>> {
+    let mut loads: Vec<(DateTime<Local>, &str)> = model_usage
+        .iter()
+        .flat_map(|(digest, usage)| usage.loads.iter().map(move |t| (*t, digest.as_str())))
+        .collect();
+    loads.sort();
+
+    let mut sessions: Vec<BTreeSet<&str>> = Vec::new();
+    let mut last: Option<DateTime<Local>> = None;
+    for (time, digest) in loads {
+        if last.is_none_or(|last| time - last > gap) {
+            sessions.push(BTreeSet::new());
+        }
+        if let Some(session) = sessions.last_mut() {
+            session.insert(digest);
+        }
+        last = Some(time);
+    }
+    sessions
+}
+
+/// Models loaded together in at least `min_sessions` sessions, most often first.
+/// Loads no more than `gap` apart belong to the same session.
+pub fn find(model_usage: &HashMap<String, ModelUsage>, gap: Duration, min_sessions: usize) -> Vec<Pair> {
+    let sessions = sessions(model_usage, gap);
+
+    let mut per_model: HashMap<&str, usize> = HashMap::new();
+    let mut together: HashMap<(&str, &str), usize> = HashMap::new();
+    for session in &sessions {
+        let digests: Vec<_> = session.iter().copied().collect();
+        for (i, a) in digests.iter().enumerate() {
+            *per_model.entry(a).or_default() += 1;
+            for b in &digests[i + 1..] {
+                *together.entry((a, b)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut pairs: Vec<_> = together
+        .into_iter()
+        .filter(|(_, count)| *count >= min_sessions.max(1))
+        .map(|((a, b), count)| Pair {
+            a: model_usage[a].name.clone(),
+            b: model_usage[b].name.clone(),
+            a_digest: a.to_string(),
+            b_digest: b.to_string(),
+            together: count,
+            a_sessions: per_model[a],
+            b_sessions: per_model[b],
+        })
+        .collect();
+    pairs.sort_by(|x, y| {
+        y.together
+            .cmp(&x.together)
+            .then_with(|| y.strength().total_cmp(&x.strength()))
+            .then_with(|| x.a.cmp(&y.a))
+    });
+    pairs
+}
+
+pub fn run(model_usage: &HashMap<String, ModelUsage>, gap: Duration, min_sessions: usize) -> Result<()> {
+    let pairs = find(model_usage, gap, min_sessions);
+    if pairs.is_empty() {
+        println!("\nNo models were used together in {} or more sessions.\n", min_sessions);
+        return Ok(());
+    }
+
+    println!("\nModels used in the same sessions (loads at most {} minutes apart):", gap.num_minutes());
+    let rows: Vec<_> = pairs
+        .iter()
+        .map(|p| {
+            vec![
+                p.a.clone(),
+                p.b.clone(),
+                p.together.to_string(),
+                format!("{}/{}", p.together, p.a_sessions),
+                format!("{}/{}", p.together, p.b_sessions),
+            ]
+        })
+        .collect();
+    table::print(
+        &[
+            ("Model", Align::Left),
+            ("Used With", Align::Left),
+            ("Sessions", Align::Right),
+            ("Of Model's", Align::Right),
+            ("Of Other's", Align::Right),
+        ],
+        &rows,
+    );
+    println!();
+    Ok(())
+}
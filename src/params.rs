@@ -0,0 +1,98 @@
+//! Parameter counts, for filtering the report with `--params` and grouping it
+//! with `--group-by params`. The count comes from the model's config blob, where
+//! Ollama records it as e.g. `7.6B`, and failing that from a size in the name such
+//! as `llama3:70b` or `mixtral:8x7b`.
+
+use std::collections::HashMap;
+
+use crate::{report::ReportRow, InstalledModel, ModelConfig};
+
+/// Upper bounds, in billions, and labels of the buckets counts are grouped in.
+const BUCKETS: [(f64, &str); 8] = [
+    (1.0, "<1B"),
+    (4.0, "1-3B"),
+    (7.0, "4-6B"),
+    (10.0, "7-9B"),
+    (13.0, "10-12B"),
+    (35.0, "13-34B"),
+    (70.0, "35-69B"),
+    (f64::INFINITY, "70B+"),
+];
+
+/// Label of the group for models whose count isn't known.
+pub const UNKNOWN: &str = "unknown";
+
+/// The bucket a count in billions falls in, by position and label.
+pub fn bucket(billions: f64) -> (usize, &'static str) {
+    // Rounded the way the sizes are named, so 3.8B is a 4B model and 70.6B a 70B one
+    let billions = if billions >= 1.0 { billions.round() } else { billions };
+    BUCKETS
+        .iter()
+        .enumerate()
+        .find(|(_, (below, _))| billions < *below)
+        .map(|(i, (_, label))| (i, *label))
+        .expect("the last bucket has no bound")
+}
+
+/// Reads a count such as `7b`, `1.5B`, `137M` or `8x7b`, in billions.
+pub fn parse_count(text: &str) -> Option<f64> {
+    let text = text.trim().to_ascii_lowercase();
+    let (number, scale) = match text.char_indices().last()? {
+        (i, 'b') => (&text[..i], 1.0),
+        (i, 'm') => (&text[..i], 0.001),
+        (i, 't') => (&text[..i], 1000.0),
+        _ => return None,
+    };
+    let (experts, each) = number.split_once('x').unwrap_or(("1", number));
+    let experts: f64 = experts.parse().ok()?;
+    let each: f64 = each.parse().ok()?;
+    (each.is_finite() && each > 0.0).then_some(experts * each * scale)
+}
+
+/// Parses the value of `--params` into the bucket it names.
+pub fn parse_filter(text: &str) -> Result<usize, String> {
+    parse_count(text)
+        .map(|billions| bucket(billions).0)
+        .ok_or_else(|| format!("'{}' isn't a parameter count such as 7b, 1.5b or 70b", text))
+}
+
+/// The first size in a name, such as the `70b` in `llama3:70b-instruct`.
+fn from_name(name: &str) -> Option<f64> {
+    name.split(|c: char| !(c.is_ascii_alphanumeric() || c == '.'))
+        .filter(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .find_map(parse_count)
+}
+
+/// Parameter counts of the installed models, by digest.
+pub struct Lookup {
+    by_digest: HashMap<String, f64>,
+}
+
+impl Lookup {
+    /// Reads the config blob of one tag of each digest.
+    pub fn new(installed: &[InstalledModel]) -> Lookup {
+        let mut by_digest = HashMap::new();
+        for model in installed {
+            if by_digest.contains_key(&model.digest) {
+                continue;
+            }
+            let count = ModelConfig::read(model).and_then(|config| config.model_type).and_then(|t| parse_count(&t));
+            if let Some(count) = count.or_else(|| from_name(&model.name)) {
+                by_digest.insert(model.digest.clone(), count);
+            }
+        }
+        Lookup { by_digest }
+    }
+
+    /// The count of a row's model, in billions, if it's known.
+    pub fn count(&self, row: &ReportRow) -> Option<f64> {
+        // Deleted models are named after their digest, which says nothing
+        let named_by_digest = row.digest.get(..8).is_some_and(|short| row.name.starts_with(short));
+        self.by_digest.get(&row.digest).copied().or_else(|| from_name(&row.name).filter(|_| !named_by_digest))
+    }
+
+    /// The bucket of a row's model, by position and label, with unknown counts last.
+    pub fn bucket(&self, row: &ReportRow) -> (usize, &'static str) {
+        self.count(row).map_or((BUCKETS.len(), UNKNOWN), bucket)
+    }
+}
@@ -0,0 +1,271 @@
+use glob::{glob, Pattern};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+use tracing::debug;
+
+use crate::config::{self, Defaults};
+
+static CURRENT: RwLock<Option<Paths>> = RwLock::new(None);
+
+/// Everywhere omar reads from or writes to on disk. Every lookup goes through
+/// [`Paths::current`], which is worked out from the platform and the environment
+/// unless something else was installed with [`Paths::install`].
+#[derive(Debug, Clone)]
+pub struct Paths {
+    /// Ollama's models directory, holding `manifests` and `blobs`.
+    pub model_dir: PathBuf,
+    /// More models directories, listed after the first in `OLLAMA_MODELS`, whose
+    /// models are reported along with those in [`Paths::model_dir`].
+    pub extra_model_dirs: Vec<PathBuf>,
+    /// Directory holding the `server*.log` files, if this platform has one.
+    pub log_dir: Option<PathBuf>,
+    /// Config file read when `--config` isn't given.
+    pub config_file: Option<PathBuf>,
+    /// Usage history database used when `--db` isn't given.
+    pub history_db: PathBuf,
+    /// File remembering the manifests already read, or `None` to read them all
+    /// on every scan.
+    pub manifest_cache: Option<PathBuf>,
+}
+
+impl Paths {
+    /// The platform's locations, with `OLLAMA_MODELS` and `OMAR_LOG_DIR` taking
+    /// precedence.
+    pub fn detect() -> Paths {
+        let home = || dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+
+        // OLLAMA_MODELS may list several directories, the way PATH does
+        let mut listed = env::var_os("OLLAMA_MODELS")
+            .map(|dirs| env::split_paths(&dirs).filter(|dir| !dir.as_os_str().is_empty()).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .map(normalize_model_dir);
+        let model_dir = match listed.next() {
+            Some(dir) => {
+                debug!(dir = %dir.display(), "using the models directory from OLLAMA_MODELS");
+                dir
+            }
+            None => platform_model_dir(),
+        };
+        let extra_model_dirs: Vec<_> = listed.collect();
+        if !extra_model_dirs.is_empty() {
+            debug!(dirs = ?extra_model_dirs, "also scanning the other models directories in OLLAMA_MODELS");
+        }
+
+        let log_dir = env::var_os("OMAR_LOG_DIR").map(PathBuf::from).or_else(|| {
+            if cfg!(target_os = "macos") {
+                Some(home().join(".ollama").join("logs"))
+            } else if cfg!(target_os = "windows") {
+                env::var_os("LOCALAPPDATA").map(PathBuf::from).or_else(dirs::data_local_dir).map(|dir| dir.join("Ollama"))
+            } else {
+                // Linux installs log to the journal rather than a file
+                None
+            }
+        });
+
+        // The history database lives in the XDG state directory where there is
+        // one, and the local data directory elsewhere.
+        let history_db = dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("omar")
+            .join("history.db");
+
+        Paths {
+            model_dir,
+            extra_model_dirs,
+            log_dir,
+            config_file: config::default_path(),
+            history_db,
+            manifest_cache: dirs::cache_dir().map(|dir| dir.join("omar").join("manifests.json")),
+        }
+    }
+
+    /// Treats `home` as the home directory of a machine where everything is laid
+    /// out the macOS way, whatever the real platform: models in `.ollama/models`,
+    /// logs in `.ollama/logs`, the config in `.config/omar`, the history in
+    /// `.local/state/omar` and the manifest cache in `.cache/omar`. Nothing outside `home` is read or written.
+    pub fn fixture(home: &Path) -> Paths {
+        Paths {
+            model_dir: home.join(".ollama").join("models"),
+            extra_model_dirs: Vec::new(),
+            log_dir: Some(home.join(".ollama").join("logs")),
+            config_file: Some(home.join(".config").join("omar").join("config.toml")),
+            history_db: home.join(".local").join("state").join("omar").join("history.db"),
+            manifest_cache: Some(home.join(".cache").join("omar").join("manifests.json")),
+        }
+    }
+
+    /// Uses the config file's directories where the environment doesn't set them.
+    pub fn apply_defaults(&mut self, defaults: &Defaults) {
+        if let Some(dir) = defaults.model_dir.as_ref().filter(|_| env::var_os("OLLAMA_MODELS").is_none()) {
+            debug!(dir = %dir.display(), "using the models directory from the config file");
+            self.model_dir = normalize_model_dir(dir.clone());
+        }
+        if let Some(dir) = defaults.log_dir.as_ref().filter(|_| env::var_os("OMAR_LOG_DIR").is_none()) {
+            self.log_dir = Some(dir.clone());
+        }
+    }
+
+    /// The server logs in [`Paths::log_dir`], the current log before rotated ones.
+    pub fn log_paths(&self) -> Vec<PathBuf> {
+        let Some(dir) = &self.log_dir else {
+            debug!("no log directory on this platform; set OMAR_LOG_DIR or log_dir under [defaults]");
+            return Vec::new();
+        };
+        let mut paths: Vec<_> = glob(&glob_pattern(dir, "server*.log"))
+            .map(|paths| paths.filter_map(Result::ok).collect())
+            .unwrap_or_default();
+        paths.sort_by(|a, b| b.file_name().cmp(&a.file_name()));
+        paths
+    }
+
+    /// Makes these the paths every lookup in the process uses from now on.
+    pub fn install(self) {
+        *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = Some(self);
+    }
+
+    /// The installed paths, or the detected ones when none were installed.
+    pub fn current() -> Paths {
+        let mut current = CURRENT.write().unwrap_or_else(|e| e.into_inner());
+        current.get_or_insert_with(Paths::detect).clone()
+    }
+}
+
+/// Where Ollama keeps its models on this platform when `OLLAMA_MODELS` isn't set.
+/// On Linux the install script runs Ollama as a system service that keeps its
+/// models under its own home, while `ollama serve` started by hand uses the
+/// user's, so both are listed, the service's first. The snap's service and its
+/// confined `ollama serve` follow.
+///
+/// On Windows, Ollama finds its home the way Go does, from `USERPROFILE`, which
+/// can differ from the profile folder Windows reports once the profile roams or is
+/// redirected. Every candidate home is listed, Ollama's first.
+pub fn default_model_dirs() -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    if cfg!(target_os = "windows") {
+        let mut homes: Vec<PathBuf> = env::var_os("USERPROFILE").map(PathBuf::from).into_iter().collect();
+        if let (Some(drive), Some(path)) = (env::var_os("HOMEDRIVE"), env::var_os("HOMEPATH")) {
+            let mut joined = drive;
+            joined.push(path);
+            homes.push(PathBuf::from(joined));
+        }
+        homes.push(home);
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for dir in homes.into_iter().map(|home| home.join(".ollama").join("models")) {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        dirs
+    } else if cfg!(target_os = "macos") {
+        vec![home.join(".ollama").join("models")]
+    } else {
+        let snap = home.join("snap").join("ollama");
+        vec![
+            PathBuf::from("/usr/share/ollama/.ollama/models"),
+            home.join(".ollama").join("models"),
+            PathBuf::from("/var/snap/ollama/common/models"),
+            snap.join("common").join(".ollama").join("models"),
+            snap.join("current").join(".ollama").join("models"),
+        ]
+    }
+}
+
+/// The systemd unit Ollama's server logs to on Linux: the snap's when it's
+/// installed as one, or the one the install script sets up.
+pub fn journal_unit() -> &'static str {
+    if Path::new("/snap/ollama").is_dir() {
+        "snap.ollama.listener"
+    } else {
+        "ollama"
+    }
+}
+
+/// The units to read the journal of, the snap's always among them since a snap
+/// can be installed alongside the service the install script set up.
+pub fn journal_units() -> Vec<&'static str> {
+    let mut units = vec![journal_unit()];
+    if !units.contains(&"snap.ollama.listener") {
+        units.push("snap.ollama.listener");
+    }
+    units
+}
+
+/// Where LM Studio keeps downloaded models: `~/.lmstudio/models` since 0.3, and
+/// `~/.cache/lm-studio/models` before.
+pub fn default_lmstudio_dirs() -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    vec![
+        home.join(".lmstudio").join("models"),
+        home.join(".cache").join("lm-studio").join("models"),
+    ]
+}
+
+/// The first of [`default_model_dirs`] that has manifests, or else the first.
+fn platform_model_dir() -> PathBuf {
+    let candidates = default_model_dirs();
+    match candidates.iter().find(|dir| dir.join("manifests").is_dir()) {
+        Some(dir) => {
+            debug!(dir = %dir.display(), "using the models directory that has manifests");
+            dir.clone()
+        }
+        None => {
+            debug!(candidates = ?candidates, "no models directory has manifests, defaulting to the first");
+            candidates[0].clone()
+        }
+    }
+}
+
+/// A glob pattern matching `rest` below `dir`, with `dir` escaped so that a `[` in a
+/// folder name isn't taken as a wildcard. A Windows `\\?\` verbatim prefix is
+/// dropped first, since verbatim paths don't accept the `/` the pattern joins with.
+pub fn glob_pattern(dir: &Path, rest: &str) -> String {
+    let dir = strip_verbatim(dir);
+    Path::new(&Pattern::escape(&dir.to_string_lossy())).join(rest).to_string_lossy().into_owned()
+}
+
+/// `dir` without a `\\?\` or `\\?\UNC\` prefix, as `D:\models` or `\\server\share`.
+/// Other verbatim paths, such as volume GUIDs, have no plain form and are kept as is.
+fn strip_verbatim(dir: &Path) -> PathBuf {
+    let path = dir.to_string_lossy();
+    if let Some(share) = path.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{}", share));
+    }
+    match path.strip_prefix(r"\\?\") {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => PathBuf::from(rest),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Accepts the `.ollama` directory above the models directory too, since that's an
+/// easy mistake to make when setting `OLLAMA_MODELS` by hand.
+fn normalize_model_dir(dir: PathBuf) -> PathBuf {
+    let nested = dir.join("models");
+    if !dir.join("manifests").is_dir() && nested.join("manifests").is_dir() {
+        debug!(dir = %nested.display(), "using the models directory inside the one given");
+        return nested;
+    }
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_verbatim_drops_the_prefix_from_drive_and_unc_paths() {
+        assert_eq!(strip_verbatim(Path::new(r"\\?\D:\models")), PathBuf::from(r"D:\models"));
+        assert_eq!(strip_verbatim(Path::new(r"\\?\UNC\nas\share\models")), PathBuf::from(r"\\nas\share\models"));
+        assert_eq!(strip_verbatim(Path::new(r"\\?\Volume{1234}\models")), PathBuf::from(r"\\?\Volume{1234}\models"));
+        assert_eq!(strip_verbatim(Path::new("/usr/share/ollama")), PathBuf::from("/usr/share/ollama"));
+    }
+
+    #[test]
+    fn glob_pattern_escapes_wildcards_in_the_directory() {
+        assert_eq!(glob_pattern(Path::new("/models [old]"), "x"), format!("/models [[]old[]]{}x", std::path::MAIN_SEPARATOR));
+    }
+}
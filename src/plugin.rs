@@ -0,0 +1,129 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    config::{PluginConfig, PluginKind},
+    source::{UsageEvent, UsageSource},
+};
+
+/// A model as plugins see it, both in their input and in inventory output.
+#[derive(Debug, Serialize, Deserialize)]
+struct PluginModel {
+    name: String,
+    digest: String,
+    #[serde(default)]
+    size: u64,
+}
+
+/// What every plugin receives on stdin: the models omar already knows about.
+#[derive(Serialize)]
+struct PluginInput<'a> {
+    kind: PluginKind,
+    models: &'a [PluginModel],
+}
+
+/// A load reported by a usage plugin, identified by digest or by tag name.
+#[derive(Deserialize)]
+struct PluginEvent {
+    digest: Option<String>,
+    model: Option<String>,
+    loaded_at: DateTime<Local>,
+}
+
+#[derive(Deserialize)]
+struct UsageOutput {
+    events: Vec<PluginEvent>,
+}
+
+#[derive(Deserialize)]
+struct InventoryOutput {
+    models: Vec<PluginModel>,
+}
+
+fn known_models(hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Vec<PluginModel> {
+    let mut models: Vec<_> = hash_to_name_size
+        .iter()
+        .flat_map(|(digest, (names, size))| {
+            names.iter().map(move |name| PluginModel {
+                name: name.clone(),
+                digest: digest.clone(),
+                size: *size,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    models
+}
+
+/// Runs `plugin` with the known models as JSON on stdin and parses its stdout.
+fn run<T: DeserializeOwned>(plugin: &PluginConfig, models: &[PluginModel]) -> Result<T> {
+    let mut child = Command::new(&plugin.command)
+        .args(&plugin.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run plugin '{}' ({})", plugin.name, plugin.command))?;
+
+    let input = serde_json::to_vec(&PluginInput { kind: plugin.kind, models })?;
+    // A plugin that doesn't read its input closes the pipe early, which is fine.
+    let _ = child.stdin.take().expect("stdin is piped").write_all(&input);
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("Plugin '{}' exited with {}", plugin.name, output.status);
+    }
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("Plugin '{}' printed invalid JSON", plugin.name))
+}
+
+/// Model loads reported by an external executable.
+pub struct PluginSource<'a> {
+    pub plugin: &'a PluginConfig,
+}
+
+impl UsageSource for PluginSource<'_> {
+    fn name(&self) -> &'static str {
+        "plugin"
+    }
+
+    fn events(&mut self, hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Result<Vec<UsageEvent>> {
+        let models = known_models(hash_to_name_size);
+        let output: UsageOutput = run(self.plugin, &models)?;
+
+        Ok(output
+            .events
+            .into_iter()
+            .filter_map(|event| {
+                let digest = event.digest.or_else(|| {
+                    let name = event.model?;
+                    models.iter().find(|m| m.name == name).map(|m| m.digest.clone())
+                })?;
+                Some(UsageEvent { digest, loaded_at: event.loaded_at })
+            })
+            .collect())
+    }
+}
+
+/// Adds the models reported by inventory plugins to `hash_to_name_size`, for models
+/// that live somewhere omar can't scan. Digests already known keep their names.
+pub fn extend_inventory(
+    plugins: &[PluginConfig],
+    hash_to_name_size: &mut HashMap<String, (Vec<String>, u64)>,
+) -> Result<()> {
+    for plugin in plugins.iter().filter(|p| p.kind == PluginKind::Inventory) {
+        let output: InventoryOutput = run(plugin, &known_models(hash_to_name_size))?;
+        for model in output.models {
+            let entry = hash_to_name_size.entry(model.digest).or_insert_with(|| (Vec::new(), model.size));
+            if !entry.0.contains(&model.name) {
+                entry.0.push(model.name);
+            }
+        }
+    }
+    Ok(())
+}
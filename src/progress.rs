@@ -0,0 +1,42 @@
+//! Progress bars for the scans that can take long enough to look hung: the
+//! manifests of a large or network-mounted store, and multi-gigabyte logs. They
+//! are drawn on stderr once [`enable`] has been called, and never when stderr
+//! isn't a terminal.
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Shows progress bars from now on. Left off for anything that redraws the
+/// terminal itself or writes to stderr as it goes, such as the TUI or `--explain`.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// A bar counting the `len` manifests of a models directory.
+pub(crate) fn manifests(len: u64) -> ProgressBar {
+    bar(len, "{spinner} Reading manifests {bar:30} {pos}/{len} ({rate}) {wide_msg}")
+}
+
+/// A bar counting the `len` bytes of the log named `name`.
+pub(crate) fn log(len: u64, name: &str) -> ProgressBar {
+    let bar = bar(len, "{spinner} Reading {msg} {bar:30} {bytes}/{total_bytes} ({bytes_per_sec}, {eta} left)");
+    bar.set_message(name.to_string());
+    bar
+}
+
+fn bar(len: u64, template: &str) -> ProgressBar {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template(template)
+        .expect("valid progress template")
+        // indicatif's own {per_sec} keeps four decimals
+        .with_key("rate", |state: &ProgressState, out: &mut dyn Write| {
+            let _ = write!(out, "{:.0}/s", state.per_sec());
+        });
+    // indicatif draws nothing when stderr isn't a terminal
+    ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::stderr()).with_style(style)
+}
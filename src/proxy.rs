@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use glob::glob;
+use serde_json::Value;
+use std::{collections::HashMap, fs};
+use tracing::debug;
+
+use crate::{
+    prune::normalize_name,
+    source::{UsageEvent, UsageSource},
+};
+
+/// Prefixes LiteLLM puts in front of a model to say which provider serves it.
+const PROVIDER_PREFIXES: [&str; 2] = ["ollama_chat/", "ollama/"];
+
+/// Fields a request's time may be logged under, most specific first.
+const TIME_FIELDS: [&str; 6] = ["startTime", "start_time", "timestamp", "time", "created_at", "created"];
+
+/// A time as proxies log it: seconds or milliseconds since the epoch, RFC 3339, or
+/// a local date and time.
+fn parse_time(value: &Value) -> Option<DateTime<Local>> {
+    match value {
+        Value::Number(n) => {
+            let mut seconds = n.as_f64()?;
+            if seconds > 100_000_000_000.0 {
+                seconds /= 1000.0;
+            }
+            Local.timestamp_opt(seconds.trunc() as i64, (seconds.fract() * 1e9) as u32).single()
+        }
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .map(|t| t.with_timezone(&Local))
+            .ok()
+            .or_else(|| {
+                let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f").ok()?;
+                Local.from_local_datetime(&naive).earliest()
+            }),
+        _ => None,
+    }
+}
+
+/// Requests in the JSON-lines logs of a proxy in front of Ollama, such as LiteLLM's
+/// standard logging payload or an OpenAI-compatible gateway's access log. Each line
+/// with a model and a time is a request, attributed to an installed tag by the
+/// model the proxy sent it to, with LiteLLM's provider prefix removed, or through
+/// `aliases` from the name the client asked for.
+pub struct ProxySource<'a> {
+    pub patterns: &'a [String],
+    pub aliases: &'a HashMap<String, String>,
+}
+
+impl ProxySource<'_> {
+    fn resolve<'m>(&self, entry: &Value, digests: &HashMap<String, &'m str>) -> Option<&'m str> {
+        let candidates = ["model", "model_group"].into_iter().filter_map(|field| entry.get(field)?.as_str());
+        for requested in candidates {
+            let model = self.aliases.get(requested).map(String::as_str).unwrap_or(requested);
+            let model = PROVIDER_PREFIXES.iter().find_map(|p| model.strip_prefix(p)).unwrap_or(model);
+            if let Some(digest) = digests.get(&normalize_name(model)) {
+                return Some(digest);
+            }
+        }
+        None
+    }
+}
+
+impl UsageSource for ProxySource<'_> {
+    fn name(&self) -> &'static str {
+        "proxy"
+    }
+
+    fn events(&mut self, hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Result<Vec<UsageEvent>> {
+        let digests: HashMap<String, &str> = hash_to_name_size
+            .iter()
+            .flat_map(|(digest, (names, _))| names.iter().map(move |name| (name.clone(), digest.as_str())))
+            .collect();
+
+        let mut events = Vec::new();
+        for pattern in self.patterns {
+            let paths = glob(pattern).with_context(|| format!("Invalid proxy log pattern {}", pattern))?;
+            for path in paths.filter_map(Result::ok) {
+                let content = fs::read(&path).with_context(|| format!("Failed to read proxy log {}", path.display()))?;
+                let mut unmatched = 0;
+                for line in String::from_utf8_lossy(&content).lines() {
+                    let Ok(entry) = serde_json::from_str::<Value>(line) else {
+                        continue;
+                    };
+                    let Some(loaded_at) = TIME_FIELDS.iter().find_map(|field| parse_time(entry.get(field)?)) else {
+                        continue;
+                    };
+                    match self.resolve(&entry, &digests) {
+                        Some(digest) => events.push(UsageEvent { digest: digest.to_string(), loaded_at }),
+                        None => unmatched += 1,
+                    }
+                }
+                debug!(path = %path.display(), unmatched, "read proxy log");
+            }
+        }
+        Ok(events)
+    }
+}
@@ -0,0 +1,300 @@
+use anyhow::{Context, Result};
+use glob::glob;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{api, paths, strip_digest, InstalledModel};
+
+#[derive(Debug, Deserialize)]
+pub struct Digest {
+    pub digest: String,
+}
+
+/// The parts of any manifest needed to know which blobs it references, whether or
+/// not it has a model layer.
+#[derive(Debug, Deserialize)]
+pub struct AnyManifest {
+    pub config: Option<Digest>,
+    #[serde(default)]
+    pub layers: Vec<Digest>,
+}
+
+/// Normalizes a name reported by the Ollama API to the form used in the report,
+/// dropping the registry host and the implicit `library/` namespace.
+pub fn normalize_name(name: &str) -> String {
+    let mut parts: Vec<&str> = name.split('/').collect();
+    if parts.len() == 3 {
+        parts.remove(0);
+    }
+    if parts.len() == 2 && parts[0] == "library" {
+        parts.remove(0);
+    }
+
+    let name = parts.join("/");
+    if name.contains(':') {
+        name
+    } else {
+        format!("{}:latest", name)
+    }
+}
+
+/// Shown when no server answered, so nothing could be protected as loaded.
+pub const NO_SERVER: &str = "no Ollama server is reachable, so the models it may have loaded couldn't be checked";
+
+/// Digests of the installed models the Ollama server currently has loaded, or
+/// `None` when no server is reachable to ask.
+///
+/// Every tag sharing a loaded digest is protected, since they all point at the same blob.
+pub fn loaded_digests(installed: &[InstalledModel]) -> Result<Option<HashSet<String>>> {
+    let Some(loaded) = api::loaded_models()? else {
+        return Ok(None);
+    };
+    let loaded: HashSet<String> = loaded.iter().map(|n| normalize_name(n)).collect();
+    Ok(Some(installed
+        .iter()
+        .filter(|m| loaded.contains(&m.name))
+        .map(|m| m.digest.clone())
+        .collect()))
+}
+
+/// Asks the user to confirm a destructive action on stdin.
+pub fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Digests referenced by the manifests under `manifest_dir` other than those in
+/// `deleting`. Hidden files are skipped, as the scan skips them, and a manifest
+/// that can't be read or parsed is skipped with a warning.
+fn referenced_blobs(manifest_dir: &Path, deleting: &HashSet<&Path>) -> Result<HashSet<String>> {
+    let mut referenced = HashSet::new();
+
+    for entry in glob(&paths::glob_pattern(manifest_dir, "**/*"))
+        .context("Failed to read glob pattern")?
+    {
+        let path = entry.context("Failed to get manifest path")?;
+        if !path.is_file() || deleting.contains(path.as_path()) {
+            continue;
+        }
+        if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+            continue;
+        }
+        let manifest = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str::<AnyManifest>(&content)?));
+        match manifest {
+            Ok(manifest) => {
+                for layer in manifest.config.iter().chain(manifest.layers.iter()) {
+                    referenced.insert(strip_digest(&layer.digest));
+                }
+            }
+            Err(err) => eprintln!("Warning: skipping manifest {}: {:#}", path.display(), err),
+        }
+    }
+
+    Ok(referenced)
+}
+
+/// Removes empty directories between `path` and `root`, like `ollama rm` does.
+fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if d == root || fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+/// Bytes freed by deleting `selected`, counting only blobs no remaining model references.
+pub fn reclaimable(installed: &[InstalledModel], selected: &HashSet<usize>) -> u64 {
+    let kept: HashSet<&str> = installed
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !selected.contains(i))
+        .flat_map(|(_, m)| m.blobs.iter().map(|(digest, _)| digest.as_str()))
+        .collect();
+
+    let mut freed = HashMap::new();
+    for &i in selected {
+        for (digest, size) in &installed[i].blobs {
+            if !kept.contains(digest.as_str()) {
+                freed.insert(digest.as_str(), *size);
+            }
+        }
+    }
+
+    freed.values().sum()
+}
+
+/// What [`delete_models`] removed.
+#[derive(Debug, Default)]
+pub struct Deleted {
+    /// Names of the models whose manifests were deleted, in the order given.
+    pub names: Vec<String>,
+    /// Bytes of blobs removed.
+    pub freed: u64,
+}
+
+/// Deletes the manifests of `models`, then every blob of theirs that no remaining
+/// manifest references. Models from another of the directories in `OLLAMA_MODELS`
+/// are deleted from there. Nothing is printed, so the TUI can call it too.
+pub fn delete_models(model_dir: &Path, models: &[&InstalledModel]) -> Result<Deleted> {
+    let mut by_dir: BTreeMap<PathBuf, Vec<&InstalledModel>> = BTreeMap::new();
+    for model in models {
+        let dir = if model.path.as_os_str().is_empty() { model_dir.to_path_buf() } else { model.models_dir() };
+        by_dir.entry(dir).or_default().push(model);
+    }
+    let mut deleted = Deleted::default();
+    for (dir, models) in by_dir {
+        deleted.freed += delete_from(&dir, &models)?;
+    }
+    deleted.names = models.iter().map(|model| model.name.clone()).collect();
+    Ok(deleted)
+}
+
+/// [`delete_models`] for models that are all in `model_dir`.
+fn delete_from(model_dir: &Path, models: &[&InstalledModel]) -> Result<u64> {
+    let manifest_dir = model_dir.join("manifests");
+
+    // Read what's left before deleting anything rather than trusting the scan,
+    // which skips manifests without a model layer that may still share blobs
+    let deleting: HashSet<&Path> = models.iter().map(|model| model.path.as_path()).collect();
+    let referenced = referenced_blobs(&manifest_dir, &deleting)?;
+
+    for model in models {
+        fs::remove_file(&model.path)
+            .with_context(|| format!("Failed to delete manifest {}", model.path.display()))?;
+        remove_empty_parents(&model.path, &manifest_dir);
+    }
+
+    let mut freed = 0;
+    let mut removed = HashSet::new();
+    for model in models {
+        for (digest, size) in &model.blobs {
+            if referenced.contains(digest) || !removed.insert(digest.clone()) {
+                continue;
+            }
+            let blob = model_dir.join("blobs").join(format!("sha256-{}", digest));
+            match fs::remove_file(&blob) {
+                Ok(()) => freed += size,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(err).with_context(|| format!("Failed to delete blob {}", blob.display()))
+                }
+            }
+        }
+    }
+
+    Ok(freed)
+}
+
+/// A models directory in the temp dir holding `models`, each a name and the
+/// digests of its config and layers, with the last layer taken as the model. Every
+/// blob gets a 10-byte file. Returns the directory and the models as a scan finds them.
+#[cfg(test)]
+pub(crate) fn test_models_dir(label: &str, models: &[(&str, &[&str])]) -> (PathBuf, Vec<InstalledModel>) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DIRS: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "omar-{}-{}-{}",
+        label,
+        std::process::id(),
+        DIRS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("blobs")).unwrap();
+
+    let installed = models
+        .iter()
+        .map(|(name, digests)| {
+            let (model, tag) = name.split_once(':').unwrap();
+            let path = dir.join("manifests").join("registry.ollama.ai").join("library").join(model).join(tag);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let layer = |digest: &&str| serde_json::json!({ "digest": format!("sha256:{}", digest), "size": 10 });
+            let manifest = serde_json::json!({
+                "config": layer(&digests[0]),
+                "layers": digests[1..].iter().map(layer).collect::<Vec<_>>(),
+            });
+            fs::write(&path, manifest.to_string()).unwrap();
+            for digest in digests.iter() {
+                fs::write(dir.join("blobs").join(format!("sha256-{}", digest)), "0123456789").unwrap();
+            }
+            InstalledModel {
+                name: name.to_string(),
+                path,
+                digest: digests[digests.len() - 1].to_string(),
+                size: 10,
+                blobs: digests.iter().map(|digest| (digest.to_string(), 10)).collect(),
+            }
+        })
+        .collect();
+    (dir, installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(dir: &Path, digest: &str) -> PathBuf {
+        dir.join("blobs").join(format!("sha256-{}", digest))
+    }
+
+    #[test]
+    fn delete_keeps_blobs_another_model_shares() {
+        let (dir, installed) = test_models_dir(
+            "prune",
+            &[("llama3:latest", &["config-a", "shared"]), ("llama3:tuned", &["config-b", "shared"])],
+        );
+
+        let deleted = delete_models(&dir, &[&installed[0]]).unwrap();
+
+        assert_eq!(deleted.names, ["llama3:latest"]);
+        assert_eq!(deleted.freed, 10);
+        assert!(!installed[0].path.exists());
+        assert!(!blob(&dir, "config-a").exists());
+        assert!(blob(&dir, "shared").exists());
+        assert!(installed[1].path.exists());
+        assert!(blob(&dir, "config-b").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    /// A manifest without a model layer isn't in the scan, but its blobs are still
+    /// in use.
+    #[test]
+    fn delete_keeps_blobs_of_manifests_the_scan_skips() {
+        let (dir, installed) = test_models_dir("prune", &[("llava:latest", &["config", "projector", "weights"])]);
+        let other = dir.join("manifests").join("registry.ollama.ai").join("library").join("projector").join("latest");
+        fs::create_dir_all(other.parent().unwrap()).unwrap();
+        fs::write(&other, r#"{"layers":[{"digest":"sha256:projector"}]}"#).unwrap();
+
+        let deleted = delete_models(&dir, &[&installed[0]]).unwrap();
+
+        assert_eq!(deleted.freed, 20);
+        assert!(blob(&dir, "projector").exists());
+        assert!(!blob(&dir, "weights").exists());
+        assert!(!installed[0].path.parent().unwrap().exists(), "empty model directory left behind");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn reclaimable_counts_only_unshared_blobs() {
+        let (dir, installed) = test_models_dir(
+            "prune",
+            &[("llama3:latest", &["config-a", "shared"]), ("llama3:tuned", &["config-b", "shared"])],
+        );
+        assert_eq!(reclaimable(&installed, &HashSet::from([0])), 10);
+        assert_eq!(reclaimable(&installed, &HashSet::from([0, 1])), 30);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Local};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+use crate::{
+    adapter, format_size, get_model_dir, pairs, prune,
+    table::{self, Align},
+    InstalledModel, ModelUsage,
+};
+
+/// A model that could be deleted, with what deleting it would gain and cost.
+struct Candidate<'a> {
+    model: &'a InstalledModel,
+    usage: Option<&'a ModelUsage>,
+    /// Bytes freed by deleting this tag and every other tag sharing its weights.
+    reclaim: u64,
+    /// How much the model would be missed; see [`regret`].
+    regret: f64,
+}
+
+impl Candidate<'_> {
+    /// Gigabytes reclaimed per unit of regret, in the binary units [`format_size`]
+    /// shows the sizes in. Higher is a better candidate.
+    fn score(&self) -> f64 {
+        self.reclaim as f64 / 1_024.0 / 1_024.0 / 1_024.0 / self.regret
+    }
+}
+
+/// How much deleting a model would be missed: `(1 + uses) / (1 + weeks idle)`.
+/// Every load or embedding request adds to it and every idle week wears it down,
+/// so a model loaded a hundred times but untouched for months can still rank below
+/// a fresh pull, while an embedding model loaded once and kept serving doesn't.
+/// Models never loaded count their idle time from when their manifest was written.
+fn regret(model: &InstalledModel, usage: Option<&ModelUsage>, now: DateTime<Local>) -> f64 {
+    let (loads, since) = match usage {
+        Some(u) => (u.recorded_count.unwrap_or(u.usage_count) + u.embeddings, Some(u.last_used.max(u.last_embedding.unwrap_or(u.last_used)))),
+        None => (0, fs::metadata(&model.path).and_then(|m| m.modified()).ok().map(DateTime::from)),
+    };
+    let idle_days = since.map_or(0, |t| (now - t).num_days().max(0));
+    (1 + loads) as f64 / (1.0 + idle_days as f64 / 7.0)
+}
+
+/// Orders installed models from best to worst deletion candidate by [`Candidate::score`],
+/// keeping tags that share weights next to each other. Models whose digest is in
+/// `protected` are left out entirely.
+fn rank_candidates<'a>(
+    installed: &'a [InstalledModel],
+    model_usage: &'a HashMap<String, ModelUsage>,
+    protected: &HashSet<String>,
+) -> Vec<(usize, Candidate<'a>)> {
+    let now = Local::now();
+    let mut candidates: Vec<_> = installed
+        .iter()
+        .enumerate()
+        .filter(|(_, model)| !protected.contains(&model.digest))
+        .map(|(i, model)| {
+            let usage = model_usage.get(&model.digest);
+            let group: HashSet<usize> = installed
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| m.digest == model.digest)
+                .map(|(j, _)| j)
+                .collect();
+            let reclaim = prune::reclaimable(installed, &group);
+            (i, Candidate { model, usage, reclaim, regret: regret(model, usage, now) })
+        })
+        .collect();
+
+    candidates.sort_by(|(_, a), (_, b)| {
+        b.score()
+            .total_cmp(&a.score())
+            .then_with(|| a.model.digest.cmp(&b.model.digest))
+            .then_with(|| a.model.name.cmp(&b.model.name))
+    });
+
+    candidates
+}
+
+/// The candidates as table rows, in the order of `indices`.
+fn candidate_rows(candidates: &[(usize, Candidate)], indices: &[usize]) -> Vec<Vec<String>> {
+    indices
+        .iter()
+        .filter_map(|i| candidates.iter().find(|(j, _)| j == i))
+        .map(|(_, c)| {
+            let (last_used, usage_count) = match c.usage {
+                Some(u) => (u.last_used.format("%Y-%m-%d").to_string(), u.usage_count.to_string()),
+                None => ("never".to_string(), "0".to_string()),
+            };
+            vec![
+                c.model.name.clone(),
+                last_used,
+                usage_count,
+                format_size(c.model.size),
+                format_size(c.reclaim),
+                format!("{:.2}", c.score()),
+            ]
+        })
+        .collect()
+}
+
+const COLUMNS: [(&str, Align); 6] = [
+    ("Model", Align::Left),
+    ("Last Used", Align::Left),
+    ("Usage Count", Align::Right),
+    ("Size", Align::Right),
+    ("Reclaims", Align::Right),
+    ("Score", Align::Right),
+];
+
+/// Picks the smallest set of stale models whose deletion frees at least `target` bytes.
+///
+/// Models are added greedily in candidate order, the most gigabytes reclaimed per
+/// unit of regret first, until the target is met. Then any model that isn't needed
+/// to stay above the target is dropped again, starting with the last one added, the
+/// one with the lowest score.
+fn plan<'a>(
+    installed: &[InstalledModel],
+    candidates: &[(usize, Candidate<'a>)],
+    target: u64,
+) -> (Vec<usize>, u64) {
+    let mut selected = HashSet::new();
+    let mut order = Vec::new();
+
+    for (i, _) in candidates {
+        if prune::reclaimable(installed, &selected) >= target {
+            break;
+        }
+        selected.insert(*i);
+        order.push(*i);
+    }
+
+    for i in order.clone().into_iter().rev() {
+        selected.remove(&i);
+        if prune::reclaimable(installed, &selected) >= target {
+            order.retain(|&j| j != i);
+        } else {
+            selected.insert(i);
+        }
+    }
+
+    (order, prune::reclaimable(installed, &selected))
+}
+
+/// Plans the deletions that free at least `target` bytes, or without a target
+/// lists every candidate ranked by score. Models the server has loaded and tags in
+/// `keep` are never candidates.
+pub fn run(
+    installed: &[InstalledModel],
+    model_usage: &HashMap<String, ModelUsage>,
+    target: Option<u64>,
+    keep: &[String],
+    apply: bool,
+    yes: bool,
+) -> Result<()> {
+    if apply && target.is_none() {
+        bail!("--apply needs a target: pass --free or set free under [defaults] in the config file");
+    }
+
+    let mut protected = prune::loaded_digests(installed)?.unwrap_or_else(|| {
+        eprintln!("Warning: {}", prune::NO_SERVER);
+        HashSet::new()
+    });
+    for model in installed.iter().filter(|m| protected.contains(&m.digest)) {
+        println!("Skipping {}: currently loaded by the Ollama server", model.name);
+    }
+    for model in installed.iter().filter(|m| keep.contains(&m.name)) {
+        println!("Skipping {}: in the keep list", model.name);
+        protected.insert(model.digest.clone());
+    }
+
+    let candidates = rank_candidates(installed, model_usage, &protected);
+
+    let Some(target) = target else {
+        println!("\nDeletion candidates, best first (score is GB reclaimed per unit of regret):");
+        if candidates.is_empty() {
+            println!("\nNo installed models to delete.\n");
+            return Ok(());
+        }
+        let all: Vec<_> = candidates.iter().map(|(i, _)| *i).collect();
+        table::print(&COLUMNS, &candidate_rows(&candidates, &all));
+        println!("\nPass --free to plan how to free a given amount of space.\n");
+        return Ok(());
+    };
+
+    let (order, total) = plan(installed, &candidates, target);
+
+    println!("\nCleanup plan to free at least {}:", format_size(target));
+
+    if order.is_empty() {
+        println!("\nNo installed models to delete.\n");
+        return Ok(());
+    }
+
+    let rows = candidate_rows(&candidates, &order);
+    table::print(&COLUMNS, &rows);
+
+    println!("\nDeleting these {} model(s) frees {} (shared layers counted once).", rows.len(), format_size(total));
+    let planned: HashSet<&str> = order.iter().map(|&i| installed[i].digest.as_str()).collect();
+    for pair in pairs::find(model_usage, Duration::minutes(pairs::DEFAULT_GAP_MINUTES), 2) {
+        let (gone, kept) = match (planned.contains(pair.a_digest.as_str()), planned.contains(pair.b_digest.as_str())) {
+            (true, false) => (&pair.a, &pair.b),
+            (false, true) => (&pair.b, &pair.a),
+            _ => continue,
+        };
+        println!("Note: {} is usually used alongside {} ({} sessions together), which is being kept.", gone, kept, pair.together);
+    }
+    if total < target {
+        println!("Warning: deleting every candidate model only frees {}, short of the {} requested.", format_size(total), format_size(target));
+    }
+    println!();
+
+    if !apply {
+        return Ok(());
+    }
+    let models: Vec<_> = order.iter().map(|&i| &installed[i]).collect();
+    for warning in adapter::orphaned(installed, &models) {
+        println!("Warning: {}", warning);
+    }
+    if !yes && !prune::confirm(&format!("Delete these {} model(s)?", rows.len()))? {
+        println!("Nothing deleted.");
+        return Ok(());
+    }
+
+    let deleted = prune::delete_models(&get_model_dir(), &models)?;
+    for name in &deleted.names {
+        println!("Deleted {}", name);
+    }
+    println!("\nFreed {}.\n", format_size(deleted.freed));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+    use std::path::PathBuf;
+
+    const GB: u64 = 1 << 30;
+
+    fn model(name: &str, digest: &str, size: u64) -> InstalledModel {
+        InstalledModel {
+            name: name.to_string(),
+            path: PathBuf::from("/nonexistent").join(name),
+            digest: digest.to_string(),
+            size,
+            blobs: vec![(digest.to_string(), size)],
+        }
+    }
+
+    /// Usage of a model last loaded `weeks_idle` weeks ago, once.
+    fn idle(digest: &str, weeks_idle: i64) -> ModelUsage {
+        ModelUsage::new(digest, Vec::new(), Status::Active, 0, Local::now() - Duration::weeks(weeks_idle))
+    }
+
+    #[test]
+    fn plan_drops_models_the_target_is_met_without() {
+        // The small model idle for over a year ranks first, but the big one frees
+        // enough on its own
+        let installed = [model("small:latest", "s", GB), model("big:latest", "b", 6 * GB)];
+        let usage = HashMap::from([("s".to_string(), idle("s", 70))]);
+        let candidates = rank_candidates(&installed, &usage, &HashSet::new());
+        assert_eq!(candidates.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0, 1]);
+
+        assert_eq!(plan(&installed, &candidates, 5 * GB), (vec![1], 6 * GB));
+        assert_eq!(plan(&installed, &candidates, GB / 2), (vec![0], GB));
+    }
+
+    #[test]
+    fn plan_deletes_tags_sharing_weights_together() {
+        let installed = [model("a:latest", "x", 4 * GB), model("b:latest", "x", 4 * GB), model("c:latest", "y", GB)];
+        let usage = HashMap::new();
+        let candidates = rank_candidates(&installed, &usage, &HashSet::new());
+
+        let (order, freed) = plan(&installed, &candidates, 2 * GB);
+        assert_eq!(order.iter().copied().collect::<HashSet<_>>(), HashSet::from([0, 1]));
+        assert_eq!(freed, 4 * GB);
+    }
+
+    #[test]
+    fn score_is_in_the_units_sizes_are_shown_in() {
+        let installed = [model("a:latest", "x", 3 * GB)];
+        let usage = HashMap::new();
+        let candidates = rank_candidates(&installed, &usage, &HashSet::new());
+        assert_eq!(format_size(candidates[0].1.reclaim), "3.0 GB");
+        assert_eq!(candidates[0].1.score(), 3.0);
+    }
+
+    #[test]
+    fn protected_models_are_not_candidates() {
+        let installed = [model("a:latest", "x", GB), model("b:latest", "y", GB)];
+        let usage = HashMap::new();
+        let candidates = rank_candidates(&installed, &usage, &HashSet::from(["x".to_string()]));
+        assert_eq!(candidates.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [1]);
+    }
+}
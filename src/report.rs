@@ -0,0 +1,817 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    format_size,
+    kind::ModelKind,
+    schema::Versioned,
+    table::{self, Align},
+    wsl,
+    InstalledModel, ModelConfig, ModelUsage, Status, Trend, Warning,
+};
+
+/// How long a model can sit unused after being pulled before it's flagged, unless
+/// told otherwise.
+pub const DEFAULT_NEVER_USED_DAYS: i64 = 30;
+
+/// An installed model (all tags sharing its weights) pulled long enough ago that it
+/// would have been tried by now, but never loaded: the safest deletion there is.
+#[derive(Debug, Clone, Serialize)]
+pub struct NeverUsed {
+    pub name: String,
+    pub digest: String,
+    pub size: u64,
+    /// When the newest of its tags was pulled or created.
+    pub pulled_at: DateTime<Local>,
+}
+
+/// One row of the report: a model with its usage, or a tag the logs never mention.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRow {
+    pub name: String,
+    /// The installed tags the row stands for, which `name` joins. Empty for a
+    /// deleted model and for files omar doesn't manage.
+    #[serde(skip)]
+    pub tags: Vec<String>,
+    pub digest: String,
+    pub last_used: Option<DateTime<Local>>,
+    pub usage_count: usize,
+    /// Distinct days the model was loaded on.
+    pub days_used: usize,
+    /// Loads within [`Report::window`].
+    pub window_count: Option<usize>,
+    /// All-time loads from the history database, when it's in use.
+    pub all_time: Option<usize>,
+    /// Whether loads are picking up or falling off, for models that have been used.
+    pub trend: Option<Trend>,
+    /// Embedding requests credited to the model, when there were any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embeddings: Option<usize>,
+    /// Requests that offered the model tools, when a debug log showed any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_requests: Option<usize>,
+    /// Requests for JSON output, when a debug log showed any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_requests: Option<usize>,
+    /// Distinct addresses the access log shows requests for the model coming from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clients: Option<usize>,
+    /// Failed loads and runner crashes the logs show, and how many ran out of
+    /// memory, when there were any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failures: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oom_failures: Option<usize>,
+    /// What the model is for, once [`Report::classify`] has worked it out.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ModelKind>,
+    /// SPDX identifier of the license, once [`Report::license`] has read it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// When the model was created, from its config, once [`Report::date`] has read it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Local>>,
+    /// When the newest of the row's tags was pulled or created, from its manifest's
+    /// modification time, once [`Report::date`] has read it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pulled: Option<DateTime<Local>>,
+    pub size: u64,
+}
+
+impl ReportRow {
+    /// When the model was created, or failing that pulled, for sorting and the
+    /// Created column.
+    fn created_or_pulled(&self) -> Option<DateTime<Local>> {
+        self.created.or(self.pulled)
+    }
+}
+
+/// A quick pulse of the server: when and what it loads most, and how the last week
+/// compares with the one before. Weeks are the seven days up to now, not calendar
+/// weeks.
+#[derive(Debug, Clone, Serialize)]
+pub struct Activity {
+    pub busiest_day: NaiveDate,
+    pub busiest_day_loads: usize,
+    pub busiest_model: String,
+    pub busiest_model_loads: usize,
+    pub loads_this_week: usize,
+    pub loads_last_week: usize,
+    /// Models loaded at least once in the last seven days.
+    pub models_this_week: usize,
+}
+
+impl Activity {
+    /// `None` when nothing has been loaded.
+    fn from_usage(model_usage: &HashMap<String, ModelUsage>) -> Option<Activity> {
+        let week_ago = Local::now() - Duration::days(7);
+        let two_weeks_ago = week_ago - Duration::days(7);
+
+        let mut per_day: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        let (mut this_week, mut last_week, mut models_this_week) = (0, 0, 0);
+        for usage in model_usage.values() {
+            let mut touched = false;
+            for load in &usage.loads {
+                *per_day.entry(load.date_naive()).or_default() += 1;
+                if *load >= week_ago {
+                    this_week += 1;
+                    touched = true;
+                } else if *load >= two_weeks_ago {
+                    last_week += 1;
+                }
+            }
+            models_this_week += touched as usize;
+        }
+
+        // Ties go to the most recent day and the first name
+        let (busiest_day, busiest_day_loads) = per_day.into_iter().max_by_key(|(day, count)| (*count, *day))?;
+        let busiest = model_usage
+            .values()
+            .max_by(|a, b| a.usage_count.cmp(&b.usage_count).then_with(|| b.name.cmp(&a.name)))?;
+
+        Some(Activity {
+            busiest_day,
+            busiest_day_loads,
+            busiest_model: busiest.name.clone(),
+            busiest_model_loads: busiest.usage_count,
+            loads_this_week: this_week,
+            loads_last_week: last_week,
+            models_this_week,
+        })
+    }
+
+    /// Label and text for each line of the section.
+    fn lines(&self) -> [(&'static str, String); 4] {
+        [
+            ("Busiest day", format!("{} ({} load(s))", self.busiest_day.format("%Y-%m-%d"), self.busiest_day_loads)),
+            ("Busiest model", format!("{} ({} load(s))", self.busiest_model, self.busiest_model_loads)),
+            (
+                "Loads",
+                format!("{} in the last 7 days, {} in the 7 before", self.loads_this_week, self.loads_last_week),
+            ),
+            ("Models used", format!("{} in the last 7 days", self.models_this_week)),
+        ]
+    }
+}
+
+impl NeverUsed {
+    fn line(&self) -> String {
+        format!(
+            "{} ({}, pulled {}, {} days ago)",
+            self.name,
+            format_size(self.size),
+            self.pulled_at.format("%Y-%m-%d"),
+            (Local::now() - self.pulled_at).num_days()
+        )
+    }
+}
+
+/// One group of installed models in [`Report::groups`], counting every digest
+/// once however many tags share it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Group {
+    pub name: String,
+    pub models: usize,
+    pub usage_count: usize,
+    pub size: u64,
+}
+
+/// What `--group-by` adds up the installed models by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Parameter count: under 1B, 1-3B, 4-6B, 7-9B, 10-12B, 13-34B, 35-69B and 70B+
+    Params,
+    /// What the model is for: chat, embedding, vision or code
+    Type,
+}
+
+impl GroupBy {
+    /// Heading of the group column and of the table.
+    pub fn title(self) -> &'static str {
+        match self {
+            GroupBy::Params => "Parameter Count",
+            GroupBy::Type => "Type",
+        }
+    }
+}
+
+/// The report's three sections, one per [`Status`], in the order they're shown, and the files that
+/// had to be skipped to produce them.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub active: Vec<ReportRow>,
+    pub unlogged: Vec<ReportRow>,
+    pub deleted: Vec<ReportRow>,
+    /// GGUF files from LM Studio's models directory, when it's included. See
+    /// [`lmstudio::scan`](crate::lmstudio::scan).
+    pub lmstudio: Vec<ReportRow>,
+    /// Repositories in the Hugging Face hub cache, when it's included. See
+    /// [`huggingface::scan`](crate::huggingface::scan).
+    pub huggingface: Vec<ReportRow>,
+    /// Models in the Ollama store on the other side of WSL, when it's included. See
+    /// [`wsl::other_model_dirs`](crate::wsl::other_model_dirs).
+    pub wsl: Vec<ReportRow>,
+    pub warnings: Vec<Warning>,
+    /// Shown ahead of the sections, when anything has been loaded.
+    pub activity: Option<Activity>,
+    /// Unlogged models pulled at least [`Report::never_used_days`] ago, largest first.
+    /// Filled in by [`Report::flag_never_used`].
+    pub never_used: Vec<NeverUsed>,
+    pub never_used_days: i64,
+    /// The `--window` the rows' window counts cover, e.g. `30d`.
+    pub window: Option<String>,
+    /// What [`Report::groups`] are grouped by, when `--group-by` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<&'static str>,
+    /// The installed models added up by [`Report::group`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+}
+
+impl Report {
+    /// Sorts models into sections: active and deleted models by last use, then by
+    /// usage count, and unlogged tags by name.
+    pub fn build(
+        hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+        model_usage: &HashMap<String, ModelUsage>,
+    ) -> Report {
+        let row = |m: &ModelUsage| ReportRow {
+            name: m.name.clone(),
+            tags: m.tags.clone(),
+            digest: m.digest.clone(),
+            last_used: Some(m.last_used),
+            usage_count: m.usage_count,
+            days_used: m.days_used,
+            window_count: m.window_count,
+            all_time: m.recorded_count,
+            trend: Some(m.trend()),
+            embeddings: (m.embeddings > 0).then_some(m.embeddings),
+            tool_requests: (m.tool_requests > 0).then_some(m.tool_requests),
+            structured_requests: (m.structured_requests > 0).then_some(m.structured_requests),
+            clients: (!m.clients.is_empty()).then_some(m.clients.len()),
+            failures: (m.failures > 0).then_some(m.failures),
+            oom_failures: (m.failures > 0).then_some(m.oom_failures),
+            kind: None,
+            license: None,
+            created: None,
+            pulled: None,
+            size: m.size,
+        };
+
+        let mut used: Vec<_> = model_usage.values().collect();
+        used.sort_by(|a, b| {
+            b.last_used
+                .cmp(&a.last_used)
+                .then_with(|| b.usage_count.cmp(&a.usage_count))
+        });
+        let (deleted, active): (Vec<_>, Vec<_>) = used.into_iter().partition(|m| m.status == Status::Deleted);
+
+        // Installed tags with no usage, one row per tag even where several share a
+        // digest, including those sharing one whose usage was attributed to another
+        let used_tags: HashSet<(&str, &str)> = model_usage
+            .values()
+            .flat_map(|m| m.tags.iter().map(move |name| (m.digest.as_str(), name.as_str())))
+            .collect();
+        let mut unlogged: Vec<_> = hash_to_name_size
+            .iter()
+            .flat_map(|(digest, (names, size))| names.iter().map(move |n| (n.as_str(), digest, *size)))
+            .filter(|(name, digest, _)| !used_tags.contains(&(digest.as_str(), *name)))
+            .map(|(name, digest, size)| ReportRow {
+                name: name.to_string(),
+                tags: vec![name.to_string()],
+                digest: digest.clone(),
+                last_used: None,
+                usage_count: 0,
+                days_used: 0,
+                window_count: None,
+                all_time: None,
+                trend: None,
+                embeddings: None,
+                tool_requests: None,
+                structured_requests: None,
+                clients: None,
+                failures: None,
+                oom_failures: None,
+                kind: None,
+                license: None,
+                created: None,
+                pulled: None,
+                size,
+            })
+            .collect();
+        unlogged.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Report {
+            active: active.into_iter().map(row).collect(),
+            unlogged,
+            deleted: deleted.into_iter().map(row).collect(),
+            lmstudio: Vec::new(),
+            huggingface: Vec::new(),
+            wsl: Vec::new(),
+            warnings: Vec::new(),
+            activity: Activity::from_usage(model_usage),
+            never_used: Vec::new(),
+            never_used_days: DEFAULT_NEVER_USED_DAYS,
+            window: None,
+            group_by: None,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Drops the rows of every section that `keep` rejects. Call before
+    /// [`Report::flag_never_used`] so only the models kept can be flagged.
+    pub fn retain(&mut self, keep: impl Fn(&ReportRow) -> bool) {
+        for rows in [&mut self.active, &mut self.unlogged, &mut self.deleted, &mut self.lmstudio, &mut self.huggingface, &mut self.wsl] {
+            rows.retain(&keep);
+        }
+    }
+
+    /// Fills in each row's kind from `kind`, except in the Hugging Face cache,
+    /// which holds datasets and models of every sort besides language models.
+    pub fn classify(&mut self, kind: impl Fn(&ReportRow) -> Option<ModelKind>) {
+        for rows in [&mut self.active, &mut self.unlogged, &mut self.deleted, &mut self.lmstudio, &mut self.wsl] {
+            for row in rows.iter_mut() {
+                row.kind = kind(row);
+            }
+        }
+    }
+
+    /// Fills in the license of each installed model, in the active and unlogged
+    /// sections, from `license`.
+    pub fn license(&mut self, license: impl Fn(&ReportRow) -> Option<String>) {
+        for row in self.active.iter_mut().chain(&mut self.unlogged) {
+            row.license = license(row);
+        }
+    }
+
+    /// Adds up the installed models, the active and unlogged ones, into groups by
+    /// the position and name `key` gives each row, in order of position.
+    pub fn group(&mut self, by: GroupBy, key: impl Fn(&ReportRow) -> (usize, &'static str)) {
+        let mut groups: BTreeMap<(usize, &str), (HashSet<&str>, Group)> = BTreeMap::new();
+        for row in self.active.iter().chain(&self.unlogged) {
+            let (position, name) = key(row);
+            let (digests, group) = groups.entry((position, name)).or_insert_with(|| {
+                (HashSet::new(), Group { name: name.to_string(), models: 0, usage_count: 0, size: 0 })
+            });
+            group.usage_count += row.usage_count;
+            if digests.insert(&row.digest) {
+                group.models += 1;
+                group.size += row.size;
+            }
+        }
+        self.groups = groups.into_values().map(|(_, group)| group).collect();
+        self.group_by = Some(by.title());
+    }
+
+    /// Headers and cells for the groups, with a total row, like [`Columns::layout`].
+    fn group_table(&self, by: &str) -> (Vec<(String, Align)>, Vec<Vec<String>>) {
+        let headers = [(by, Align::Left), ("Models", Align::Right), ("Usage Count", Align::Right), ("Size", Align::Right)]
+            .map(|(header, align)| (header.to_string(), align))
+            .to_vec();
+        let row = |name: &str, models: usize, usage_count: usize, size: u64| {
+            vec![name.to_string(), models.to_string(), usage_count.to_string(), format_size(size)]
+        };
+        let mut cells: Vec<_> = self.groups.iter().map(|g| row(&g.name, g.models, g.usage_count, g.size)).collect();
+        cells.push(row(
+            "Total",
+            self.groups.iter().map(|g| g.models).sum(),
+            self.groups.iter().map(|g| g.usage_count).sum(),
+            self.groups.iter().map(|g| g.size).sum(),
+        ));
+        (headers, cells)
+    }
+
+    /// Picks out the unlogged models whose newest tag was pulled at least `min_days`
+    /// ago. Call before [`Report::split_tags`] or [`Report::sort`] change the rows.
+    pub fn flag_never_used(&mut self, installed: &[InstalledModel], min_days: i64) {
+        let cutoff = Local::now() - Duration::days(min_days);
+        let mut by_digest: BTreeMap<&str, (Vec<&str>, u64)> = BTreeMap::new();
+        for row in &self.unlogged {
+            let entry = by_digest.entry(&row.digest).or_insert((Vec::new(), row.size));
+            entry.0.push(&row.name);
+        }
+
+        let mut never_used: Vec<_> = by_digest
+            .into_iter()
+            .filter_map(|(digest, (names, size))| {
+                // A tag without a readable manifest can't be dated, so the digest isn't flagged
+                let pulled_at = installed
+                    .iter()
+                    .filter(|m| m.digest == digest)
+                    .map(|m| m.pulled_at())
+                    .collect::<Option<Vec<_>>>()?
+                    .into_iter()
+                    .max()?;
+                (pulled_at <= cutoff).then(|| NeverUsed {
+                    name: names.join(", "),
+                    digest: digest.to_string(),
+                    size,
+                    pulled_at,
+                })
+            })
+            .collect();
+        never_used.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        self.never_used = never_used;
+        self.never_used_days = min_days;
+    }
+
+    /// Fills in when each installed model was created and pulled, from the config
+    /// and manifests of the tags the row is named after, so call it after
+    /// [`Report::split_tags`] to date each tag on its own.
+    pub fn date(&mut self, installed: &[InstalledModel]) {
+        for row in self.active.iter_mut().chain(&mut self.unlogged) {
+            let tags: Vec<_> = row.tags.iter().filter_map(|name| installed.iter().find(|m| m.name == *name)).collect();
+            row.created = tags.first().and_then(|tag| ModelConfig::read(tag)).and_then(|config| config.created_at());
+            row.pulled = tags.iter().filter_map(|tag| tag.pulled_at()).max();
+        }
+    }
+
+    /// Gives each tag sharing a digest its own active row instead of one row under
+    /// their joined names. The logs only name the weights that were loaded, so
+    /// every tag is credited with all of the digest's usage.
+    pub fn split_tags(&mut self) {
+        self.active = std::mem::take(&mut self.active)
+            .into_iter()
+            .flat_map(|row| {
+                row.tags
+                    .iter()
+                    .map(|name| ReportRow { name: name.clone(), tags: vec![name.clone()], ..row.clone() })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+
+    /// Re-sorts every section. [`Report::build`] already sorts by
+    /// [`SortOrder::LastUsed`].
+    pub fn sort(&mut self, order: SortOrder) {
+        let compare = |a: &ReportRow, b: &ReportRow| match order {
+            SortOrder::LastUsed => b
+                .last_used
+                .cmp(&a.last_used)
+                .then_with(|| b.usage_count.cmp(&a.usage_count))
+                .then_with(|| a.name.cmp(&b.name)),
+            SortOrder::Usage => b
+                .usage_count
+                .cmp(&a.usage_count)
+                .then_with(|| b.last_used.cmp(&a.last_used)),
+            SortOrder::DaysUsed => b
+                .days_used
+                .cmp(&a.days_used)
+                .then_with(|| b.usage_count.cmp(&a.usage_count))
+                .then_with(|| b.last_used.cmp(&a.last_used)),
+            SortOrder::Size => b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)),
+            SortOrder::Name => a.name.cmp(&b.name),
+            SortOrder::Newest => b.created_or_pulled().cmp(&a.created_or_pulled()).then_with(|| a.name.cmp(&b.name)),
+            SortOrder::Oldest => (a.created_or_pulled().is_none(), a.created_or_pulled())
+                .cmp(&(b.created_or_pulled().is_none(), b.created_or_pulled()))
+                .then_with(|| a.name.cmp(&b.name)),
+        };
+        for rows in [&mut self.active, &mut self.unlogged, &mut self.deleted, &mut self.lmstudio, &mut self.huggingface, &mut self.wsl] {
+            rows.sort_by(compare);
+        }
+    }
+
+    /// Each non-empty section with its title and whether it lists usage and sizes.
+    fn sections(&self) -> Vec<(&'static str, &[ReportRow], Columns)> {
+        [
+            ("Active Models", &self.active[..], Columns { usage: true, size: true, file_time: None }),
+            ("Unlogged Models", &self.unlogged[..], Columns { usage: false, size: true, file_time: None }),
+            ("Deleted Models", &self.deleted[..], Columns { usage: true, size: false, file_time: None }),
+            ("LM Studio Models", &self.lmstudio[..], Columns { usage: false, size: true, file_time: Some("Modified") }),
+            (
+                "Hugging Face Cache",
+                &self.huggingface[..],
+                Columns { usage: false, size: true, file_time: Some("Last Accessed") },
+            ),
+            (wsl::SECTION, &self.wsl[..], Columns { usage: false, size: true, file_time: None }),
+        ]
+        .into_iter()
+        .filter(|(_, rows, _)| !rows.is_empty())
+        .collect()
+    }
+}
+
+/// How rows are ordered within each section of the report.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+    /// Most recently used first, then most used
+    #[default]
+    LastUsed,
+    /// Most used first
+    Usage,
+    /// Used on the most distinct days first
+    DaysUsed,
+    /// Largest first
+    Size,
+    /// Alphabetically by name
+    Name,
+    /// Created or pulled most recently first
+    Newest,
+    /// Created or pulled longest ago first
+    Oldest,
+}
+
+/// Which columns a section shows. Unlogged tags have no usage and deleted models
+/// no size. Files from other tools have no usage either, only a time taken from the
+/// file system, under the header given.
+#[derive(Clone, Copy)]
+struct Columns {
+    usage: bool,
+    size: bool,
+    file_time: Option<&'static str>,
+}
+
+impl Columns {
+    /// Headers and cells for `rows`. The type, license and created columns only
+    /// appear once the rows have been classified, licensed and dated. The all-time
+    /// column needs usage history merged in, and the windowed one a `window`.
+    fn layout(self, rows: &[ReportRow], window: Option<&str>) -> (Vec<(String, Align)>, Vec<Vec<String>>) {
+        let all_time = self.usage && rows.iter().any(|r| r.all_time.is_some());
+        let window = window.filter(|_| self.usage);
+        let embeddings = self.usage && rows.iter().any(|r| r.embeddings.is_some());
+        let tools = self.usage && rows.iter().any(|r| r.tool_requests.is_some());
+        let structured = self.usage && rows.iter().any(|r| r.structured_requests.is_some());
+        let clients = self.usage && rows.iter().any(|r| r.clients.is_some());
+        let failures = self.usage && rows.iter().any(|r| r.failures.is_some());
+
+        let kind = rows.iter().any(|r| r.kind.is_some());
+        let license = rows.iter().any(|r| r.license.is_some());
+        let created = rows.iter().any(|r| r.created_or_pulled().is_some());
+
+        let mut headers = vec![("Model".to_string(), Align::Left)];
+        if kind {
+            headers.push(("Type".to_string(), Align::Left));
+        }
+        if license {
+            headers.push(("License".to_string(), Align::Left));
+        }
+        if created {
+            headers.push(("Created".to_string(), Align::Left));
+        }
+        if let Some(header) = self.file_time {
+            headers.push((header.to_string(), Align::Left));
+        }
+        if self.usage {
+            headers.push(("Last Used".to_string(), Align::Left));
+            headers.push(("Usage Count".to_string(), Align::Right));
+        }
+        if let Some(window) = window {
+            headers.push((format!("Uses ({})", window), Align::Right));
+        }
+        if self.usage {
+            headers.push(("Days Used".to_string(), Align::Right));
+            headers.push(("Trend".to_string(), Align::Left));
+        }
+        if embeddings {
+            headers.push(("Embeddings".to_string(), Align::Right));
+        }
+        if tools {
+            headers.push(("Tools".to_string(), Align::Right));
+        }
+        if structured {
+            headers.push(("JSON".to_string(), Align::Right));
+        }
+        if clients {
+            headers.push(("Clients".to_string(), Align::Right));
+        }
+        if failures {
+            headers.push(("Failures".to_string(), Align::Right));
+        }
+        if all_time {
+            headers.push(("All-time".to_string(), Align::Right));
+        }
+        if self.size {
+            headers.push(("Size".to_string(), Align::Right));
+        }
+
+        let cells = rows
+            .iter()
+            .map(|r| {
+                let mut cells = vec![r.name.clone()];
+                if kind {
+                    cells.push(r.kind.map_or(String::new(), |k| k.as_str().to_string()));
+                }
+                if license {
+                    cells.push(r.license.clone().unwrap_or_default());
+                }
+                if created {
+                    cells.push(r.created_or_pulled().map_or(String::new(), |t| t.format("%Y-%m-%d").to_string()));
+                }
+                if self.file_time.is_some() {
+                    cells.push(r.last_used.map_or(String::new(), |t| t.format("%Y-%m-%d").to_string()));
+                }
+                if self.usage {
+                    cells.push(r.last_used.map_or(String::new(), |t| t.format("%Y-%m-%d").to_string()));
+                    cells.push(r.usage_count.to_string());
+                }
+                if window.is_some() {
+                    cells.push(r.window_count.map_or(String::new(), |c| c.to_string()));
+                }
+                if self.usage {
+                    cells.push(r.days_used.to_string());
+                    cells.push(r.trend.map_or(String::new(), |t| t.marker().to_string()));
+                }
+                if embeddings {
+                    cells.push(r.embeddings.map_or(String::new(), |c| c.to_string()));
+                }
+                if tools {
+                    cells.push(r.tool_requests.map_or(String::new(), |c| c.to_string()));
+                }
+                if structured {
+                    cells.push(r.structured_requests.map_or(String::new(), |c| c.to_string()));
+                }
+                if clients {
+                    cells.push(r.clients.map_or(String::new(), |c| c.to_string()));
+                }
+                if failures {
+                    cells.push(match (r.failures, r.oom_failures) {
+                        (Some(failures), Some(oom)) if oom > 0 => format!("{} ({} OOM)", failures, oom),
+                        (failures, _) => failures.map_or(String::new(), |c| c.to_string()),
+                    });
+                }
+                if all_time {
+                    cells.push(r.all_time.map_or(String::new(), |c| c.to_string()));
+                }
+                if self.size {
+                    cells.push(format_size(r.size));
+                }
+                cells
+            })
+            .collect();
+
+        (headers, cells)
+    }
+}
+
+/// Turns a [`Report`] into text in one output format.
+pub trait Renderer {
+    fn render(&self, report: &Report) -> String;
+}
+
+/// Aligned plain-text tables, one per section.
+pub struct TableRenderer;
+
+impl Renderer for TableRenderer {
+    fn render(&self, report: &Report) -> String {
+        let mut out = String::new();
+        if let Some(activity) = &report.activity {
+            out.push_str("\nActivity:\n");
+            for (label, text) in activity.lines() {
+                out.push_str(&format!("  {:<15}{}\n", format!("{}:", label), text));
+            }
+        }
+        if !report.never_used.is_empty() {
+            out.push_str(&format!("\nPulled {}+ days ago and never used:\n", report.never_used_days));
+            for model in &report.never_used {
+                out.push_str(&format!("  {}\n", model.line()));
+            }
+        }
+        for (title, rows, columns) in report.sections() {
+            out.push_str(&format!("\n{}:\n", title));
+            // The unlogged section has always had its title underlined
+            if !columns.usage && columns.file_time.is_none() {
+                out.push_str(&format!("{}\n", "-".repeat(title.len())));
+            }
+            let (headers, cells) = columns.layout(rows, report.window.as_deref());
+            out.push_str(&table::render(&headers, &cells));
+        }
+        if let Some(by) = report.group_by {
+            let (headers, cells) = report.group_table(by);
+            out.push_str(&format!("\nBy {}:\n", by));
+            out.push_str(&table::render(&headers, &cells));
+        }
+        if !report.warnings.is_empty() {
+            out.push_str("\nWarnings:\n");
+            for warning in &report.warnings {
+                out.push_str(&format!("  {}\n", warning));
+            }
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// The whole report as a JSON object with a list per section.
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+    fn render(&self, report: &Report) -> String {
+        let mut out = serde_json::to_string_pretty(&Versioned::new(report)).expect("report serializes to JSON");
+        out.push('\n');
+        out
+    }
+}
+
+/// A single CSV table with the section in the first column and sizes in bytes.
+/// Warnings don't fit the table and are left to the caller, and neither do groups.
+pub struct CsvRenderer;
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Renderer for CsvRenderer {
+    fn render(&self, report: &Report) -> String {
+        let mut out = String::from("section,model,digest,last_used,usage_count,all_time,size_bytes,trend,days_used,window_count,type,license,created,pulled,embeddings,tool_requests,structured_requests,clients,failures,oom_failures\n");
+        let sections = [
+            ("active", &report.active),
+            ("unlogged", &report.unlogged),
+            ("deleted", &report.deleted),
+            ("lmstudio", &report.lmstudio),
+            ("huggingface", &report.huggingface),
+            ("wsl", &report.wsl),
+        ];
+        for (section, rows) in sections {
+            for r in rows {
+                let fields = [
+                    section.to_string(),
+                    r.name.clone(),
+                    r.digest.clone(),
+                    r.last_used.map_or(String::new(), |t| t.to_rfc3339()),
+                    r.usage_count.to_string(),
+                    r.all_time.map_or(String::new(), |c| c.to_string()),
+                    r.size.to_string(),
+                    r.trend.map_or(String::new(), |t| t.as_str().to_string()),
+                    r.days_used.to_string(),
+                    r.window_count.map_or(String::new(), |c| c.to_string()),
+                    r.kind.map_or(String::new(), |k| k.as_str().to_string()),
+                    r.license.clone().unwrap_or_default(),
+                    r.created.map_or(String::new(), |t| t.to_rfc3339()),
+                    r.pulled.map_or(String::new(), |t| t.to_rfc3339()),
+                    r.embeddings.unwrap_or(0).to_string(),
+                    r.tool_requests.unwrap_or(0).to_string(),
+                    r.structured_requests.unwrap_or(0).to_string(),
+                    r.clients.unwrap_or(0).to_string(),
+                    r.failures.unwrap_or(0).to_string(),
+                    r.oom_failures.unwrap_or(0).to_string(),
+                ];
+                out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// GitHub-flavoured Markdown with a heading and a table per section.
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn render(&self, report: &Report) -> String {
+        let mut out = String::new();
+        if let Some(activity) = &report.activity {
+            out.push_str("## Activity\n\n");
+            for (label, text) in activity.lines() {
+                out.push_str(&format!("- **{}:** {}\n", label, text));
+            }
+            out.push('\n');
+        }
+        if !report.never_used.is_empty() {
+            out.push_str(&format!("## Pulled {}+ Days Ago and Never Used\n\n", report.never_used_days));
+            for model in &report.never_used {
+                out.push_str(&format!("- {}\n", model.line()));
+            }
+            out.push('\n');
+        }
+        for (title, rows, columns) in report.sections() {
+            let (headers, cells) = columns.layout(rows, report.window.as_deref());
+            out.push_str(&format!("## {}\n\n", title));
+            out.push_str(&markdown_table(&headers, &cells));
+        }
+        if let Some(by) = report.group_by {
+            let (headers, cells) = report.group_table(by);
+            out.push_str(&format!("## By {}\n\n", by));
+            out.push_str(&markdown_table(&headers, &cells));
+        }
+        if !report.warnings.is_empty() {
+            out.push_str("## Warnings\n\n");
+            for warning in &report.warnings {
+                out.push_str(&format!("- `{}`: {}\n", warning.path.display(), warning.reason));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn markdown_table<S: AsRef<str>>(headers: &[(S, Align)], cells: &[Vec<String>]) -> String {
+    let mut out = format!("| {} |\n", headers.iter().map(|(h, _)| h.as_ref()).collect::<Vec<_>>().join(" | "));
+    let rule: Vec<_> = headers
+        .iter()
+        .map(|(_, align)| if *align == Align::Right { "---:" } else { "---" })
+        .collect();
+    out.push_str(&format!("| {} |\n", rule.join(" | ")));
+    for row in cells {
+        let row: Vec<_> = row.iter().map(|c| c.replace('|', "\\|")).collect();
+        out.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    out.push('\n');
+    out
+}
@@ -0,0 +1,174 @@
+use anyhow::{bail, Context, Result};
+use glob::glob;
+use std::{collections::HashMap, path::Path, process::Command};
+
+use crate::{api::{self, RegistryTls}, format_size, paths, read_manifest, strip_digest, ModelUsage, Status, Warning};
+
+const DEFAULT_REGISTRY: &str = "registry.ollama.ai";
+
+/// A tag recovered from a manifest backup, identified the way a registry knows it.
+struct PullTarget {
+    registry: String,
+    repository: String,
+    tag: String,
+}
+
+impl PullTarget {
+    /// The name to hand to `ollama pull`, shortened the same way ollama shortens it.
+    fn pull_name(&self) -> String {
+        let repository = self.repository.strip_prefix("library/").unwrap_or(&self.repository);
+        if self.registry == DEFAULT_REGISTRY {
+            format!("{}:{}", repository, self.tag)
+        } else {
+            format!("{}/{}:{}", self.registry, repository, self.tag)
+        }
+    }
+}
+
+/// Maps model digests to the tags that used them, from a backup made by `backup-manifests`.
+fn tags_by_digest(backup: &Path) -> Result<HashMap<String, Vec<PullTarget>>> {
+    let mut tags: HashMap<String, Vec<PullTarget>> = HashMap::new();
+
+    for entry in glob(&paths::glob_pattern(backup, "**/*")).context("Failed to read glob pattern")? {
+        let Ok(path) = entry else { continue };
+        if !path.is_file() {
+            continue;
+        }
+
+        let relative = path.strip_prefix(backup)?;
+        let parts: Vec<_> = relative.iter().map(|c| c.to_string_lossy().to_string()).collect();
+        if parts.len() != 4 {
+            continue;
+        }
+
+        let manifest = match read_manifest(&path) {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                // Only costs the names this manifest would have resolved
+                if let Some(warning) = Warning::from_error(&err) {
+                    eprintln!("Skipping {}", warning);
+                }
+                continue;
+            }
+        };
+        if let Some(layer) = manifest
+            .layers
+            .iter()
+            .find(|l| l.media_type == "application/vnd.ollama.image.model")
+        {
+            tags.entry(strip_digest(&layer.digest)).or_default().push(PullTarget {
+                registry: parts[0].clone(),
+                repository: format!("{}/{}", parts[1], parts[2]),
+                tag: parts[3].clone(),
+            });
+        }
+    }
+
+    Ok(tags)
+}
+
+/// Bytes `ollama pull` would download: every layer of the current registry manifest
+/// whose blob isn't already on disk.
+fn download_size(agent: &ureq::Agent, model_dir: &Path, target: &PullTarget) -> Result<u64> {
+    let manifest = api::registry_manifest(agent, &target.registry, &target.repository, &target.tag)?;
+    Ok(manifest
+        .config
+        .iter()
+        .chain(manifest.layers.iter())
+        .filter(|l| {
+            !model_dir
+                .join("blobs")
+                .join(format!("sha256-{}", strip_digest(&l.digest)))
+                .exists()
+        })
+        .map(|l| l.size)
+        .sum())
+}
+
+pub fn run(
+    model_dir: &Path,
+    model_usage: &HashMap<String, ModelUsage>,
+    backup: Option<&Path>,
+    sizes: bool,
+    tls: &RegistryTls,
+    execute: bool,
+) -> Result<()> {
+    let mut deleted: Vec<_> = model_usage
+        .values()
+        .filter(|m| m.status == Status::Deleted)
+        .collect();
+    deleted.sort_by_key(|m| std::cmp::Reverse(m.last_used));
+
+    if deleted.is_empty() {
+        println!("\nNo deleted models found in the logs.\n");
+        return Ok(());
+    }
+
+    let tags = match backup {
+        Some(dir) if !dir.is_dir() => bail!("Backup directory {} does not exist", dir.display()),
+        Some(dir) => tags_by_digest(dir)?,
+        None => HashMap::new(),
+    };
+
+    let mut unresolved = Vec::new();
+    let mut targets = Vec::new();
+    for usage in &deleted {
+        match tags.get(&usage.digest) {
+            Some(found) => targets.extend(found),
+            None => unresolved.push(usage),
+        }
+    }
+
+    // Registry lookups are independent, so ask for all of them at once
+    let notes: Vec<String> = if sizes {
+        let agent = tls.agent()?;
+        api::concurrently(&targets, |target| match download_size(&agent, model_dir, target) {
+            Ok(size) => format!("  # downloads {}", format_size(size)),
+            Err(err) => format!("  # size unavailable: {:#}", err),
+        })
+    } else {
+        vec![String::new(); targets.len()]
+    };
+
+    let mut commands = Vec::new();
+    println!("\nDeleted models that can be pulled again:");
+    for (target, note) in targets.iter().zip(&notes) {
+        let name = target.pull_name();
+        println!("ollama pull {}{}{}", if tls.insecure { "--insecure " } else { "" }, name, note);
+        commands.push(name);
+    }
+
+    if commands.is_empty() {
+        println!("(none)");
+    }
+
+    if !unresolved.is_empty() {
+        println!("\nCould not resolve the name of {} deleted model(s):", unresolved.len());
+        for usage in &unresolved {
+            println!("  {} (last used {})", usage.name, usage.last_used.format("%Y-%m-%d"));
+        }
+        if backup.is_none() {
+            println!("Pass --backup with a directory made by backup-manifests to resolve them.");
+        }
+    }
+    println!();
+
+    if execute {
+        for name in &commands {
+            let mut pull = Command::new("ollama");
+            pull.arg("pull");
+            if tls.insecure {
+                pull.arg("--insecure");
+            }
+            let status = pull
+                .arg(name)
+                .status()
+                .context("Failed to run ollama")?;
+            if !status.success() {
+                bail!("ollama pull {} failed with {}", name, status);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// The version of omar's JSON output, carried in every document as `schema_version`.
+/// Adding fields leaves it alone; it goes up when a field is removed, renamed or
+/// changes type, so consumers can tell a document they can't read.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Any JSON omar prints or serves, with [`SCHEMA_VERSION`] ahead of its own fields.
+#[derive(Serialize)]
+pub struct Versioned<'a, T: Serialize> {
+    schema_version: u32,
+    #[serde(flatten)]
+    inner: &'a T,
+}
+
+impl<'a, T: Serialize> Versioned<'a, T> {
+    pub fn new(inner: &'a T) -> Self {
+        Versioned { schema_version: SCHEMA_VERSION, inner }
+    }
+}
+
+/// Which JSON document to print the schema of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Document {
+    /// The report printed with --format json
+    Report,
+    /// Snapshots written with `snapshot --output` or `collect`, and served at /api/report
+    Snapshot,
+    /// A model's details served at /api/models/<name>
+    Model,
+}
+
+fn nullable(schema: Value) -> Value {
+    json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+fn count() -> Value {
+    json!({ "type": "integer", "minimum": 0 })
+}
+
+fn time() -> Value {
+    json!({ "type": "string", "format": "date-time" })
+}
+
+fn document(title: &str, description: &str, properties: Value, required: &[&str]) -> Value {
+    let mut properties = properties;
+    properties["schema_version"] = json!({ "const": SCHEMA_VERSION });
+    let mut required: Vec<&str> = required.to_vec();
+    required.insert(0, "schema_version");
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "description": description,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn report_row() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "description": "Tag, or the tags sharing these weights joined with \", \"" },
+            "digest": { "type": "string" },
+            "last_used": nullable(time()),
+            "usage_count": count(),
+            "days_used": count(),
+            "window_count": nullable(count()),
+            "all_time": nullable(count()),
+            "trend": nullable(json!({ "enum": ["up", "down", "flat"] })),
+            "embeddings": { "type": "integer", "minimum": 1, "description": "Embedding requests the server log credits the model with; absent when none" },
+            "tool_requests": { "type": "integer", "minimum": 1, "description": "Requests whose prompt in a debug log offered the model tools; absent when none" },
+            "structured_requests": { "type": "integer", "minimum": 1, "description": "Requests a debug log shows asking for JSON or a schema; absent when none" },
+            "clients": { "type": "integer", "minimum": 1, "description": "Distinct addresses the access log shows requests for the model from; absent when none" },
+            "failures": { "type": "integer", "minimum": 1, "description": "Failed loads and runner crashes the logs show; absent when none" },
+            "oom_failures": { "type": "integer", "minimum": 0, "description": "How many of the failures ran out of memory; absent when there were no failures" },
+            "type": { "enum": ["chat", "embedding", "vision", "code"], "description": "What the model is for; absent where it can't be told" },
+            "license": { "type": "string", "description": "SPDX identifier of the license layers joined with \" AND \", \"none\" without one or \"unidentified\"; installed models only" },
+            "created": { "type": "string", "format": "date-time", "description": "When the model was created, from its config; absent where it doesn't say" },
+            "pulled": { "type": "string", "format": "date-time", "description": "Modification time of the newest manifest of the row's tags; installed models only" },
+            "size": { "type": "integer", "minimum": 0, "description": "Bytes" },
+        },
+        "required": ["name", "digest", "last_used", "usage_count", "days_used", "size"],
+    })
+}
+
+fn report() -> Value {
+    let rows = json!({ "type": "array", "items": report_row() });
+    document(
+        "omar report",
+        "The report printed by `omar --format json`.",
+        json!({
+            "active": rows,
+            "unlogged": rows,
+            "deleted": rows,
+            "lmstudio": rows,
+            "huggingface": rows,
+            "wsl": rows,
+            "warnings": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": { "path": { "type": "string" }, "reason": { "type": "string" } },
+                    "required": ["path", "reason"],
+                },
+            },
+            "activity": nullable(json!({
+                "type": "object",
+                "properties": {
+                    "busiest_day": { "type": "string", "format": "date" },
+                    "busiest_day_loads": count(),
+                    "busiest_model": { "type": "string" },
+                    "busiest_model_loads": count(),
+                    "loads_this_week": count(),
+                    "loads_last_week": count(),
+                    "models_this_week": count(),
+                },
+            })),
+            "never_used": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "digest": { "type": "string" },
+                        "size": count(),
+                        "pulled_at": time(),
+                    },
+                    "required": ["name", "digest", "size", "pulled_at"],
+                },
+            },
+            "never_used_days": { "type": "integer" },
+            "window": nullable(json!({ "type": "string" })),
+            "group_by": { "type": "string", "description": "Heading of what the groups are grouped by, with --group-by" },
+            "groups": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "models": { "type": "integer", "minimum": 0, "description": "Distinct weights, however many tags share them" },
+                        "usage_count": count(),
+                        "size": { "type": "integer", "minimum": 0, "description": "Bytes" },
+                    },
+                    "required": ["name", "models", "usage_count", "size"],
+                },
+            },
+        }),
+        &["active", "unlogged", "deleted", "warnings"],
+    )
+}
+
+fn snapshot_model() -> Value {
+    json!({
+        "name": { "type": "string" },
+        "digest": { "type": "string" },
+        "size": { "type": "integer", "minimum": 0, "description": "Bytes, 0 for deleted models" },
+        "status": { "enum": ["active", "unlogged", "deleted"] },
+        "last_used": nullable(time()),
+        "usage_count": count(),
+    })
+}
+
+const SNAPSHOT_MODEL_REQUIRED: [&str; 6] = ["name", "digest", "size", "status", "last_used", "usage_count"];
+
+fn snapshot() -> Value {
+    document(
+        "omar snapshot",
+        "A snapshot written by `omar snapshot --output` or `omar collect`, and served at /api/report.",
+        json!({
+            "taken_at": time(),
+            "host": { "type": "string", "description": "The machine a `collect` snapshot was taken on" },
+            "models": {
+                "type": "array",
+                "items": { "type": "object", "properties": snapshot_model(), "required": SNAPSHOT_MODEL_REQUIRED },
+            },
+        }),
+        &["taken_at", "models"],
+    )
+}
+
+fn model() -> Value {
+    let mut properties = snapshot_model();
+    properties["shared_with"] = json!({ "type": "array", "items": { "type": "string" } });
+    properties["blobs"] = json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": { "digest": { "type": "string" }, "size": count() },
+            "required": ["digest", "size"],
+        },
+    });
+    properties["parameters"] = json!({
+        "type": "object",
+        "description": "Default options from the model's params layer, such as num_ctx, temperature or stop",
+    });
+    let mut required = SNAPSHOT_MODEL_REQUIRED.to_vec();
+    required.extend(["shared_with", "blobs", "parameters"]);
+    document("omar model", "A model's details served at /api/models/<name>.", properties, &required)
+}
+
+/// The JSON Schema of a document.
+pub fn schema(document: Document) -> Value {
+    match document {
+        Document::Report => report(),
+        Document::Snapshot => snapshot(),
+        Document::Model => model(),
+    }
+}
+
+/// Prints the JSON Schema of a document.
+pub fn run(document: Document) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&schema(document))?);
+    Ok(())
+}
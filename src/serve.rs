@@ -0,0 +1,352 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, RwLock},
+    thread,
+    time::Duration,
+};
+use tiny_http::{Header, Response, Server};
+
+use crate::{
+    scan_models, format_size,
+    history::{self, Snapshot, SnapshotModel},
+    index_by_digest, parse_usage, read_manifest, read_params,
+    schema::Versioned,
+    InstalledModel, Status,
+};
+
+/// Everything the server knows as of the last refresh.
+struct State {
+    snapshot: Snapshot,
+    installed: Vec<InstalledModel>,
+}
+
+#[derive(Serialize)]
+struct Blob<'a> {
+    digest: &'a str,
+    size: u64,
+}
+
+#[derive(Serialize)]
+struct ModelDetail<'a> {
+    #[serde(flatten)]
+    model: &'a SnapshotModel,
+    shared_with: Vec<&'a str>,
+    blobs: Vec<Blob<'a>>,
+    /// Default options from the params layer, such as `num_ctx` or `stop`.
+    parameters: BTreeMap<String, serde_json::Value>,
+}
+
+fn scan() -> Result<State> {
+    let installed = scan_models()?;
+    let hash_to_name_size = index_by_digest(&installed);
+    let model_usage = parse_usage(&hash_to_name_size)?;
+    let models = history::current_models(&installed, &model_usage);
+
+    Ok(State {
+        snapshot: Snapshot {
+            taken_at: Local::now(),
+            host: None,
+            models,
+        },
+        installed,
+    })
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let (hi, lo) = (bytes[i + 1], bytes[i + 2]);
+            if hi.is_ascii_hexdigit() && lo.is_ascii_hexdigit() {
+                out.push((hex_value(hi) << 4) | hex_value(lo));
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A tile of the treemap, in percent of the map's width and height.
+struct Tile {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Splits a `width` by `height` rectangle into tiles with the given areas, which
+/// must be sorted largest first and add up to the rectangle's area. Uses the
+/// squarified layout, which keeps tiles as close to square as it can so their
+/// sizes are easy to compare.
+fn squarify(areas: &[f64], width: f64, height: f64) -> Vec<Tile> {
+    // The worst aspect ratio in a row of areas laid along a side of length `side`
+    let worst = |row: &[f64], side: f64| {
+        let sum: f64 = row.iter().sum();
+        let (max, min) = (row[0], row[row.len() - 1]);
+        (side * side * max / (sum * sum)).max(sum * sum / (side * side * min))
+    };
+
+    let mut tiles = Vec::with_capacity(areas.len());
+    let (mut x, mut y, mut w, mut h) = (0.0, 0.0, width, height);
+    let mut rest = areas;
+    while !rest.is_empty() {
+        let side = w.min(h);
+        let mut n = 1;
+        while n < rest.len() && worst(&rest[..n + 1], side) <= worst(&rest[..n], side) {
+            n += 1;
+        }
+        let (row, remaining) = rest.split_at(n);
+        let sum: f64 = row.iter().sum();
+        // Lay the row along the shorter side, then carry on in what's left
+        if w >= h {
+            let row_width = sum / h;
+            let mut top = y;
+            for area in row {
+                tiles.push(Tile { left: x, top, width: row_width, height: area / row_width });
+                top += area / row_width;
+            }
+            x += row_width;
+            w -= row_width;
+        } else {
+            let row_height = sum / w;
+            let mut left = x;
+            for area in row {
+                tiles.push(Tile { left, top: y, width: area / row_height, height: row_height });
+                left += area / row_height;
+            }
+            y += row_height;
+            h -= row_height;
+        }
+        rest = remaining;
+    }
+
+    for tile in &mut tiles {
+        tile.left *= 100.0 / width;
+        tile.width *= 100.0 / width;
+        tile.top *= 100.0 / height;
+        tile.height *= 100.0 / height;
+    }
+    tiles
+}
+
+/// Staleness bands for the treemap's colors: the most days since last use each
+/// covers, its color and its legend label. Models never used take the last color.
+const STALENESS: [(i64, &str, &str); 4] = [
+    (7, "#4caf50", "used this week"),
+    (30, "#cddc39", "this month"),
+    (90, "#ff9800", "this quarter"),
+    (i64::MAX, "#e53935", "longer ago or never"),
+];
+
+fn staleness_color(last_used: Option<DateTime<Local>>, now: DateTime<Local>) -> &'static str {
+    let days = last_used.map_or(i64::MAX, |t| (now - t).num_days());
+    STALENESS.iter().find(|(max, _, _)| days <= *max).map_or(STALENESS[3].1, |(_, color, _)| color)
+}
+
+/// One installed digest on the treemap, under all its tags.
+struct Weights<'a> {
+    names: Vec<&'a str>,
+    size: u64,
+    last_used: Option<DateTime<Local>>,
+}
+
+/// Installed weights as a treemap of linked tiles sized by bytes and colored by how
+/// long ago they were used. Tags sharing weights share a tile.
+fn render_treemap(snapshot: &Snapshot) -> String {
+    let mut by_digest: BTreeMap<&str, Weights> = BTreeMap::new();
+    for m in snapshot.models.iter().filter(|m| m.status != Status::Deleted && m.size > 0) {
+        let weights = by_digest
+            .entry(&m.digest)
+            .or_insert(Weights { names: Vec::new(), size: m.size, last_used: None });
+        weights.names.push(&m.name);
+        weights.last_used = weights.last_used.max(m.last_used);
+    }
+    let mut models: Vec<_> = by_digest.into_values().collect();
+    models.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.names.cmp(&b.names)));
+    if models.is_empty() {
+        return String::new();
+    }
+
+    // The map is twice as wide as it's tall
+    let total: u64 = models.iter().map(|m| m.size).sum();
+    let areas: Vec<f64> = models.iter().map(|m| m.size as f64 / total as f64 * 200.0 * 100.0).collect();
+    let tiles = squarify(&areas, 200.0, 100.0);
+
+    let mut html = String::from("<h2>Disk Usage</h2><div class=\"treemap\">");
+    for (Weights { names, size, last_used }, tile) in models.iter().zip(tiles) {
+        let name = names.join(", ");
+        let last_used_text = last_used.map_or("never used".to_string(), |t| format!("last used {}", t.format("%Y-%m-%d")));
+        write!(
+            html,
+            "<a class=\"tile\" href=\"/api/models/{}\" title=\"{} &#10;{}, {}\" \
+             style=\"left:{:.3}%;top:{:.3}%;width:{:.3}%;height:{:.3}%;background:{}\">{}<br>{}</a>",
+            html_escape(names[0]),
+            html_escape(&name),
+            format_size(*size),
+            last_used_text,
+            tile.left,
+            tile.top,
+            tile.width,
+            tile.height,
+            staleness_color(*last_used, snapshot.taken_at),
+            html_escape(&name),
+            format_size(*size)
+        )
+        .unwrap();
+    }
+    html.push_str("</div><p class=\"legend\">");
+    for (_, color, label) in STALENESS {
+        write!(html, "<span style=\"background:{}\"></span>{} ", color, label).unwrap();
+    }
+    html.push_str("</p>");
+    html
+}
+
+fn render_html(snapshot: &Snapshot, refresh: u64) -> String {
+    let mut html = String::new();
+    write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta http-equiv=\"refresh\" content=\"{}\">\
+         <title>Ollama Model Report</title><style>\
+         body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse}}\
+         td,th{{padding:4px 12px;text-align:left;border-bottom:1px solid #ddd}}td.n{{text-align:right}}\
+         .treemap{{position:relative;width:100%;max-width:1200px;aspect-ratio:2/1}}\
+         .tile{{position:absolute;box-sizing:border-box;border:1px solid #fff;overflow:hidden;padding:4px;\
+         font-size:12px;color:#000;text-decoration:none}}.tile:hover{{filter:brightness(1.15);z-index:1}}\
+         .legend span{{display:inline-block;width:12px;height:12px;margin:0 4px 0 8px}}\
+         </style></head><body><h1>Ollama Model Report</h1><p>Updated {}</p>{}\
+         <h2>Models</h2><table><tr><th>Model</th><th>Status</th><th>Last Used</th><th>Usage Count</th><th>Size</th></tr>",
+        refresh,
+        snapshot.taken_at.format("%Y-%m-%d %H:%M:%S"),
+        render_treemap(snapshot)
+    )
+    .unwrap();
+
+    for m in &snapshot.models {
+        write!(
+            html,
+            "<tr><td><a href=\"/api/models/{}\">{}</a></td><td>{}</td><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td></tr>",
+            html_escape(&m.name),
+            html_escape(&m.name),
+            m.status.as_str(),
+            m.last_used.map_or("never".to_string(), |t| t.format("%Y-%m-%d").to_string()),
+            m.usage_count,
+            if m.size > 0 { format_size(m.size) } else { String::new() }
+        )
+        .unwrap();
+    }
+
+    html.push_str("</table><p><a href=\"/api/report\">JSON</a></p></body></html>");
+    html
+}
+
+fn json_response<T: Serialize>(value: &T) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(serde_json::to_string_pretty(&Versioned::new(value)).unwrap_or_default())
+        .with_header("Content-Type: application/json".parse::<Header>().unwrap())
+}
+
+/// Serves the report over HTTP, rescanning every `refresh` seconds in the background.
+pub fn run(listen: &str, refresh: u64) -> Result<()> {
+    let state = Arc::new(RwLock::new(scan()?));
+
+    let background = Arc::clone(&state);
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(refresh));
+        match scan() {
+            Ok(fresh) => *background.write().unwrap() = fresh,
+            Err(err) => eprintln!("Failed to refresh the report: {:#}", err),
+        }
+    });
+
+    let server = Server::http(listen).map_err(|e| anyhow!("Failed to listen on {}: {}", listen, e))?;
+    println!("Serving the report on http://{}/", listen);
+
+    for request in server.incoming_requests() {
+        let state = state.read().unwrap();
+        let url = request.url().split('?').next().unwrap_or("").to_string();
+
+        let response = if url == "/" {
+            Response::from_string(render_html(&state.snapshot, refresh))
+                .with_header("Content-Type: text/html; charset=utf-8".parse::<Header>().unwrap())
+        } else if url == "/api/report" {
+            json_response(&state.snapshot)
+        } else if let Some(name) = url.strip_prefix("/api/models/") {
+            let name = percent_decode(name);
+            match state.snapshot.models.iter().find(|m| m.name == name) {
+                Some(model) => {
+                    let shared_with = state
+                        .snapshot
+                        .models
+                        .iter()
+                        .filter(|m| m.digest == model.digest && m.name != model.name)
+                        .map(|m| m.name.as_str())
+                        .collect();
+                    let installed = state.installed.iter().find(|m| m.name == model.name);
+                    let blobs = installed
+                        .map(|m| m.blobs.iter().map(|(digest, size)| Blob { digest, size: *size }).collect())
+                        .unwrap_or_default();
+                    let parameters = installed
+                        .and_then(|m| Some(read_params(m, &read_manifest(&m.path).ok()?)))
+                        .unwrap_or_default();
+                    json_response(&ModelDetail { model, shared_with, blobs, parameters })
+                }
+                None => Response::from_string("model not found\n").with_status_code(404),
+            }
+        } else {
+            Response::from_string("not found\n").with_status_code(404)
+        };
+        drop(state);
+
+        if let Err(err) = request.respond(response) {
+            eprintln!("Failed to send response: {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_decodes_escapes() {
+        assert_eq!(percent_decode("/model/llama3%3Alatest"), "/model/llama3:latest");
+        assert_eq!(percent_decode("/model/caf%C3%A9"), "/model/café");
+    }
+
+    #[test]
+    fn percent_decode_keeps_what_isnt_an_escape() {
+        assert_eq!(percent_decode("/100%"), "/100%");
+        assert_eq!(percent_decode("/%zz"), "/%zz");
+        assert_eq!(percent_decode("/%4"), "/%4");
+    }
+
+    #[test]
+    fn percent_decode_doesnt_split_multibyte_characters() {
+        assert_eq!(percent_decode("/%aé"), "/%aé");
+        assert_eq!(percent_decode("/%éa"), "/%éa");
+        assert_eq!(percent_decode("/é%41"), "/éA");
+    }
+}
@@ -0,0 +1,290 @@
+//! `omar show`: one installed model's layers, and the Modelfile that would
+//! recreate it, put back together from those layers. `omar diff-config` compares
+//! the same layers of two models.
+
+use anyhow::{bail, Context, Result};
+use std::fmt::Write;
+
+use crate::{
+    format_size, prune::normalize_name, read_manifest, read_params, strip_digest, InstalledModel, ModelConfig,
+    ModelLayer, ModelManifest,
+};
+
+/// The installed tag called `name`, in any of the forms `ollama` accepts.
+pub fn find<'a>(installed: &'a [InstalledModel], name: &str) -> Result<&'a InstalledModel> {
+    let name = normalize_name(name);
+    match installed.iter().find(|m| m.name == name) {
+        Some(model) => Ok(model),
+        None => bail!("{} is not installed", name),
+    }
+}
+
+fn manifest(model: &InstalledModel) -> Result<ModelManifest> {
+    read_manifest(&model.path).with_context(|| format!("Failed to read the manifest of {}", model.name))
+}
+
+/// The last part of a layer's media type, such as `template` or `params`.
+fn layer_kind(layer: &ModelLayer) -> &str {
+    layer.media_type.rsplit_once('.').map_or(layer.media_type.as_str(), |(_, kind)| kind)
+}
+
+/// Where the blob of `layer` is on disk.
+fn blob_path(model: &InstalledModel, layer: &ModelLayer) -> String {
+    model.models_dir().join("blobs").join(format!("sha256-{}", strip_digest(&layer.digest))).display().to_string()
+}
+
+/// The text of the first layer of `kind`, such as the template or the system prompt.
+fn layer_text(model: &InstalledModel, manifest: &ModelManifest, kind: &str) -> Option<String> {
+    let layer = manifest.layers.iter().find(|layer| layer_kind(layer) == kind)?;
+    std::fs::read_to_string(blob_path(model, layer)).ok().filter(|text| !text.trim().is_empty())
+}
+
+/// Lines of a template or system prompt shown before the rest is cut, without `--full`.
+const PREVIEW_LINES: usize = 12;
+
+/// Characters of one line shown before it's cut, without `--full`.
+const PREVIEW_WIDTH: usize = 120;
+
+/// `text` indented under a heading, cut to [`PREVIEW_LINES`] lines of at most
+/// [`PREVIEW_WIDTH`] characters unless `full`.
+fn preview(out: &mut String, heading: &str, text: &str, full: bool) {
+    let _ = writeln!(out, "\n{}:", heading);
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let shown = if full { lines.len() } else { lines.len().min(PREVIEW_LINES) };
+    for line in &lines[..shown] {
+        match line.char_indices().nth(PREVIEW_WIDTH).filter(|_| !full) {
+            Some((cut, _)) => {
+                let _ = writeln!(out, "  {}...", &line[..cut]);
+            }
+            None => {
+                let _ = writeln!(out, "  {}", line);
+            }
+        }
+    }
+    if shown < lines.len() {
+        let _ = writeln!(out, "  ... {} more line(s); --full shows everything", lines.len() - shown);
+    }
+}
+
+/// Another installed tag with the same weights, to name in `FROM` instead of a
+/// blob path that only exists on this machine. The shortest name is taken, as the
+/// likeliest to be the one the weights were pulled as.
+fn base_tag<'a>(model: &InstalledModel, installed: &'a [InstalledModel]) -> Option<&'a InstalledModel> {
+    installed
+        .iter()
+        .filter(|m| m.digest == model.digest && m.name != model.name)
+        .min_by_key(|m| (m.name.len(), m.name.clone()))
+}
+
+/// A Modelfile block, in triple quotes when it spans lines or has quotes in it.
+fn block(text: &str) -> String {
+    let text = text.trim_end_matches('\n');
+    if text.contains(['\n', '"']) {
+        format!("\"\"\"{}\"\"\"", text)
+    } else {
+        format!("\"{}\"", text)
+    }
+}
+
+/// A Modelfile that recreates `model` with `ollama create`: the weights it was
+/// built on, then its template, system prompt, adapters, parameters, messages and
+/// license. Only approximate, since a manifest doesn't keep everything: a model
+/// built from a GGUF file names its blob, which only exists on this machine.
+pub fn modelfile(model: &InstalledModel, installed: &[InstalledModel]) -> Result<String> {
+    let manifest = manifest(model)?;
+    let mut out = String::new();
+    let _ = writeln!(out, "# Modelfile for {}, reconstructed by omar from its manifest", model.name);
+    let weights = manifest.layers.iter().find(|layer| layer_kind(layer) == "model");
+    match (base_tag(model, installed), weights) {
+        (Some(base), _) => {
+            let _ = writeln!(out, "# The weights, sha256:{}, are also installed as {}", model.digest, base.name);
+            let _ = writeln!(out, "FROM {}", base.name);
+        }
+        (None, Some(weights)) => {
+            let _ = writeln!(out, "FROM {}", blob_path(model, weights));
+        }
+        (None, None) => bail!("{} has no weights layer to build from", model.name),
+    }
+
+    for layer in &manifest.layers {
+        let text = || std::fs::read_to_string(blob_path(model, layer)).unwrap_or_default();
+        match layer_kind(layer) {
+            "adapter" => {
+                let _ = writeln!(out, "ADAPTER {}", blob_path(model, layer));
+            }
+            "template" => {
+                let _ = writeln!(out, "TEMPLATE {}", block(&text()));
+            }
+            "system" => {
+                let _ = writeln!(out, "SYSTEM {}", block(&text()));
+            }
+            "license" => {
+                let _ = writeln!(out, "LICENSE {}", block(&text()));
+            }
+            "messages" => {
+                let messages: Vec<serde_json::Value> = serde_json::from_str(&text()).unwrap_or_default();
+                for message in messages {
+                    let role = message["role"].as_str().unwrap_or("user");
+                    let _ = writeln!(out, "MESSAGE {} {}", role, block(message["content"].as_str().unwrap_or("")));
+                }
+            }
+            _ => {}
+        }
+    }
+    for (name, value) in read_params(model, &manifest) {
+        // Lists such as stop sequences take one line per value
+        let values = match value {
+            serde_json::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let _ = writeln!(out, "PARAMETER {} {}", name, value);
+        }
+    }
+    Ok(out)
+}
+
+/// What `model` is built from: its config, system prompt and template (cut short
+/// unless `full`), default parameters and layers.
+pub fn detail(model: &InstalledModel, installed: &[InstalledModel], full: bool) -> Result<String> {
+    let manifest = manifest(model)?;
+    let mut out = String::new();
+    let mut field = |name: &str, value: &str| {
+        let _ = writeln!(out, "{:<16}{}", name, value);
+    };
+    field("Model", &model.name);
+    field("Digest", &model.digest);
+    field("Size", &format_size(model.size));
+    let shared: Vec<_> = installed
+        .iter()
+        .filter(|m| m.digest == model.digest && m.name != model.name)
+        .map(|m| m.name.as_str())
+        .collect();
+    field("Shared with", &if shared.is_empty() { "-".to_string() } else { shared.join(", ") });
+    if let Some(pulled) = model.pulled_at() {
+        field("Pulled", &pulled.format("%Y-%m-%d %H:%M").to_string());
+    }
+    if let Some(config) = ModelConfig::read_from(model, &manifest) {
+        if let Some(created) = config.created_at() {
+            field("Created", &created.format("%Y-%m-%d %H:%M").to_string());
+        }
+        let or_unknown = |value: Option<String>| value.unwrap_or_else(|| "unknown".to_string());
+        field("Family", &or_unknown(config.model_family));
+        field("Parameters", &or_unknown(config.model_type));
+        field("Quantization", &or_unknown(config.file_type));
+    }
+
+    if let Some(system) = layer_text(model, &manifest, "system") {
+        preview(&mut out, "System prompt", &system, full);
+    }
+    if let Some(template) = layer_text(model, &manifest, "template") {
+        preview(&mut out, "Template", &template, full);
+    }
+
+    let params = read_params(model, &manifest);
+    if !params.is_empty() {
+        let _ = writeln!(out, "\nDefault parameters:");
+        for (name, value) in params {
+            let _ = writeln!(out, "  {:<16}{}", name, value);
+        }
+    }
+
+    let _ = writeln!(out, "\nLayers:");
+    let config = manifest.config.iter().map(|layer| ("config", layer));
+    for (kind, layer) in config.chain(manifest.layers.iter().map(|layer| (layer_kind(layer), layer))) {
+        let digest = strip_digest(&layer.digest);
+        let _ = writeln!(out, "  {:<12}{:<16}{:>10}", kind, &digest[..12.min(digest.len())], format_size(layer.size));
+    }
+    Ok(out)
+}
+
+/// The lines of `a` and `b` in order, each marked `' '` when in both, `'-'` when
+/// only in `a` and `'+'` when only in `b`, from their longest common subsequence.
+fn line_diff<'a>(a: &'a str, b: &'a str) -> Vec<(char, &'a str)> {
+    let a: Vec<&str> = a.trim_end().lines().collect();
+    let b: Vec<&str> = b.trim_end().lines().collect();
+    // common[i][j]: the longest common subsequence of a[i..] and b[j..]
+    let mut common = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] { common[i + 1][j + 1] + 1 } else { common[i + 1][j].max(common[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            lines.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', a[i]));
+            i += 1;
+        } else {
+            lines.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// The configuration of `a` and `b` side by side: the weights they're built on,
+/// their default parameters, system prompts and templates. Rows that differ are
+/// marked with `*`, and differing texts are shown as a diff, `-` lines from `a`
+/// and `+` lines from `b`.
+pub fn diff_config(a: &InstalledModel, b: &InstalledModel, installed: &[InstalledModel]) -> Result<String> {
+    let (manifest_a, manifest_b) = (manifest(a)?, manifest(b)?);
+    let mut out = format!("  {:<16}{:<32}{}\n", "", a.name, b.name);
+    let mut differences = 0;
+    let mut row = |out: &mut String, name: &str, left: &str, right: &str| {
+        let marker = if left == right { ' ' } else { '*' };
+        if left != right {
+            differences += 1;
+        }
+        let _ = writeln!(out, "{} {:<16}{:<32}{}", marker, name, left, right);
+    };
+    row(&mut out, "Base digest", &a.digest[..12.min(a.digest.len())], &b.digest[..12.min(b.digest.len())]);
+    let base = |model: &InstalledModel| base_tag(model, installed).map_or("-".to_string(), |m| m.name.clone());
+    row(&mut out, "Base tag", &base(a), &base(b));
+    let adapters = |manifest: &ModelManifest| manifest.layers.iter().filter(|layer| layer_kind(layer) == "adapter").count();
+    row(&mut out, "Adapters", &adapters(&manifest_a).to_string(), &adapters(&manifest_b).to_string());
+
+    let (params_a, params_b) = (read_params(a, &manifest_a), read_params(b, &manifest_b));
+    let mut names: Vec<&String> = params_a.keys().chain(params_b.keys()).collect();
+    names.sort();
+    names.dedup();
+    if !names.is_empty() {
+        let _ = writeln!(out, "\nDefault parameters:");
+    }
+    for name in names {
+        let value = |params: &std::collections::BTreeMap<String, serde_json::Value>| {
+            params.get(name).map_or("-".to_string(), |value| value.to_string())
+        };
+        row(&mut out, &format!("  {}", name), &value(&params_a), &value(&params_b));
+    }
+
+    for (heading, kind) in [("System prompt", "system"), ("Template", "template")] {
+        let text_a = layer_text(a, &manifest_a, kind).unwrap_or_default();
+        let text_b = layer_text(b, &manifest_b, kind).unwrap_or_default();
+        if text_a == text_b {
+            let state = if text_a.is_empty() { "neither has one" } else { "identical" };
+            let _ = writeln!(out, "\n  {}: {}", heading, state);
+            continue;
+        }
+        differences += 1;
+        let _ = writeln!(out, "\n* {}:", heading);
+        for (marker, line) in line_diff(&text_a, &text_b) {
+            let _ = writeln!(out, "  {} {}", marker, line);
+        }
+    }
+
+    match differences {
+        0 => {
+            let _ = writeln!(out, "\nThe two models are configured the same.");
+        }
+        n => {
+            let _ = writeln!(out, "\n{} difference(s).", n);
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,255 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+use tracing::debug;
+
+use crate::{
+    api,
+    config::{PluginConfig, PluginKind, SourcesConfig},
+    history,
+    openwebui::OpenWebUiSource,
+    plugin::PluginSource,
+    proxy::ProxySource,
+    get_log_paths, prune, resolve_digest, LogCache, ModelUsage, Status, TREND_DAYS,
+};
+
+/// One model load, as seen by a [`UsageSource`].
+#[derive(Debug, Clone)]
+pub struct UsageEvent {
+    pub digest: String,
+    pub loaded_at: DateTime<Local>,
+}
+
+/// Somewhere model loads can be learned from: the server logs, the running server,
+/// the history database, and so on.
+pub trait UsageSource {
+    /// Short name used in the config file.
+    fn name(&self) -> &'static str;
+
+    /// Every load this source knows about. `hash_to_name_size` maps the digests of
+    /// installed models to their tag names, for sources that only see names.
+    fn events(&mut self, hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Result<Vec<UsageEvent>>;
+
+    /// Whether the source records its own `--timing` phases, so collecting from it
+    /// shouldn't be timed as one.
+    fn times_itself(&self) -> bool {
+        false
+    }
+}
+
+impl<T: UsageSource + ?Sized> UsageSource for &RefCell<T> {
+    fn name(&self) -> &'static str {
+        self.borrow().name()
+    }
+
+    fn events(&mut self, hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Result<Vec<UsageEvent>> {
+        self.borrow_mut().events(hash_to_name_size)
+    }
+
+    fn times_itself(&self) -> bool {
+        self.borrow().times_itself()
+    }
+}
+
+/// Sources that can be listed under `[sources]` in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Logs,
+    Api,
+    History,
+    Openwebui,
+    Proxy,
+    Journald,
+}
+
+/// Models the Ollama server has loaded right now, each counted as loaded at the
+/// moment it was asked.
+#[derive(Default)]
+pub struct ApiSource;
+
+impl UsageSource for ApiSource {
+    fn name(&self) -> &'static str {
+        "api"
+    }
+
+    fn events(&mut self, hash_to_name_size: &HashMap<String, (Vec<String>, u64)>) -> Result<Vec<UsageEvent>> {
+        let loaded: HashSet<String> = api::loaded_models()?
+            .unwrap_or_else(|| {
+                eprintln!("Warning: {}", prune::NO_SERVER);
+                Vec::new()
+            })
+            .iter()
+            .map(|n| prune::normalize_name(n))
+            .collect();
+        let now = Local::now();
+        Ok(hash_to_name_size
+            .iter()
+            .filter(|(_, (names, _))| names.iter().any(|name| loaded.contains(name)))
+            .map(|(digest, _)| UsageEvent { digest: digest.clone(), loaded_at: now })
+            .collect())
+    }
+}
+
+/// Loads in the journal of the Ollama service, where Linux installs log instead of
+/// to a file. Read through the same cache as the logs, so the requests and failures
+/// it shows are counted too. With `fallback` the journal is only read when there
+/// are no log files, which would hold the same lines.
+pub struct JournaldSource<'a> {
+    pub cache: &'a RefCell<LogCache>,
+    pub fallback: bool,
+}
+
+impl UsageSource for JournaldSource<'_> {
+    fn name(&self) -> &'static str {
+        "journald"
+    }
+
+    fn events(&mut self, _: &HashMap<String, (Vec<String>, u64)>) -> Result<Vec<UsageEvent>> {
+        if self.fallback && !get_log_paths().is_empty() {
+            debug!("there are log files, so the journal isn't read");
+            return Ok(Vec::new());
+        }
+        let mut cache = self.cache.borrow_mut();
+        cache.read_journal();
+        Ok(cache.journal_events())
+    }
+
+    fn times_itself(&self) -> bool {
+        true
+    }
+}
+
+/// Every load recorded in the history database, including those long gone from the
+/// logs. Loads the daemon saw through the API only count for digests no log ever
+/// mentioned, as in [`history::recorded_usage`].
+pub struct HistorySource {
+    pub db: PathBuf,
+}
+
+impl UsageSource for HistorySource {
+    fn name(&self) -> &'static str {
+        "history"
+    }
+
+    fn events(&mut self, _: &HashMap<String, (Vec<String>, u64)>) -> Result<Vec<UsageEvent>> {
+        let conn = history::open(&self.db)?;
+        let events = history::usage_events(&conn)?;
+        let logged: HashSet<&str> = events
+            .iter()
+            .filter(|(_, source, _)| source == "log")
+            .map(|(digest, _, _)| digest.as_str())
+            .collect();
+        Ok(events
+            .iter()
+            .filter(|(digest, source, _)| source == "log" || !logged.contains(digest.as_str()))
+            .map(|(digest, _, loaded_at)| UsageEvent { digest: digest.clone(), loaded_at: *loaded_at })
+            .collect())
+    }
+}
+
+/// Builds the sources named in the config, in order, followed by any usage plugins.
+/// The logs and the journal are read through `cache` so that the caller can keep
+/// using what was read. The settings of sources that need any come from `settings`.
+pub fn configured<'a>(
+    kinds: &[SourceKind],
+    settings: &'a SourcesConfig,
+    plugins: &'a [PluginConfig],
+    cache: &'a RefCell<LogCache>,
+    db: PathBuf,
+) -> Vec<Box<dyn UsageSource + 'a>> {
+    let mut sources: Vec<Box<dyn UsageSource + 'a>> = Vec::new();
+    for (i, kind) in kinds.iter().enumerate() {
+        // Each is listed once, however often it's named
+        if kinds[..i].contains(kind) {
+            continue;
+        }
+        match kind {
+            SourceKind::Logs => sources.push(Box::new(cache)),
+            SourceKind::Journald => sources.push(Box::new(JournaldSource {
+                cache,
+                fallback: kinds.contains(&SourceKind::Logs),
+            })),
+            SourceKind::Api => sources.push(Box::new(ApiSource)),
+            SourceKind::History => sources.push(Box::new(HistorySource { db: db.clone() })),
+            SourceKind::Openwebui => sources.push(Box::new(OpenWebUiSource { db: settings.openwebui_db.clone() })),
+            SourceKind::Proxy => sources.push(Box::new(ProxySource {
+                patterns: &settings.proxy_logs,
+                aliases: &settings.proxy_aliases,
+            })),
+        }
+    }
+    for plugin in plugins.iter().filter(|p| p.kind == PluginKind::Usage) {
+        sources.push(Box::new(PluginSource { plugin }));
+    }
+    sources
+}
+
+/// Merges the loads from every source into per-model usage. A load several sources
+/// saw at the same moment is only counted once, from the first of them.
+pub fn collect_usage(
+    sources: &mut [Box<dyn UsageSource + '_>],
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+) -> Result<HashMap<String, ModelUsage>> {
+    let mut seen: HashSet<(String, DateTime<Local>)> = HashSet::new();
+    let mut merged = Vec::new();
+
+    for source in sources.iter_mut() {
+        let events = if source.times_itself() {
+            source.events(hash_to_name_size)?
+        } else {
+            let name = source.name();
+            crate::timing::time(|| format!("Usage from {}", name), || source.events(hash_to_name_size))?
+        };
+        let fresh: Vec<_> = events
+            .into_iter()
+            .filter(|e| !seen.contains(&(e.digest.clone(), e.loaded_at)))
+            .collect();
+        debug!(source = source.name(), events = fresh.len(), "collected usage");
+        seen.extend(fresh.iter().map(|e| (e.digest.clone(), e.loaded_at)));
+        merged.extend(fresh);
+    }
+
+    Ok(usage_from_events(&merged, hash_to_name_size))
+}
+
+/// Tallies loads per digest, under the joined names of its tags. Digests no installed tag has are reported as
+/// deleted under a shortened digest.
+pub fn usage_from_events(
+    events: &[UsageEvent],
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+) -> HashMap<String, ModelUsage> {
+    let mut model_usage: HashMap<String, ModelUsage> = HashMap::new();
+    let mut days = HashSet::new();
+    let now = Local::now();
+
+    for event in events {
+        let (tags, size, status) = resolve_digest(hash_to_name_size, &event.digest);
+        let entry = model_usage.entry(event.digest.clone()).or_insert_with(|| {
+            ModelUsage::new(&event.digest, tags, status, size, event.loaded_at)
+        });
+        entry.usage_count += 1;
+        entry.loads.push(event.loaded_at);
+        if days.insert((event.digest.as_str(), event.loaded_at.date_naive())) {
+            entry.days_used += 1;
+        }
+        let age = (now - event.loaded_at).num_days();
+        if age < TREND_DAYS {
+            entry.recent_count += 1;
+        } else if age < 2 * TREND_DAYS {
+            entry.prior_count += 1;
+        }
+        entry.last_used = entry.last_used.max(event.loaded_at);
+    }
+
+    for usage in model_usage.values().filter(|m| m.status == Status::Deleted) {
+        debug!(digest = %usage.digest, loads = usage.usage_count, "no installed model has this digest, reporting it as deleted");
+    }
+
+    model_usage
+}
@@ -0,0 +1,85 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Runs on the remote host with the models and logs directories given to omar, if
+/// any, as `$1` and `$2`. It finds them the way omar would there, saving the
+/// systemd journal, the snap's included, as `server.log` when there are no log
+/// files, and writes a tar of the manifests, the blobs small enough to be configs
+/// rather than weights, and the logs.
+const COLLECT_SCRIPT: &str = r#"
+if [ -n "$1" ] && [ ! -d "$1/manifests" ]; then
+    echo "$1 on $(hostname) has no manifests folder" >&2
+    exit 1
+fi
+models=
+for d in "$1" "$OLLAMA_MODELS" /usr/share/ollama/.ollama/models "$HOME/.ollama/models" /var/snap/ollama/common/models \
+    "$HOME/snap/ollama/common/.ollama/models" "$HOME/snap/ollama/current/.ollama/models"; do
+    if [ -n "$d" ] && [ -d "$d/manifests" ]; then models=$d; break; fi
+done
+if [ -z "$models" ]; then
+    echo "No Ollama models directory found on $(hostname); pass --remote-models-dir" >&2
+    exit 1
+fi
+logs=${2:-$HOME/.ollama/logs}
+if ! ls "$logs"/server*.log >/dev/null 2>&1; then
+    logs=$(mktemp -d)
+    trap 'rm -rf "$logs"' EXIT
+    journalctl -u ollama -u snap.ollama.listener -o cat --no-pager > "$logs/server.log" 2>/dev/null || true
+fi
+cd "$models" || exit 1
+set -- manifests $(find blobs -type f -size -64k 2>/dev/null)
+tar -cf - "$@" -C "$logs" $(cd "$logs" && ls server*.log)
+"#;
+
+/// Quotes `arg` for the remote shell.
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Where the files copied from `host` are kept while the report is made. It serves
+/// as both the models directory and the logs directory.
+pub fn local_dir(host: &str) -> PathBuf {
+    let host: String = host.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '-' }).collect();
+    env::temp_dir().join(format!("omar-ssh-{}", host))
+}
+
+/// Copies the manifests, config blobs and server logs from `host` into `dir` over
+/// ssh, which needs nothing on the host beyond a POSIX shell and tar. `models_dir`
+/// and `logs_dir` are paths on the host, for when they aren't where Ollama keeps them
+/// by default.
+pub fn collect(host: &str, models_dir: Option<&str>, logs_dir: Option<&str>, dir: &Path) -> Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir).with_context(|| format!("Failed to clear {}", dir.display()))?;
+    }
+    fs::create_dir_all(dir.join("blobs")).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let remote = format!("sh -s -- {} {}", quote(models_dir.unwrap_or("")), quote(logs_dir.unwrap_or("")));
+    let mut ssh = Command::new("ssh")
+        .args(["-o", "BatchMode=yes", host, &remote])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run ssh; is it installed and on the PATH?")?;
+    ssh.stdin.take().expect("stdin is piped").write_all(COLLECT_SCRIPT.as_bytes())?;
+
+    let extracted = Command::new("tar")
+        .args(["-xf", "-", "-C"])
+        .arg(dir)
+        .stdin(ssh.stdout.take().expect("stdout is piped"))
+        .output()
+        .context("Failed to run tar")?;
+    // When ssh fails it has said why, and tar's complaint about getting nothing adds no more
+    let status = ssh.wait()?;
+    if !status.success() {
+        bail!("Collecting from {} over ssh failed ({})", host, status);
+    }
+    if !extracted.status.success() {
+        bail!("The files from {} couldn't be unpacked: {}", host, String::from_utf8_lossy(&extracted.stderr).trim());
+    }
+    Ok(())
+}
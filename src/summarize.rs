@@ -0,0 +1,50 @@
+use anyhow::Result;
+use serde_json::json;
+use std::collections::HashMap;
+
+use crate::{
+    api, format_size, notify,
+    report::Report,
+    schema::Versioned,
+    total_size, InstalledModel, ModelUsage,
+};
+
+const SYSTEM_PROMPT: &str = "\
+You summarize reports on the Ollama models installed on a machine for the person who owns it. \
+The report is JSON. `active` models have been loaded while installed, `unlogged` ones are installed \
+but were never seen loaded, and `deleted` ones were loaded but have since been removed. Sizes are in \
+bytes; give them in GB. `never_used` lists models pulled long ago and never loaded, and `stale` the \
+models not loaded for `stale_days` days and the space they take.
+
+Write a short executive summary of two to four sentences on how much space the models take and how \
+much of it is in active use, then at most five concrete recommendations as a bulleted list, such as \
+which models to delete and how much space that frees. Only recommend deleting models that are unused, \
+and never ones used in the last week. Use the model names as given. Don't repeat the data back.";
+
+/// Sends the report to `model` on the local Ollama server and prints what it makes
+/// of it.
+pub fn run(
+    installed: &[InstalledModel],
+    hash_to_name_size: &HashMap<String, (Vec<String>, u64)>,
+    model_usage: &HashMap<String, ModelUsage>,
+    stale_days: i64,
+    model: &str,
+) -> Result<()> {
+    let mut report = Report::build(hash_to_name_size, model_usage);
+    report.flag_never_used(installed, stale_days);
+    let stale = notify::stale_models(installed, hash_to_name_size, model_usage, stale_days);
+    let total = total_size(installed);
+
+    // Compact JSON, as whitespace costs the model context for nothing
+    let prompt = serde_json::to_string(&json!({
+        "total_size": total,
+        "total_size_human": format_size(total),
+        "stale": stale,
+        "report": Versioned::new(&report),
+    }))?;
+
+    eprintln!("Asking {} to summarize the report...", model);
+    let summary = api::chat(model, SYSTEM_PROMPT, &prompt)?;
+    println!("\n{}\n", summary.trim());
+    Ok(())
+}
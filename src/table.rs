@@ -0,0 +1,51 @@
+/// Column alignment for [`render`] and [`print`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Lays out a header, a dashed separator and `rows`, sizing each column to its widest cell.
+pub fn render<S: AsRef<str>>(columns: &[(S, Align)], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (header, _))| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(header.as_ref().len())
+        })
+        .collect();
+
+    let format_row = |cells: Vec<&str>| -> String {
+        cells
+            .iter()
+            .zip(columns)
+            .zip(&widths)
+            .map(|((cell, (_, align)), &width)| match align {
+                Align::Left => format!("{:<width$}", cell, width = width),
+                Align::Right => format!("{:>width$}", cell, width = width),
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = format_row(columns.iter().map(|(h, _)| h.as_ref()).collect());
+    out.push('\n');
+    out.push_str(&widths.iter().map(|&w| "-".repeat(w)).collect::<Vec<_>>().join("  "));
+    out.push('\n');
+    for row in rows {
+        out.push_str(&format_row(row.iter().map(String::as_str).collect()));
+        out.push('\n');
+    }
+    out
+}
+
+/// Prints the table laid out by [`render`].
+pub fn print<S: AsRef<str>>(columns: &[(S, Align)], rows: &[Vec<String>]) {
+    print!("{}", render(columns, rows));
+}
@@ -0,0 +1,58 @@
+//! How long each phase of a run took, for `--timing`: scanning each models
+//! directory, reading each log, the other usage sources and rendering. Nothing is
+//! recorded until [`enable`] is called.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::table::{self, Align};
+
+struct Timings {
+    started: Instant,
+    phases: Vec<(String, Duration)>,
+}
+
+static TIMINGS: Mutex<Option<Timings>> = Mutex::new(None);
+
+/// Starts recording, counting the total from now.
+pub fn enable() {
+    *TIMINGS.lock().unwrap_or_else(|e| e.into_inner()) = Some(Timings { started: Instant::now(), phases: Vec::new() });
+}
+
+/// Runs `f`, recording how long it took under the name `phase` gives.
+pub fn time<T>(phase: impl FnOnce() -> String, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    record(phase, start.elapsed());
+    result
+}
+
+/// Records that `phase` took `took`. The name is only worked out when recording.
+pub fn record(phase: impl FnOnce() -> String, took: Duration) {
+    if let Some(timings) = TIMINGS.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        timings.phases.push((phase(), took));
+    }
+}
+
+/// The phases recorded so far, in the order they finished, with each one's share
+/// of the time since [`enable`] and that total on the last line. `None` if timing
+/// is off.
+pub fn report() -> Option<String> {
+    let guard = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+    let timings = guard.as_ref()?;
+    let total = timings.started.elapsed();
+    let share = |took: Duration| format!("{:.0}%", 100.0 * took.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON));
+    let mut rows: Vec<Vec<String>> = timings
+        .phases
+        .iter()
+        .map(|(phase, took)| vec![phase.clone(), seconds(*took), share(*took)])
+        .collect();
+    rows.push(vec!["Total".to_string(), seconds(total), "100%".to_string()]);
+    Some(table::render(&[("Phase", Align::Left), ("Time", Align::Right), ("Share", Align::Right)], &rows))
+}
+
+fn seconds(took: Duration) -> String {
+    format!("{:.3}s", took.as_secs_f64())
+}
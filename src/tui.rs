@@ -0,0 +1,817 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Margin},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Tabs},
+    DefaultTerminal, Frame,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    adapter, scan_models, format_size, get_model_dir,
+    history::{self, SnapshotModel, Status},
+    index_by_digest, merge_history, prune, read_manifest, read_params, strip_digest, InstalledModel, LogCache,
+    ModelConfig,
+};
+
+/// What has to be typed to confirm a deletion.
+const CONFIRM_WORD: &str = "delete";
+
+/// Days of usage shown in each row's sparkline.
+const SPARKLINE_DAYS: usize = 30;
+
+const PANES: [(Status, &str); 3] = [
+    (Status::Active, "Active"),
+    (Status::Unlogged, "Unlogged"),
+    (Status::Deleted, "Deleted"),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortColumn {
+    Name,
+    LastUsed,
+    UsageCount,
+    Size,
+}
+
+impl SortColumn {
+    fn next(self) -> SortColumn {
+        match self {
+            SortColumn::Name => SortColumn::LastUsed,
+            SortColumn::LastUsed => SortColumn::UsageCount,
+            SortColumn::UsageCount => SortColumn::Size,
+            SortColumn::Size => SortColumn::Name,
+        }
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            SortColumn::Name => "Model",
+            SortColumn::LastUsed => "Last Used",
+            SortColumn::UsageCount => "Usage Count",
+            SortColumn::Size => "Size",
+        }
+    }
+}
+
+/// The full-screen pane opened with Enter.
+struct Detail {
+    title: String,
+    lines: Vec<Line<'static>>,
+    scroll: u16,
+}
+
+/// A deletion waiting for the user to type [`CONFIRM_WORD`].
+struct PendingDelete {
+    /// Manifests of the tags to delete, with how to show each: its name, and its
+    /// models directory when another directory has the same tag.
+    models: Vec<(PathBuf, String)>,
+    /// Tags left out because the server has them loaded.
+    loaded: Vec<String>,
+    /// Warnings for the adapters the deletion leaves without a base tag, and when
+    /// no server could say which models are loaded.
+    warnings: Vec<String>,
+    freed: u64,
+    input: String,
+}
+
+struct App {
+    cache: LogCache,
+    /// History database to keep up to date, or `None` with `--no-history`.
+    db: Option<PathBuf>,
+    installed: Vec<InstalledModel>,
+    models: Vec<SnapshotModel>,
+    /// Loads per day for each digest over the last [`SPARKLINE_DAYS`] days.
+    daily: HashMap<String, Vec<u64>>,
+    scanned_at: chrono::DateTime<Local>,
+    pane: usize,
+    tables: [TableState; 3],
+    error: Option<String>,
+    /// Column picked with `s`; until then each section keeps its usual order.
+    sort: Option<SortColumn>,
+    descending: bool,
+    filter: String,
+    /// Whether keys are going into the filter rather than moving around.
+    typing: bool,
+    detail: Option<Detail>,
+    /// Manifests of the tags marked with space for deletion.
+    marked: HashSet<PathBuf>,
+    pending: Option<PendingDelete>,
+    /// Outcome of the last action, shown until the next key press.
+    message: Option<String>,
+}
+
+impl App {
+    fn new(db: Option<PathBuf>) -> App {
+        App {
+            cache: LogCache::default(),
+            db,
+            installed: Vec::new(),
+            models: Vec::new(),
+            daily: HashMap::new(),
+            scanned_at: Local::now(),
+            pane: 0,
+            tables: Default::default(),
+            error: None,
+            sort: None,
+            descending: false,
+            filter: String::new(),
+            typing: false,
+            detail: None,
+            marked: HashSet::new(),
+            pending: None,
+            message: None,
+        }
+    }
+
+    /// Rescans manifests and reads whatever the logs gained since the last refresh.
+    fn scan(&mut self) -> Result<()> {
+        let installed = scan_models()?;
+        let hash_to_name_size = index_by_digest(&installed);
+        let mut model_usage = self.cache.parse_logs(&hash_to_name_size)?;
+        let daily = match &self.db {
+            Some(db) => {
+                merge_history(db, &self.cache, &hash_to_name_size, &mut model_usage)?;
+                history::daily_usage(&history::open(db)?, SPARKLINE_DAYS)?
+            }
+            None => self.daily_from_logs(),
+        };
+
+        self.models = history::current_models(&installed, &model_usage);
+        self.marked.retain(|path| installed.iter().any(|m| m.path == *path));
+        self.installed = installed;
+        self.daily = daily;
+        self.scanned_at = Local::now();
+        Ok(())
+    }
+
+    fn refresh(&mut self) {
+        self.error = self.scan().err().map(|err| format!("{:#}", err));
+        self.clamp_selection();
+    }
+
+    /// Loads per day from the logs alone, for when history isn't being kept.
+    fn daily_from_logs(&self) -> HashMap<String, Vec<u64>> {
+        let today = Local::now().date_naive();
+        let mut daily: HashMap<String, Vec<u64>> = HashMap::new();
+        for (hash, time) in self.cache.logs().flat_map(|f| f.loads.iter()) {
+            let Some(time) = time else { continue };
+            let age = (today - time.date_naive()).num_days();
+            if (0..SPARKLINE_DAYS as i64).contains(&age) {
+                daily.entry(hash.clone()).or_insert_with(|| vec![0; SPARKLINE_DAYS])
+                    [SPARKLINE_DAYS - 1 - age as usize] += 1;
+            }
+        }
+        daily
+    }
+
+    /// Keeps each section's selection on a row after the rows change under it.
+    fn clamp_selection(&mut self) {
+        for pane in 0..PANES.len() {
+            let len = self.rows(pane).len();
+            let table = &mut self.tables[pane];
+            match table.selected() {
+                _ if len == 0 => table.select(None),
+                Some(i) if i >= len => table.select(Some(len - 1)),
+                None => table.select(Some(0)),
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// The models shown in `pane` that fuzzy-match the filter. Without a chosen sort
+    /// column they're best match first while filtering, and otherwise most recently
+    /// used first, or by name for unlogged tags.
+    fn rows(&self, pane: usize) -> Vec<&SnapshotModel> {
+        let mut scored: Vec<_> = self
+            .models
+            .iter()
+            .filter(|m| m.status == PANES[pane].0)
+            .filter_map(|m| fuzzy_match(&self.filter, &m.name).map(|(score, _)| (m, score)))
+            .collect();
+        if self.sort.is_none() && !self.filter.is_empty() {
+            scored.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+            return scored.into_iter().map(|(m, _)| m).collect();
+        }
+        let mut rows: Vec<_> = scored.into_iter().map(|(m, _)| m).collect();
+
+        match self.sort {
+            None if PANES[pane].0 == Status::Unlogged => rows.sort_by(|a, b| a.name.cmp(&b.name)),
+            None => rows.sort_by(|a, b| {
+                b.last_used
+                    .cmp(&a.last_used)
+                    .then_with(|| b.usage_count.cmp(&a.usage_count))
+            }),
+            Some(column) => {
+                rows.sort_by(|a, b| {
+                    let order = match column {
+                        SortColumn::Name => a.name.cmp(&b.name),
+                        SortColumn::LastUsed => a.last_used.cmp(&b.last_used),
+                        SortColumn::UsageCount => a.usage_count.cmp(&b.usage_count),
+                        SortColumn::Size => a.size.cmp(&b.size),
+                    };
+                    let order = if self.descending { order.reverse() } else { order };
+                    order.then_with(|| a.name.cmp(&b.name))
+                });
+            }
+        }
+        rows
+    }
+
+    fn selected(&self) -> Option<&SnapshotModel> {
+        let index = self.tables[self.pane].selected()?;
+        self.rows(self.pane).get(index).copied()
+    }
+
+    /// Everything known about `model`: the tags and blobs it shares, what its config
+    /// blob says, and what the logs and history recorded about its loads.
+    fn detail(&self, model: &SnapshotModel) -> Detail {
+        let mut lines = Vec::new();
+        let mut field = |name: &str, value: String| lines.push(Line::from(format!("{:<16}{}", name, value)));
+
+        field("Status", model.status.as_str().to_string());
+        field("Digest", model.digest.clone());
+        if model.status != Status::Deleted {
+            field("Size", format_size(model.size));
+        }
+        let shared: Vec<_> = self
+            .models
+            .iter()
+            .filter(|m| m.digest == model.digest && m.name != model.name && m.status != Status::Deleted)
+            .map(|m| m.name.as_str())
+            .collect();
+        field("Shared with", if shared.is_empty() { "-".to_string() } else { shared.join(", ") });
+
+        let installed = self.installed.iter().find(|m| Some(&m.path) == model.manifest.as_ref());
+        let manifest = installed.and_then(|m| read_manifest(&m.path).ok());
+        let config = installed.and_then(ModelConfig::read);
+        if let Some(config) = config {
+            let or_unknown = |value: Option<String>| value.unwrap_or_else(|| "unknown".to_string());
+            field("Family", or_unknown(config.model_family));
+            field("Parameters", or_unknown(config.model_type));
+            field("Quantization", or_unknown(config.file_type));
+        }
+
+        let first_logged = self
+            .cache
+            .files
+            .values()
+            .flat_map(|f| f.loads.iter())
+            .filter(|(hash, _)| *hash == model.digest)
+            .filter_map(|(_, time)| *time)
+            .min();
+        let recorded = self
+            .db
+            .as_ref()
+            .and_then(|db| history::open(db).ok())
+            .and_then(|conn| history::recorded_usage(&conn).ok())
+            .and_then(|mut usage| usage.remove(&model.digest));
+        let first_used = match (&recorded, first_logged) {
+            (Some(recorded), Some(logged)) => Some(recorded.first_used.min(logged)),
+            (Some(recorded), None) => Some(recorded.first_used),
+            (None, logged) => logged,
+        };
+        let timestamp = |time: Option<DateTime<Local>>| {
+            time.map_or("never".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string())
+        };
+        field("First used", timestamp(first_used));
+        field("Last used", timestamp(model.last_used));
+        field("Loads in logs", model.usage_count.to_string());
+        if let Some(recorded) = &recorded {
+            field("All-time loads", recorded.count.max(model.usage_count).to_string());
+        }
+
+        let stats = self.cache.runner_stats().remove(&model.digest).unwrap_or_default();
+        field(
+            "Avg load time",
+            if stats.timed_loads == 0 {
+                "n/a".to_string()
+            } else {
+                format!("{:.1}s over {} load(s)", stats.load_seconds / stats.timed_loads as f64, stats.timed_loads)
+            },
+        );
+        field(
+            "Avg speed",
+            if stats.timed_evals == 0 {
+                "n/a".to_string()
+            } else {
+                format!("{:.1} tokens/s", stats.tokens_per_second / stats.timed_evals as f64)
+            },
+        );
+        field("Errors logged", stats.errors.to_string());
+        if stats.embeddings > 0 {
+            field("Embedding requests", stats.embeddings.to_string());
+        }
+        if stats.tool_requests > 0 {
+            field("Tool requests", stats.tool_requests.to_string());
+        }
+        if stats.structured_requests > 0 {
+            field("JSON requests", stats.structured_requests.to_string());
+        }
+        if !stats.clients.is_empty() {
+            field("Clients", stats.clients.len().to_string());
+        }
+        if stats.failures > 0 {
+            field("Load failures", format!("{} ({} out of memory)", stats.failures, stats.oom_failures));
+        }
+
+        if let Some(manifest) = manifest {
+            let params = installed.map(|m| read_params(m, &manifest)).unwrap_or_default();
+            if !params.is_empty() {
+                lines.push(Line::from(""));
+                lines.push(Line::from("Default parameters").bold());
+            }
+            for (name, value) in params {
+                // Strings stay quoted and escaped, so odd stop sequences show up as they are
+                let value = match value {
+                    serde_json::Value::Array(items) => items.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "),
+                    value => value.to_string(),
+                };
+                lines.push(Line::from(format!("  {:<16}{}", name, value)));
+            }
+
+            lines.push(Line::from(""));
+            lines.push(Line::from("Layers").bold());
+            let config = manifest.config.iter().map(|layer| ("config", layer));
+            let layers = manifest.layers.iter().map(|layer| {
+                let kind = layer
+                    .media_type
+                    .rsplit_once('.')
+                    .map_or(layer.media_type.as_str(), |(_, kind)| kind);
+                (kind, layer)
+            });
+            for (kind, layer) in config.chain(layers) {
+                let digest = strip_digest(&layer.digest);
+                let shared = self
+                    .installed
+                    .iter()
+                    .any(|m| m.name != model.name && m.blobs.iter().any(|(blob, _)| *blob == digest));
+                lines.push(Line::from(format!(
+                    "  {:<12}{:<16}{:>10}{}",
+                    kind,
+                    &digest[..12.min(digest.len())],
+                    format_size(layer.size),
+                    if shared { "  shared" } else { "" }
+                )));
+            }
+        }
+
+        Detail {
+            title: model.name.clone(),
+            lines,
+            scroll: 0,
+        }
+    }
+
+    /// Marks or unmarks the selected tag. Deleted models have nothing left to delete.
+    fn toggle_mark(&mut self) {
+        let Some(path) = self.selected().and_then(|m| m.manifest.clone()) else { return };
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+        self.move_selection(1);
+    }
+
+    /// Starts deleting the marked tags, or the selected one when none are marked,
+    /// leaving out whatever the server has loaded.
+    fn start_delete(&mut self) {
+        let paths: HashSet<PathBuf> = if self.marked.is_empty() {
+            self.selected().and_then(|m| m.manifest.clone()).into_iter().collect()
+        } else {
+            self.marked.clone()
+        };
+        if paths.is_empty() {
+            return;
+        }
+
+        let mut warnings = Vec::new();
+        let loaded_digests = match prune::loaded_digests(&self.installed) {
+            Ok(Some(digests)) => digests,
+            Ok(None) => {
+                warnings.push(prune::NO_SERVER.to_string());
+                HashSet::new()
+            }
+            Err(err) => {
+                self.message = Some(format!("Could not check which models are loaded: {:#}", err));
+                return;
+            }
+        };
+        let (loaded, selected): (Vec<_>, Vec<_>) = self
+            .installed
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| paths.contains(&m.path))
+            .partition(|(_, m)| loaded_digests.contains(&m.digest));
+        let mut loaded: Vec<String> = loaded.into_iter().map(|(_, m)| m.name.clone()).collect();
+        loaded.sort();
+
+        let selected: HashSet<usize> = selected.into_iter().map(|(i, _)| i).collect();
+        let freed = prune::reclaimable(&self.installed, &selected);
+        let mut deleting: Vec<_> = selected.iter().map(|&i| &self.installed[i]).collect();
+        deleting.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.path.cmp(&b.path)));
+        warnings.extend(adapter::orphaned(&self.installed, &deleting));
+        let models = deleting
+            .iter()
+            .map(|m| {
+                let label = if self.installed.iter().filter(|other| other.name == m.name).count() > 1 {
+                    format!("{} ({})", m.name, m.models_dir().display())
+                } else {
+                    m.name.clone()
+                };
+                (m.path.clone(), label)
+            })
+            .collect();
+
+        self.pending = Some(PendingDelete { models, loaded, warnings, freed, input: String::new() });
+    }
+
+    fn finish_delete(&mut self, pending: PendingDelete) {
+        let models: Vec<_> = pending
+            .models
+            .iter()
+            .filter_map(|(path, _)| self.installed.iter().find(|m| m.path == *path))
+            .collect();
+        self.message = Some(match prune::delete_models(&get_model_dir(), &models) {
+            Ok(deleted) => format!("Deleted {}, freed {}", deleted.names.join(", "), format_size(deleted.freed)),
+            Err(err) => format!("Deletion failed: {:#}", err),
+        });
+        self.marked.clear();
+        self.refresh();
+    }
+
+    fn move_selection(&mut self, by: isize) {
+        let len = self.rows(self.pane).len();
+        if len == 0 {
+            return;
+        }
+        let current = self.tables[self.pane].selected().unwrap_or(0) as isize;
+        let next = (current + by).clamp(0, len as isize - 1);
+        self.tables[self.pane].select(Some(next as usize));
+    }
+}
+
+/// Matches `pattern` against `text` the way fzf does: every pattern character has to
+/// appear in order, ignoring case, but not necessarily next to each other. Returns a
+/// score that favours runs of consecutive characters and matches at the start of a
+/// name part, along with the matched character positions.
+fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let text: Vec<char> = text.chars().collect();
+    let mut positions: Vec<usize> = Vec::new();
+    let mut score = 0;
+    let mut next = 0;
+
+    for wanted in pattern.chars().flat_map(char::to_lowercase) {
+        let found = (next..text.len()).find(|&i| text[i].to_lowercase().eq([wanted]))?;
+        score += 16;
+        if found == 0 || matches!(text[found - 1], '/' | ':' | '-' | '_' | '.') {
+            score += 8;
+        }
+        match positions.last() {
+            Some(&last) if found == last + 1 => score += 12,
+            Some(&last) => score -= (found - last - 1).min(10) as i64,
+            None => {}
+        }
+        positions.push(found);
+        next = found + 1;
+    }
+
+    Some((score, positions))
+}
+
+/// Draws a day-per-character bar chart scaled to the busiest day; idle days stay blank.
+fn sparkline(series: Option<&Vec<u64>>) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let Some(series) = series else {
+        return " ".repeat(SPARKLINE_DAYS);
+    };
+    let max = series.iter().copied().max().unwrap_or(0).max(1);
+    series
+        .iter()
+        .map(|&count| match count {
+            0 => ' ',
+            _ => BARS[((count * 8 - 1) / max).min(7) as usize],
+        })
+        .collect()
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [tabs_area, table_area, detail_area, help_area] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Min(3),
+        Constraint::Length(5),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let titles = (0..PANES.len()).map(|pane| format!(" {} ({}) ", PANES[pane].1, app.rows(pane).len()));
+    let tabs = Tabs::new(titles)
+        .select(app.pane)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::new().title(format!(
+            "omar    scanned {}",
+            app.scanned_at.format("%Y-%m-%d %H:%M:%S")
+        )));
+    frame.render_widget(tabs, tabs_area);
+
+    let status = PANES[app.pane].0;
+    let rows: Vec<Row> = app
+        .rows(app.pane)
+        .into_iter()
+        .map(|m| {
+            let last_used = m.last_used.map_or(String::new(), |t| t.format("%Y-%m-%d").to_string());
+            let marked = m.manifest.as_ref().is_some_and(|path| app.marked.contains(path));
+            let marker = if marked { "* " } else { "  " };
+            let matched = fuzzy_match(&app.filter, &m.name).map(|(_, positions)| positions).unwrap_or_default();
+            let mut name = vec![Span::raw(marker)];
+            name.extend(m.name.chars().enumerate().map(|(i, c)| {
+                if matched.contains(&i) {
+                    Span::styled(c.to_string(), Style::new().yellow().bold())
+                } else {
+                    Span::raw(c.to_string())
+                }
+            }));
+            let mut cells = vec![Cell::from(Line::from(name))];
+            if status != Status::Unlogged {
+                cells.push(Cell::from(last_used));
+                cells.push(Cell::from(Line::from(m.usage_count.to_string()).right_aligned()));
+                cells.push(Cell::from(sparkline(app.daily.get(&m.digest))).cyan());
+            }
+            if status != Status::Deleted {
+                cells.push(Cell::from(Line::from(format_size(m.size)).right_aligned()));
+            }
+            Row::new(cells)
+        })
+        .collect();
+
+    let (header, widths): (Vec<&str>, Vec<Constraint>) = match status {
+        Status::Active => (
+            vec!["Model", "Last Used", "Usage Count", "Last 30 Days", "Size"],
+            vec![
+                Constraint::Fill(1),
+                Constraint::Length(11),
+                Constraint::Length(13),
+                Constraint::Length(SPARKLINE_DAYS as u16),
+                Constraint::Length(9),
+            ],
+        ),
+        Status::Unlogged => (vec!["Model", "Size"], vec![Constraint::Fill(1), Constraint::Length(9)]),
+        Status::Deleted => (
+            vec!["Model", "Last Used", "Usage Count", "Last 30 Days"],
+            vec![
+                Constraint::Fill(1),
+                Constraint::Length(11),
+                Constraint::Length(13),
+                Constraint::Length(SPARKLINE_DAYS as u16),
+            ],
+        ),
+    };
+    let header = header.into_iter().map(|title| {
+        let title = match app.sort {
+            Some(column) if column.header() == title => {
+                format!("{} {}", title, if app.descending { "▼" } else { "▲" })
+            }
+            _ => title.to_string(),
+        };
+        if title.starts_with("Model") { format!("  {}", title) } else { title }
+    });
+    let table = Table::new(rows, widths)
+        .header(Row::new(header).bold().underlined())
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered());
+    frame.render_stateful_widget(table, table_area, &mut app.tables[app.pane]);
+
+    let detail = match (&app.error, app.selected()) {
+        (Some(err), _) => vec![Line::from(format!("Error: {}", err)).red()],
+        (None, Some(model)) => {
+            let shared: Vec<_> = app
+                .models
+                .iter()
+                .filter(|m| m.digest == model.digest && m.name != model.name && m.status != Status::Deleted)
+                .map(|m| m.name.as_str())
+                .collect();
+            vec![
+                Line::from(format!("Digest:      {}", model.digest)),
+                Line::from(format!(
+                    "Shared with: {}",
+                    if shared.is_empty() { "-".to_string() } else { shared.join(", ") }
+                )),
+                Line::from(format!(
+                    "Last used:   {}",
+                    model.last_used.map_or("never".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string())
+                )),
+            ]
+        }
+        (None, None) => vec![Line::from("No models in this section.")],
+    };
+    frame.render_widget(Paragraph::new(detail).block(Block::bordered().title("Details")), detail_area);
+
+    if let Some(detail) = &app.detail {
+        let area = frame.area().inner(Margin {
+            horizontal: frame.area().width / 10,
+            vertical: frame.area().height / 10,
+        });
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(detail.lines.clone())
+                .scroll((detail.scroll, 0))
+                .block(Block::bordered().title(format!(" {} ", detail.title))),
+            area,
+        );
+    }
+
+    if let Some(pending) = &app.pending {
+        let area = frame.area().inner(Margin {
+            horizontal: frame.area().width / 5,
+            vertical: frame.area().height / 4,
+        });
+        let mut lines: Vec<Line> = vec![Line::from(format!(
+            "Delete {} model(s), freeing {}:",
+            pending.models.len(),
+            format_size(pending.freed)
+        ))];
+        lines.extend(pending.models.iter().map(|(_, label)| Line::from(format!("  {}", label))));
+        if !pending.loaded.is_empty() {
+            lines.push(Line::from(format!("Skipping loaded: {}", pending.loaded.join(", "))).yellow());
+        }
+        for warning in &pending.warnings {
+            lines.push(Line::from(format!("Warning: {}", warning)).yellow());
+        }
+        lines.push(Line::from(""));
+        if pending.models.is_empty() {
+            lines.push(Line::from("Nothing to delete. Press esc."));
+        } else {
+            lines.push(Line::from(format!("Type '{}' to confirm: {}_", CONFIRM_WORD, pending.input)).bold());
+        }
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Delete ").red()), area);
+    }
+
+    let help = if app.pending.is_some() {
+        Line::from("enter confirm  esc cancel").dim()
+    } else if let Some(message) = &app.message {
+        Line::from(message.clone()).bold()
+    } else if app.detail.is_some() {
+        Line::from("esc/enter close  ↑↓ scroll").dim()
+    } else if app.typing {
+        Line::from(format!("/{}_", app.filter))
+    } else if !app.filter.is_empty() {
+        Line::from(format!("filter: {}    / edit  esc clear  s/S sort  q quit", app.filter)).dim()
+    } else {
+        Line::from("q quit  tab/←→ section  ↑↓ move  enter details  space mark  d delete  s/S sort  / filter  r refresh").dim()
+    };
+    frame.render_widget(Paragraph::new(help), help_area);
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, refresh: Duration, db: Option<PathBuf>) -> Result<()> {
+    let mut app = App::new(db);
+    app.refresh();
+    let mut last_refresh = Instant::now();
+
+    loop {
+        terminal.draw(|frame| draw(frame, &mut app))?;
+
+        let timeout = refresh.saturating_sub(last_refresh.elapsed());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                app.message = None;
+                if let Some(pending) = &mut app.pending {
+                    match key.code {
+                        KeyCode::Esc => app.pending = None,
+                        KeyCode::Enter => {
+                            let pending = app.pending.take().unwrap();
+                            if pending.input == CONFIRM_WORD && !pending.models.is_empty() {
+                                app.finish_delete(pending);
+                                // Deletion reports progress on stdout, so repaint everything.
+                                terminal.clear()?;
+                            } else {
+                                app.message = Some("Deletion cancelled".to_string());
+                            }
+                        }
+                        KeyCode::Backspace => {
+                            pending.input.pop();
+                        }
+                        KeyCode::Char(c) => pending.input.push(c),
+                        _ => {}
+                    }
+                    continue;
+                }
+                if let Some(detail) = &mut app.detail {
+                    match key.code {
+                        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => app.detail = None,
+                        KeyCode::Down | KeyCode::Char('j') => detail.scroll = detail.scroll.saturating_add(1),
+                        KeyCode::Up | KeyCode::Char('k') => detail.scroll = detail.scroll.saturating_sub(1),
+                        _ => {}
+                    }
+                    continue;
+                }
+                if app.typing {
+                    match key.code {
+                        KeyCode::Enter => app.typing = false,
+                        KeyCode::Esc => {
+                            app.typing = false;
+                            app.filter.clear();
+                        }
+                        KeyCode::Backspace => {
+                            app.filter.pop();
+                        }
+                        KeyCode::Char(c) => app.filter.push(c),
+                        _ => {}
+                    }
+                    app.clamp_selection();
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Esc if !app.filter.is_empty() => {
+                        app.filter.clear();
+                        app.clamp_selection();
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('/') => app.typing = true,
+                    KeyCode::Char(' ') => app.toggle_mark(),
+                    KeyCode::Char('d') | KeyCode::Delete => app.start_delete(),
+                    KeyCode::Enter => app.detail = app.selected().map(|model| app.detail(model)),
+                    KeyCode::Char('s') => {
+                        app.sort = Some(app.sort.map_or(SortColumn::Name, SortColumn::next));
+                        app.clamp_selection();
+                    }
+                    KeyCode::Char('S') => app.descending = !app.descending,
+                    KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => {
+                        app.pane = (app.pane + 1) % PANES.len();
+                    }
+                    KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => {
+                        app.pane = (app.pane + PANES.len() - 1) % PANES.len();
+                    }
+                    KeyCode::Char(c @ '1'..='3') => app.pane = c as usize - '1' as usize,
+                    KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+                    KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+                    KeyCode::PageDown => app.move_selection(10),
+                    KeyCode::PageUp => app.move_selection(-10),
+                    KeyCode::Home | KeyCode::Char('g') => app.move_selection(isize::MIN / 2),
+                    KeyCode::End | KeyCode::Char('G') => app.move_selection(isize::MAX / 2),
+                    KeyCode::Char('r') => {
+                        app.refresh();
+                        last_refresh = Instant::now();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if last_refresh.elapsed() >= refresh {
+            app.refresh();
+            last_refresh = Instant::now();
+        }
+    }
+}
+
+/// Runs the full-screen report until the user quits, rescanning every `refresh` seconds.
+/// Loads are recorded in the history database `db` as they're found, when there is one.
+pub fn run(refresh: u64, db: Option<PathBuf>) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, Duration::from_secs(refresh.max(1)), db);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// The same tag in two models directories: deleting one leaves the other, and
+    /// the blobs it still uses.
+    #[test]
+    fn delete_removes_only_the_selected_directorys_tag() {
+        let (kept_dir, kept) = prune::test_models_dir("tui", &[("llama3:latest", &["config", "weights"])]);
+        let (deleted_dir, deleted) = prune::test_models_dir("tui", &[("llama3:latest", &["config", "weights"])]);
+        let mut app = App::new(None);
+        app.installed = kept.into_iter().chain(deleted).collect();
+        let path = app.installed[1].path.clone();
+
+        app.finish_delete(PendingDelete {
+            models: vec![(path.clone(), "llama3:latest".to_string())],
+            loaded: Vec::new(),
+            warnings: Vec::new(),
+            freed: 0,
+            input: CONFIRM_WORD.to_string(),
+        });
+
+        assert!(!path.exists());
+        assert!(!deleted_dir.join("blobs").join("sha256-weights").exists());
+        assert!(kept_dir.join("manifests").join("registry.ollama.ai").join("library").join("llama3").join("latest").exists());
+        assert!(kept_dir.join("blobs").join("sha256-weights").exists());
+        assert_eq!(app.message.as_deref(), Some("Deleted llama3:latest, freed 0.0 MB"));
+        let _ = fs::remove_dir_all(kept_dir);
+        let _ = fs::remove_dir_all(deleted_dir);
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use crate::{
+    format_size, index_by_digest, notify, scan_models_in,
+    table::{self, Align},
+    InstalledModel, ModelUsage, Warning,
+};
+
+/// One user's Ollama models directory. The system service's shows up as the
+/// `ollama` user's, since that's whose home it's in.
+pub struct Store {
+    pub user: String,
+    pub dir: PathBuf,
+}
+
+/// Users and home directories from `/etc/passwd`, followed by the folders under
+/// `/home` and `/Users` for users it doesn't list, such as on macOS.
+fn homes() -> Vec<(String, PathBuf)> {
+    let mut homes: Vec<(String, PathBuf)> = fs::read_to_string("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            Some((fields.first()?.to_string(), PathBuf::from(fields.get(5)?)))
+        })
+        .collect();
+    for parent in ["/home", "/Users"] {
+        for entry in fs::read_dir(parent).into_iter().flatten().filter_map(Result::ok) {
+            homes.push((entry.file_name().to_string_lossy().into_owned(), entry.path()));
+        }
+    }
+    homes
+}
+
+/// Every user's `~/.ollama/models` that has manifests. Reading other users' homes
+/// usually takes root.
+pub fn stores() -> Vec<Store> {
+    let mut seen = HashSet::new();
+    homes()
+        .into_iter()
+        .map(|(user, home)| Store { user, dir: home.join(".ollama").join("models") })
+        .filter(|store| store.dir.join("manifests").is_dir() && seen.insert(store.dir.clone()))
+        .collect()
+}
+
+/// Scans every store into one list. A store that can't be scanned becomes a
+/// warning rather than an error, so the other users still show.
+pub fn scan(stores: &[Store]) -> (Vec<InstalledModel>, Vec<Warning>) {
+    let mut installed = Vec::new();
+    let mut warnings = Vec::new();
+    for store in stores {
+        match scan_models_in(store.dir.clone()) {
+            Ok((models, skipped)) => {
+                installed.extend(models);
+                warnings.extend(skipped);
+            }
+            Err(err) => warnings.push(Warning { path: store.dir.clone(), reason: err.to_string() }),
+        }
+    }
+    (installed, warnings)
+}
+
+/// Prints each user's models with their usage, then what each user keeps and how
+/// much of it hasn't been used within `stale_days`, largest first. Loads come from
+/// the same logs and sources as the report, so a model several users have shows
+/// the same loads for each of them.
+pub fn run(
+    stores: &[Store],
+    installed: &[InstalledModel],
+    model_usage: &HashMap<String, ModelUsage>,
+    stale_days: i64,
+) -> Result<()> {
+    if stores.is_empty() {
+        println!("\nNo user has an ~/.ollama/models directory omar can read; run as root to see everyone's.\n");
+        return Ok(());
+    }
+
+    let owned = |store: &Store| -> Vec<InstalledModel> {
+        installed.iter().filter(|m| m.path.starts_with(&store.dir)).cloned().collect()
+    };
+    let mut users: Vec<_> = stores
+        .iter()
+        .map(|store| {
+            let models = owned(store);
+            let index = index_by_digest(&models);
+            let total: u64 = index.values().map(|(_, size)| size).sum();
+            (store, models, index, total)
+        })
+        .collect();
+    users.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.0.user.cmp(&b.0.user)));
+
+    let mut subtotals = Vec::new();
+    for (store, models, index, total) in &users {
+        println!("\n{} ({}):", store.user, store.dir.display());
+        let mut rows: Vec<_> = index
+            .iter()
+            .map(|(digest, (tags, size))| {
+                let usage = model_usage.get(digest);
+                (
+                    *size,
+                    vec![
+                        tags.join(", "),
+                        usage.map_or("never".to_string(), |u| u.last_used.format("%Y-%m-%d").to_string()),
+                        usage.map_or(0, |u| u.usage_count).to_string(),
+                        format_size(*size),
+                    ],
+                )
+            })
+            .collect();
+        rows.sort_by(|(a, x), (b, y)| b.cmp(a).then_with(|| x[0].cmp(&y[0])));
+        table::print(
+            &[
+                ("Model", Align::Left),
+                ("Last Used", Align::Left),
+                ("Usage Count", Align::Right),
+                ("Size", Align::Right),
+            ],
+            &rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>(),
+        );
+
+        let stale = notify::stale_models(models, index, model_usage, stale_days);
+        subtotals.push(vec![
+            store.user.clone(),
+            index.len().to_string(),
+            format_size(*total),
+            format_size(stale.unused_bytes),
+        ]);
+    }
+
+    println!("\nPer user:");
+    let unused = format!("Unused {}+ Days", stale_days);
+    table::print(
+        &[
+            ("User", Align::Left),
+            ("Models", Align::Right),
+            ("Size", Align::Right),
+            (unused.as_str(), Align::Right),
+        ],
+        &subtotals,
+    );
+    let distinct: HashMap<&str, u64> = installed.iter().map(|m| (m.digest.as_str(), m.size)).collect();
+    println!(
+        "\n{} in total, {} of it distinct weights\n",
+        format_size(users.iter().map(|u| u.3).sum()),
+        format_size(distinct.values().sum())
+    );
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::debug;
+
+use crate::{report::ReportRow, scan_models_in};
+
+/// The report section for the other side's models: Windows' seen from WSL, and
+/// WSL's seen from Windows.
+pub const SECTION: &str = if cfg!(windows) { "WSL Models" } else { "Windows Models" };
+
+/// Whether omar is running inside a WSL distribution.
+pub fn detected() -> bool {
+    env::var_os("WSL_DISTRO_NAME").is_some()
+        || fs::read_to_string("/proc/sys/kernel/osrelease").is_ok_and(|release| release.to_lowercase().contains("microsoft"))
+}
+
+/// The `.ollama/models` folders under `home`'s subfolders that have manifests.
+fn stores_under(home: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(home) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path().join(".ollama").join("models"))
+        .filter(|dir| dir.join("manifests").is_dir())
+        .collect()
+}
+
+/// The distributions WSL has installed. `wsl.exe` lists them in UTF-16.
+fn distributions() -> Vec<String> {
+    let Ok(output) = Command::new("wsl.exe").args(["--list", "--quiet"]).output() else {
+        return Vec::new();
+    };
+    let units: Vec<u16> = output.stdout.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+    String::from_utf16_lossy(&units)
+        .lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\0').to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Ollama model stores on the other side of WSL: the Windows users' under
+/// `/mnt/c/Users` from inside WSL, and each distribution's, for the service and
+/// for every user, from Windows. Empty anywhere else.
+pub fn other_model_dirs() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        let mut dirs = Vec::new();
+        for distro in distributions() {
+            let root = PathBuf::from(format!(r"\\wsl.localhost\{}", distro));
+            let service = root.join("usr").join("share").join("ollama").join(".ollama").join("models");
+            if service.join("manifests").is_dir() {
+                dirs.push(service);
+            }
+            dirs.extend(stores_under(&root.join("home")));
+            let admin = root.join("root").join(".ollama").join("models");
+            if admin.join("manifests").is_dir() {
+                dirs.push(admin);
+            }
+        }
+        dirs
+    } else if detected() {
+        stores_under(Path::new("/mnt/c/Users"))
+    } else {
+        Vec::new()
+    }
+}
+
+/// The tags in another models directory, by size, as rows with no usage since
+/// that side's logs aren't read.
+pub fn scan(dir: &Path) -> Result<Vec<ReportRow>> {
+    let (models, warnings) = scan_models_in(dir.to_path_buf())?;
+    for warning in &warnings {
+        debug!(%warning, "skipped a manifest on the other side of WSL");
+    }
+    Ok(models
+        .into_iter()
+        .map(|model| ReportRow {
+            name: model.name,
+            tags: Vec::new(),
+            digest: model.digest,
+            last_used: None,
+            usage_count: 0,
+            days_used: 0,
+            window_count: None,
+            all_time: None,
+            trend: None,
+            embeddings: None,
+            tool_requests: None,
+            structured_requests: None,
+            clients: None,
+            failures: None,
+            oom_failures: None,
+            kind: None,
+            license: None,
+            created: None,
+            pulled: None,
+            size: model.size,
+        })
+        .collect())
+}
@@ -0,0 +1,170 @@
+//! Runs omar against the home directory in `tests/fixtures/home`, which has three
+//! models installed and a server log that loads two of them and a deleted one.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const LLAMA3: &str = "1a9a388336073f25f143cdd39abe37b306a367d031d6c04a79bbb545232ae113";
+const NOMIC: &str = "970aa74c0a90ef7482477cf803618e776e173c007bf957f635f1015bfcfef0e6";
+const QWEN2: &str = "6a0746a1ec1aef3e7ec53868f220ff6e389f6f8ef87a01d77c96807de94ca2aa";
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+/// A copy of the fixture home, so the manifest cache omar writes stays out of the
+/// tree. Each call gets its own, since tests run in parallel.
+fn fixture_home() -> PathBuf {
+    static HOMES: AtomicUsize = AtomicUsize::new(0);
+    let home = std::env::temp_dir().join(format!(
+        "omar-fixture-{}-{}",
+        std::process::id(),
+        HOMES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&home);
+    copy_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("home"), &home);
+    home
+}
+
+/// The report on the fixture home in `format`.
+fn report(format: &str) -> String {
+    let home = fixture_home();
+    let output = Command::new(env!("CARGO_BIN_EXE_ollama-model-report"))
+        .arg("--fixture")
+        .arg(&home)
+        .args(["--no-history", "--format", format])
+        .output()
+        .unwrap();
+    let _ = fs::remove_dir_all(&home);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// The CSV row for `digest`, by column name.
+fn csv_row(csv: &str, digest: &str) -> Vec<(String, String)> {
+    let mut lines = csv.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    let row = lines.find(|line| line.split(',').nth(2) == Some(digest)).unwrap();
+    header.iter().map(|h| h.to_string()).zip(row.split(',').map(str::to_string)).collect()
+}
+
+fn field<'a>(row: &'a [(String, String)], name: &str) -> &'a str {
+    &row.iter().find(|(column, _)| column == name).unwrap().1
+}
+
+#[test]
+fn csv_counts_embedding_requests() {
+    let csv = report("csv");
+    assert_eq!(field(&csv_row(&csv, LLAMA3), "embeddings"), "0");
+    assert_eq!(field(&csv_row(&csv, NOMIC), "embeddings"), "2");
+    assert_eq!(field(&csv_row(&csv, QWEN2), "embeddings"), "0");
+}
+
+#[test]
+fn csv_counts_tool_requests() {
+    let csv = report("csv");
+    assert_eq!(field(&csv_row(&csv, LLAMA3), "tool_requests"), "1");
+    assert_eq!(field(&csv_row(&csv, NOMIC), "tool_requests"), "0");
+    assert_eq!(field(&csv_row(&csv, QWEN2), "tool_requests"), "0");
+}
+
+#[test]
+fn csv_counts_structured_requests() {
+    let csv = report("csv");
+    assert_eq!(field(&csv_row(&csv, LLAMA3), "structured_requests"), "1");
+    assert_eq!(field(&csv_row(&csv, NOMIC), "structured_requests"), "0");
+    assert_eq!(field(&csv_row(&csv, QWEN2), "structured_requests"), "0");
+}
+
+#[test]
+fn csv_counts_clients() {
+    let csv = report("csv");
+    assert_eq!(field(&csv_row(&csv, LLAMA3), "clients"), "1");
+    assert_eq!(field(&csv_row(&csv, NOMIC), "clients"), "2");
+    assert_eq!(field(&csv_row(&csv, QWEN2), "clients"), "0");
+}
+
+#[test]
+fn csv_counts_failures_and_oom() {
+    let csv = report("csv");
+    let llama3 = csv_row(&csv, LLAMA3);
+    assert_eq!((field(&llama3, "failures"), field(&llama3, "oom_failures")), ("1", "1"));
+    let qwen2 = csv_row(&csv, QWEN2);
+    assert_eq!((field(&qwen2, "failures"), field(&qwen2, "oom_failures")), ("0", "0"));
+}
+
+#[test]
+fn json_has_every_section() {
+    let json: serde_json::Value = serde_json::from_str(&report("json")).unwrap();
+    assert_eq!(json["schema_version"], 1);
+    assert_eq!(json["active"].as_array().unwrap().len(), 2);
+    assert_eq!(json["unlogged"][0]["name"], "qwen2:7b");
+    assert_eq!(json["deleted"][0]["name"], "00000000...-deleted");
+    let llama3 = json["active"].as_array().unwrap().iter().find(|m| m["digest"] == LLAMA3).unwrap();
+    assert_eq!(llama3["oom_failures"], 1);
+}
+
+#[test]
+fn table_has_a_section_per_status() {
+    let table = report("table");
+    for title in ["Active Models:", "Unlogged Models:", "Deleted Models:"] {
+        assert!(table.contains(title), "{}", title);
+    }
+    assert!(table.contains("1 (1 OOM)"));
+    assert!(table.contains("00000000...-deleted"));
+}
+
+#[test]
+fn markdown_has_a_table_per_section() {
+    let markdown = report("markdown");
+    assert!(markdown.contains("| llama3:latest"));
+    assert!(markdown.contains("| qwen2:7b"));
+}
+
+/// With no log files, a Linux report reads the loads from the journal instead. A
+/// stand-in `journalctl` early on the `PATH` prints the fixture log.
+#[cfg(target_os = "linux")]
+#[test]
+fn journal_is_read_when_there_are_no_log_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home = fixture_home();
+    let bin = home.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let log = home.join(".ollama").join("logs").join("server.log");
+    let journal = home.join("journal.log");
+    fs::rename(&log, &journal).unwrap();
+    let journalctl = bin.join("journalctl");
+    fs::write(&journalctl, format!("#!/bin/sh\ncat '{}'\n", journal.display())).unwrap();
+    fs::set_permissions(&journalctl, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+    let output = Command::new(env!("CARGO_BIN_EXE_ollama-model-report"))
+        .arg("--fixture")
+        .arg(&home)
+        .args(["--no-history", "--format", "csv"])
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    let _ = fs::remove_dir_all(&home);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let csv = String::from_utf8(output.stdout).unwrap();
+    let llama3 = csv_row(&csv, LLAMA3);
+    assert_eq!(field(&llama3, "section"), "active");
+    assert_eq!(field(&llama3, "usage_count"), "1");
+    assert_eq!(field(&llama3, "failures"), "1");
+}